@@ -0,0 +1,21 @@
+//! Generates the magic bitboard tables into `OUT_DIR/magic_tables.rs`, which
+//! `chess::moves_gen::magic_bitboards` includes.
+
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+#[path = "src/chess/moves_gen/magic_gen/generator.rs"]
+mod generator;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/chess/moves_gen/magic_gen/generator.rs");
+    println!("cargo:rerun-if-changed=src/chess/prng.rs");
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    let path = Path::new(&out_dir).join("magic_tables.rs");
+    let mut out = BufWriter::new(File::create(&path).expect("cannot create magic_tables.rs"));
+    generator::write_tables(&mut out).expect("cannot write magic_tables.rs");
+}
@@ -0,0 +1,215 @@
+/* C interface of the chessengine library, built with `--features ffi`.
+ * Generated from src/ffi.rs with
+ *   cbindgen --config cbindgen.toml --output include/chessengine.h
+ * and checked by tests/ffi.rs, so edit the Rust side and regenerate.
+ *
+ * Moves are passed in their packed encoding, where 0 is never a legal move.
+ * Functions returning text follow snprintf: they write at most size - 1
+ * bytes plus a terminating NUL and return the full length.
+ */
+
+#ifndef CHESSENGINE_H
+#define CHESSENGINE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Returned on success.
+ */
+#define CHESS_OK 0
+
+/**
+ * Returned on failure.
+ */
+#define CHESS_ERROR -1
+
+/**
+ * A position together with the hashes of every position reached so far,
+ * the current one included, for repetition detection.
+ */
+typedef struct ChessBoard ChessBoard;
+
+/**
+ * An engine and its transposition table.
+ */
+typedef struct ChessEngine ChessEngine;
+
+/**
+ * A limit of 0 does not restrict the search. At least one must be set.
+ */
+typedef struct ChessSearchLimits {
+  /**
+   * Deepest iteration to run, capped at 64.
+   */
+  uint32_t depth;
+  /**
+   * Time budget in milliseconds.
+   */
+  uint64_t time_ms;
+  /**
+   * Node budget.
+   */
+  uint64_t nodes;
+} ChessSearchLimits;
+
+/**
+ * Outcome of `chess_engine_search`.
+ */
+typedef struct ChessSearchResult {
+  /**
+   * The best move in its packed encoding.
+   */
+  uint32_t best_move;
+  /**
+   * Centipawns from the side to move's point of view.
+   */
+  int32_t score;
+  /**
+   * Nodes visited.
+   */
+  uint64_t nodes;
+  /**
+   * Wall-clock time of the search.
+   */
+  uint64_t time_ms;
+} ChessSearchResult;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * The initial position.
+ */
+ChessBoard *chess_board_new(void);
+
+/**
+ * Parses a FEN string. Returns null when it is invalid, including positions
+ * without exactly one king per side or with the side not to move in check.
+ *
+ * # Safety
+ *
+ * `fen` must be null or a NUL-terminated string.
+ */
+ChessBoard *chess_board_from_fen(const char *fen);
+
+/**
+ * # Safety
+ *
+ * `board` must be null or a board from this library that was not freed yet.
+ */
+void chess_board_free(ChessBoard *board);
+
+/**
+ * Writes the FEN of the position.
+ *
+ * # Safety
+ *
+ * `board` must be a live board and `buffer` null or valid for `size` bytes.
+ */
+size_t chess_board_fen(const ChessBoard *board, char *buffer, size_t size);
+
+/**
+ * 0 when White is to move, 1 when Black is.
+ *
+ * # Safety
+ *
+ * `board` must be a live board.
+ */
+int32_t chess_board_side_to_move(const ChessBoard *board);
+
+/**
+ * Writes up to `capacity` legal moves into `moves` and returns how many
+ * there are in total.
+ *
+ * # Safety
+ *
+ * `board` must be a live board and `moves` null or valid for `capacity`
+ * elements.
+ */
+size_t chess_board_legal_moves(const ChessBoard *board, uint32_t *moves, size_t capacity);
+
+/**
+ * Resolves a move in coordinate notation (`e2e4`, `e7e8q`) or SAN (`Nf3`).
+ * Returns 0 when it is not a legal move in the position.
+ *
+ * # Safety
+ *
+ * `board` must be a live board and `text` null or a NUL-terminated string.
+ */
+uint32_t chess_board_parse_move(const ChessBoard *board, const char *text);
+
+/**
+ * Plays a legal move. Returns `CHESS_ERROR` and leaves the board unchanged
+ * when the move is not legal.
+ *
+ * # Safety
+ *
+ * `board` must be a live board.
+ */
+int32_t chess_board_make_move(ChessBoard *board, uint32_t mv);
+
+/**
+ * 0 ongoing, 1 checkmate, 2 stalemate, 3 threefold repetition, 4 fifty-move
+ * rule, 5 insufficient material, `CHESS_ERROR` for a null board.
+ *
+ * # Safety
+ *
+ * `board` must be a live board.
+ */
+int32_t chess_board_status(const ChessBoard *board);
+
+/**
+ * Writes a move in coordinate notation.
+ *
+ * # Safety
+ *
+ * `buffer` must be null or valid for `size` bytes.
+ */
+size_t chess_move_to_uci(uint32_t mv, char *buffer, size_t size);
+
+/**
+ * Writes a legal move of the position in SAN. Writes an empty string and
+ * returns 0 when the move is not legal.
+ *
+ * # Safety
+ *
+ * `board` must be a live board and `buffer` null or valid for `size` bytes.
+ */
+size_t chess_board_move_to_san(const ChessBoard *board, uint32_t mv, char *buffer, size_t size);
+
+/**
+ * An engine with a transposition table of `hash_mb` megabytes. Returns null
+ * when `hash_mb` is 0.
+ */
+ChessEngine *chess_engine_new(size_t hash_mb);
+
+/**
+ * # Safety
+ *
+ * `engine` must be null or an engine from this library that was not freed
+ * yet.
+ */
+void chess_engine_free(ChessEngine *engine);
+
+/**
+ * Searches the position within `limits`. Returns `CHESS_ERROR` when
+ * `limits` is null or sets no limit, as the search would not end, and when
+ * the position has no legal moves.
+ *
+ * # Safety
+ *
+ * `engine` and `board` must be live, `limits` null or valid, and `result`
+ * valid for writing.
+ */
+int32_t chess_engine_search(ChessEngine *engine,
+                            const ChessBoard *board,
+                            const ChessSearchLimits *limits,
+                            ChessSearchResult *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHESSENGINE_H */
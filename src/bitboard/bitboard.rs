@@ -1,107 +1,429 @@
+use std::fmt;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Shl, Shr};
+
+use crate::chess::moves_gen::lookup;
+use crate::chess::square::{File, Rank, Square};
+use crate::chess::table::Color;
+
+/// A set of squares, one bit per square in `Square` index order. Convert to
+/// and from the raw `u64` with `From`.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(transparent)]
-pub struct Bitboard(pub u64);
+pub struct Bitboard(u64);
 
 impl Bitboard {
+    /// The squares whose bits are set in `value`.
     #[inline(always)]
     pub const fn new(value: u64) -> Self {
         Bitboard(value)
     }
 
+    /// No squares.
     #[inline(always)]
-    pub const fn new_from_index(value: u8) -> Self {
-        Bitboard(1 << value)
+    pub const fn empty() -> Self {
+        Bitboard(0)
     }
 
+    /// All 64 squares.
     #[inline(always)]
-    #[allow(dead_code)]
-    pub const fn empty() -> Self {
-        Bitboard(0)
+    pub const fn full() -> Self {
+        Bitboard(!0)
     }
 
+    /// The raw bits.
     #[inline(always)]
     pub const fn get_value(&self) -> u64 {
         self.0
     }
 
     #[inline(always)]
-    pub fn set_empty(&mut self) {
+    pub(crate) fn set_empty(&mut self) {
         self.0 = 0;
     }
 
     #[inline(always)]
-    pub fn set_bit(&mut self, index: u8) {
+    pub(crate) fn set_bit(&mut self, index: u8) {
         let mask: u64 = 1u64 << index;
 
         self.0 |= mask;
     }
 
+    /// Number of squares in the set.
     #[inline(always)]
     pub fn count_ones(&self) -> u64 {
         self.0.count_ones() as u64
     }
 
+    /// Whether no square is set.
     #[inline(always)]
-    pub fn lsb(&self) -> u32 {
-        self.0.trailing_zeros()
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
     }
 
+    /// Whether `square` is set.
     #[inline(always)]
-    pub fn pop_lsb(&mut self) -> Option<u32> {
+    pub const fn contains(&self, square: Square) -> bool {
+        self.0 & square.bit() != 0
+    }
+
+    /// Whether at least two squares are set.
+    #[inline(always)]
+    pub const fn more_than_one(&self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    /// The lowest set square, `None` when empty.
+    #[inline(always)]
+    pub const fn first_square(&self) -> Option<Square> {
         if self.0 == 0 {
             None
         } else {
-            let idx = self.0.trailing_zeros();
-            self.0 &= self.0 - 1;
-            Some(idx)
+            Some(Square::from_lsb(self.0))
+        }
+    }
+
+    // One step in each direction. Squares that would wrap around to the
+    // other side of the board are dropped.
+
+    /// One step towards the eighth rank.
+    #[inline(always)]
+    pub const fn north(self) -> Bitboard {
+        Bitboard(self.0 << 8)
+    }
+
+    /// One step towards the first rank.
+    #[inline(always)]
+    pub const fn south(self) -> Bitboard {
+        Bitboard(self.0 >> 8)
+    }
+
+    /// One step towards the h-file.
+    #[inline(always)]
+    pub const fn east(self) -> Bitboard {
+        Bitboard((self.0 << 1) & !FILE_A)
+    }
+
+    /// One step towards the a-file.
+    #[inline(always)]
+    pub const fn west(self) -> Bitboard {
+        Bitboard((self.0 >> 1) & !FILE_H)
+    }
+
+    /// One step towards h8.
+    #[inline(always)]
+    pub const fn north_east(self) -> Bitboard {
+        Bitboard((self.0 << 9) & !FILE_A)
+    }
+
+    /// One step towards a8.
+    #[inline(always)]
+    pub const fn north_west(self) -> Bitboard {
+        Bitboard((self.0 << 7) & !FILE_H)
+    }
+
+    /// One step towards h1.
+    #[inline(always)]
+    pub const fn south_east(self) -> Bitboard {
+        Bitboard((self.0 >> 7) & !FILE_A)
+    }
+
+    /// One step towards a1.
+    #[inline(always)]
+    pub const fn south_west(self) -> Bitboard {
+        Bitboard((self.0 >> 9) & !FILE_H)
+    }
+
+    /// One step towards the opponent of `color`.
+    #[inline(always)]
+    pub const fn forward(self, color: Color) -> Bitboard {
+        match color {
+            Color::White => self.north(),
+            Color::Black => self.south(),
         }
     }
 
+    /// The whole of `file`.
     #[inline(always)]
-    pub fn and(&self, other: Bitboard) -> Bitboard {
-        Bitboard(self.0 & other.0)
+    pub const fn file(file: File) -> Bitboard {
+        Bitboard(file.mask())
+    }
+
+    /// The whole of `rank`.
+    #[inline(always)]
+    pub const fn rank(rank: Rank) -> Bitboard {
+        Bitboard(rank.mask())
+    }
+
+    /// The files next to `file`, without `file` itself.
+    pub const fn adjacent_files(file: File) -> Bitboard {
+        let mask = Bitboard::file(file);
+        Bitboard(mask.east().0 | mask.west().0)
+    }
+
+    /// The a1-h8 direction diagonal through `square`.
+    pub const fn diagonal(square: Square) -> Bitboard {
+        let offset = square.file().index() as i32 - square.rank().index() as i32;
+        if offset >= 0 {
+            Bitboard(MAIN_DIAGONAL >> (8 * offset))
+        } else {
+            Bitboard(MAIN_DIAGONAL << (-8 * offset))
+        }
     }
 
+    /// The h1-a8 direction diagonal through `square`.
+    pub const fn anti_diagonal(square: Square) -> Bitboard {
+        let offset = square.file().index() as i32 + square.rank().index() as i32 - 7;
+        if offset >= 0 {
+            Bitboard(ANTI_DIAGONAL << (8 * offset))
+        } else {
+            Bitboard(ANTI_DIAGONAL >> (-8 * offset))
+        }
+    }
+
+    /// Squares strictly between `a` and `b`, empty when they are not aligned.
     #[inline(always)]
-    pub fn or(&self, other: Bitboard) -> Bitboard {
-        Bitboard(self.0 | other.0)
+    pub fn between(a: Square, b: Square) -> Bitboard {
+        lookup::between(a, b)
     }
 
+    /// The whole line through `a` and `b`, empty when they are not aligned.
     #[inline(always)]
-    pub fn xor(&self, other: Bitboard) -> Bitboard {
-        Bitboard(self.0 ^ other.0)
+    pub fn line(a: Square, b: Square) -> Bitboard {
+        lookup::line(a, b)
     }
+}
+
+const FILE_A: u64 = 0x0101010101010101;
+const FILE_H: u64 = 0x8080808080808080;
+const MAIN_DIAGONAL: u64 = 0x8040201008040201;
+const ANTI_DIAGONAL: u64 = 0x0102040810204080;
+
+/// Yields the set squares from a1 towards h8.
+pub struct Squares(u64);
+
+impl Iterator for Squares {
+    type Item = Square;
 
     #[inline(always)]
-    pub fn not(&self) -> Bitboard {
+    fn next(&mut self) -> Option<Square> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = Square::from_lsb(self.0);
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.0.count_ones() as usize;
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for Squares {}
+
+impl IntoIterator for Bitboard {
+    type Item = Square;
+    type IntoIter = Squares;
+
+    #[inline(always)]
+    fn into_iter(self) -> Squares {
+        Squares(self.0)
+    }
+}
+
+impl From<u64> for Bitboard {
+    #[inline(always)]
+    fn from(value: u64) -> Bitboard {
+        Bitboard(value)
+    }
+}
+
+impl From<Bitboard> for u64 {
+    #[inline(always)]
+    fn from(bb: Bitboard) -> u64 {
+        bb.0
+    }
+}
+
+impl From<Square> for Bitboard {
+    #[inline(always)]
+    fn from(square: Square) -> Bitboard {
+        Bitboard(square.bit())
+    }
+}
+
+macro_rules! bit_operator {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:tt) => {
+        impl $trait for Bitboard {
+            type Output = Bitboard;
+
+            #[inline(always)]
+            fn $method(self, rhs: Bitboard) -> Bitboard {
+                Bitboard(self.0 $op rhs.0)
+            }
+        }
+
+        impl $assign_trait for Bitboard {
+            #[inline(always)]
+            fn $assign_method(&mut self, rhs: Bitboard) {
+                self.0 = self.0 $op rhs.0;
+            }
+        }
+    };
+}
+
+bit_operator!(BitAnd, bitand, BitAndAssign, bitand_assign, &);
+bit_operator!(BitOr, bitor, BitOrAssign, bitor_assign, |);
+bit_operator!(BitXor, bitxor, BitXorAssign, bitxor_assign, ^);
+
+impl Not for Bitboard {
+    type Output = Bitboard;
+
+    #[inline(always)]
+    fn not(self) -> Bitboard {
         Bitboard(!self.0)
     }
+}
+
+impl Shl<u32> for Bitboard {
+    type Output = Bitboard;
 
     #[inline(always)]
-    pub fn iter_bits(self) -> impl Iterator<Item = Bitboard> {
-        let mut bb = self;
-        std::iter::from_fn(move || bb.pop_lsb().map(|idx| Bitboard::new_from_index(idx as u8)))
+    fn shl(self, rhs: u32) -> Bitboard {
+        Bitboard(self.0 << rhs)
     }
+}
 
-    #[allow(dead_code)]
-    pub fn to_string(&self) -> String {
-        format!("{:064b}", self.0)
+impl Shr<u32> for Bitboard {
+    type Output = Bitboard;
+
+    #[inline(always)]
+    fn shr(self, rhs: u32) -> Bitboard {
+        Bitboard(self.0 >> rhs)
     }
+}
 
-    #[allow(dead_code)]
-    pub fn to_formatted_string(&self) -> String {
-        let s = format!("{:064b}", self.0);
-        s.as_bytes()
-            .chunks(8)
-            .rev()
-            .map(|chunk| {
-                let mut part = String::new();
-                for &b in chunk.iter().rev() {
-                    part.push(b as char);
+/// An 8x8 grid with rank 8 on top, `1` for set squares and `.` for empty ones.
+impl fmt::Display for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for rank in Rank::ALL.into_iter().rev() {
+            let row: Vec<&str> = File::ALL
+                .into_iter()
+                .map(|file| {
+                    if self.contains(Square::new(file, rank)) {
+                        "1"
+                    } else {
+                        "."
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn squares(list: &[Square]) -> Bitboard {
+        list.iter()
+            .fold(Bitboard::empty(), |bb, &sq| bb | Bitboard::from(sq))
+    }
+
+    #[test]
+    fn shifts_drop_squares_that_would_wrap() {
+        let a_file = Bitboard::file(File::A);
+        let h_file = Bitboard::file(File::H);
+
+        assert_eq!(h_file.east(), Bitboard::empty());
+        assert_eq!(a_file.west(), Bitboard::empty());
+        assert_eq!(a_file.east(), Bitboard::file(File::B));
+        assert_eq!(h_file.west(), Bitboard::file(File::G));
+
+        assert!(h_file.north_east().is_empty());
+        assert!(h_file.south_east().is_empty());
+        assert!(a_file.north_west().is_empty());
+        assert!(a_file.south_west().is_empty());
+
+        let corners = squares(&[Square::A1, Square::H1, Square::A8, Square::H8]);
+        assert_eq!(corners.north_east(), Bitboard::from(Square::B2));
+        assert_eq!(corners.north_west(), Bitboard::from(Square::G2));
+        assert_eq!(corners.south_east(), Bitboard::from(Square::B7));
+        assert_eq!(corners.south_west(), Bitboard::from(Square::G7));
+        assert_eq!(corners.north(), squares(&[Square::A2, Square::H2]));
+        assert_eq!(corners.south(), squares(&[Square::A7, Square::H7]));
+    }
+
+    #[test]
+    fn forward_depends_on_the_color() {
+        let e4 = Bitboard::from(Square::E4);
+        assert_eq!(e4.forward(Color::White), Bitboard::from(Square::E5));
+        assert_eq!(e4.forward(Color::Black), Bitboard::from(Square::E3));
+    }
+
+    #[test]
+    fn diagonals_through_the_corners() {
+        let main = squares(&[
+            Square::A1,
+            Square::B2,
+            Square::C3,
+            Square::D4,
+            Square::E5,
+            Square::F6,
+            Square::G7,
+            Square::H8,
+        ]);
+        let anti = squares(&[
+            Square::H1,
+            Square::G2,
+            Square::F3,
+            Square::E4,
+            Square::D5,
+            Square::C6,
+            Square::B7,
+            Square::A8,
+        ]);
+        assert_eq!(Bitboard::diagonal(Square::A1), main);
+        assert_eq!(Bitboard::diagonal(Square::H8), main);
+        assert_eq!(Bitboard::anti_diagonal(Square::H1), anti);
+        assert_eq!(Bitboard::anti_diagonal(Square::A8), anti);
+
+        assert_eq!(Bitboard::diagonal(Square::H1), Bitboard::from(Square::H1));
+        assert_eq!(Bitboard::diagonal(Square::A8), Bitboard::from(Square::A8));
+        assert_eq!(
+            Bitboard::anti_diagonal(Square::A1),
+            Bitboard::from(Square::A1)
+        );
+        assert_eq!(
+            Bitboard::anti_diagonal(Square::H8),
+            Bitboard::from(Square::H8)
+        );
+    }
+
+    #[test]
+    fn diagonals_hold_their_square_and_stay_on_the_board() {
+        for square in Square::iter() {
+            for line in [Bitboard::diagonal(square), Bitboard::anti_diagonal(square)] {
+                assert!(line.contains(square), "{square}");
+                for other in line {
+                    let files = square.file().distance(other.file());
+                    let ranks = square.rank().distance(other.rank());
+                    assert_eq!(files, ranks, "{square} {other}");
                 }
-                part.push('\n');
-                part
-            })
-            .collect()
+            }
+        }
+    }
+
+    #[test]
+    fn adjacent_files_at_the_edges() {
+        assert_eq!(Bitboard::adjacent_files(File::A), Bitboard::file(File::B));
+        assert_eq!(Bitboard::adjacent_files(File::H), Bitboard::file(File::G));
+        assert_eq!(
+            Bitboard::adjacent_files(File::D),
+            Bitboard::file(File::C) | Bitboard::file(File::E)
+        );
     }
 }
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+
+use crate::book::polyglot::{BookEntry, encode_move};
+use crate::chess::polyglot::polyglot_key;
+use crate::chess::table::Color;
+use crate::pgn::game::Game;
+use crate::pgn::reader::parse_games;
+
+pub const DEFAULT_MAX_PLY: usize = 24;
+
+#[derive(Default, Clone, Copy)]
+struct MoveStats {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+}
+
+impl MoveStats {
+    // Polyglot convention: a win counts twice as much as a draw, losses not at all
+    fn weight(&self) -> u64 {
+        2 * self.wins as u64 + self.draws as u64
+    }
+}
+
+#[derive(Default)]
+pub struct BuildStats {
+    pub games: usize,
+    pub skipped: usize,
+    pub positions: usize,
+    pub entries: usize,
+}
+
+pub struct BookBuilder {
+    max_ply: usize,
+    stats: HashMap<u64, HashMap<u16, MoveStats>>,
+    build_stats: BuildStats,
+}
+
+impl BookBuilder {
+    pub fn new(max_ply: usize) -> Self {
+        BookBuilder {
+            max_ply,
+            stats: HashMap::new(),
+            build_stats: BuildStats::default(),
+        }
+    }
+
+    pub fn add_pgn_file(&mut self, path: &str) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+        for game in parse_games(&text) {
+            match game.and_then(|game| self.add_game(&game)) {
+                Ok(()) => self.build_stats.games += 1,
+                Err(_) => self.build_stats.skipped += 1,
+            }
+        }
+        Ok(())
+    }
+
+    /// Replays the main line and credits every move up to the ply limit with
+    /// the game result from the mover's point of view. Unfinished games are
+    /// ignored.
+    fn add_game(&mut self, game: &Game) -> Result<(), String> {
+        let result = game.result.white_score().ok_or("Game has no result")?;
+        let mut board = game.start;
+
+        for node in game.moves.iter().take(self.max_ply) {
+            let mv = node.mv;
+            let score = match board.get_side() {
+                Color::White => result,
+                Color::Black => 1.0 - result,
+            };
+            let entry = self
+                .stats
+                .entry(polyglot_key(&board))
+                .or_default()
+                .entry(encode_move(&mv))
+                .or_default();
+            if score == 1.0 {
+                entry.wins += 1;
+            } else if score == 0.0 {
+                entry.losses += 1;
+            } else {
+                entry.draws += 1;
+            }
+
+            board.make_move_with_undo(&mv);
+        }
+        Ok(())
+    }
+
+    /// Writes the collected statistics as a Polyglot book, sorted by key and
+    /// then by decreasing weight. Moves that never scored are left out and
+    /// weights are scaled down per position to fit in 16 bits.
+    pub fn write(mut self, path: &str) -> Result<BuildStats, String> {
+        let mut entries = Vec::new();
+
+        for (&key, moves) in &self.stats {
+            let max_weight = moves.values().map(|s| s.weight()).max().unwrap_or(0);
+            let scale = if max_weight > u16::MAX as u64 {
+                u16::MAX as f64 / max_weight as f64
+            } else {
+                1.0
+            };
+
+            let before = entries.len();
+            for (&mv, stats) in moves {
+                let weight = (stats.weight() as f64 * scale) as u16;
+                if weight > 0 {
+                    entries.push(BookEntry {
+                        key,
+                        mv,
+                        weight,
+                        learn: 0,
+                    });
+                }
+            }
+            if entries.len() > before {
+                self.build_stats.positions += 1;
+            }
+        }
+
+        entries.sort_by(|a, b| a.key.cmp(&b.key).then(b.weight.cmp(&a.weight)));
+        self.build_stats.entries = entries.len();
+
+        let mut file =
+            fs::File::create(path).map_err(|e| format!("Cannot create {}: {}", path, e))?;
+        let bytes: Vec<u8> = entries.iter().flat_map(|e| e.to_bytes()).collect();
+        file.write_all(&bytes)
+            .map_err(|e| format!("Cannot write {}: {}", path, e))?;
+
+        Ok(self.build_stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::book::polyglot::PolyglotBook;
+    use crate::chess::notation::parse_move;
+    use crate::chess::table::Board;
+    use std::path::PathBuf;
+
+    const GAMES: &str = "1. e4 e5 1-0
+1. e4 c5 1/2-1/2
+1. e4 e5 1-0
+1. d4 d5 0-1
+1. Nf3 1-0
+";
+
+    /// A fresh file path in the temporary directory, unique per test.
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "chessengine-book-{}-{}.bin",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn builder(pgn: &str) -> BookBuilder {
+        let mut builder = BookBuilder::new(DEFAULT_MAX_PLY);
+        for game in parse_games(pgn) {
+            builder.add_game(&game.unwrap()).unwrap();
+        }
+        builder
+    }
+
+    fn read_entries(path: &PathBuf) -> Vec<BookEntry> {
+        fs::read(path)
+            .unwrap()
+            .chunks_exact(16)
+            .map(BookEntry::from_bytes)
+            .collect()
+    }
+
+    /// The position after `moves` from the start, with the Polyglot code of `text`.
+    fn position(moves: &[&str], text: &str) -> (Board, u16) {
+        let mut board = Board::new();
+        for mv in moves {
+            board.make_move(&parse_move(&board, mv).unwrap()).unwrap();
+        }
+        let code = encode_move(&parse_move(&board, text).unwrap());
+        (board, code)
+    }
+
+    fn weight(entries: &[BookEntry], moves: &[&str], text: &str) -> Option<u16> {
+        let (board, code) = position(moves, text);
+        let key = polyglot_key(&board);
+        entries
+            .iter()
+            .find(|e| e.key == key && e.mv == code)
+            .map(|e| e.weight)
+    }
+
+    #[test]
+    fn weights_count_wins_twice_and_drop_lost_moves() {
+        let path = temp_path("weights");
+        let stats = builder(GAMES).write(path.to_str().unwrap()).unwrap();
+        let entries = read_entries(&path);
+        fs::remove_file(&path).unwrap();
+
+        // Two wins and a draw
+        assert_eq!(weight(&entries, &[], "e2e4"), Some(5));
+        assert_eq!(weight(&entries, &[], "g1f3"), Some(2));
+        assert_eq!(weight(&entries, &["e2e4"], "c7c5"), Some(1));
+        assert_eq!(weight(&entries, &["d2d4"], "d7d5"), Some(2));
+        // Only ever lost
+        assert_eq!(weight(&entries, &[], "d2d4"), None);
+        assert_eq!(weight(&entries, &["e2e4"], "e7e5"), None);
+
+        assert_eq!(stats.entries, 4);
+        assert_eq!(stats.positions, 3);
+    }
+
+    #[test]
+    fn weights_are_scaled_into_16_bits() {
+        let mut builder = BookBuilder::new(DEFAULT_MAX_PLY);
+        let win = parse_games("1. e4 1-0").remove(0).unwrap();
+        let draw = parse_games("1. d4 1/2-1/2").remove(0).unwrap();
+        for _ in 0..40_000 {
+            builder.add_game(&win).unwrap();
+        }
+        for _ in 0..10_000 {
+            builder.add_game(&draw).unwrap();
+        }
+
+        let path = temp_path("scaled");
+        builder.write(path.to_str().unwrap()).unwrap();
+        let entries = read_entries(&path);
+        fs::remove_file(&path).unwrap();
+
+        // 80000 and 10000 scaled by the same factor
+        assert_eq!(weight(&entries, &[], "e2e4"), Some(u16::MAX));
+        assert_eq!(weight(&entries, &[], "d2d4"), Some(8191));
+    }
+
+    #[test]
+    fn entries_are_sorted_by_key_then_weight() {
+        let path = temp_path("sorted");
+        builder(GAMES).write(path.to_str().unwrap()).unwrap();
+        let entries = read_entries(&path);
+        fs::remove_file(&path).unwrap();
+
+        for pair in entries.windows(2) {
+            assert!(pair[0].key <= pair[1].key);
+            if pair[0].key == pair[1].key {
+                assert!(pair[0].weight >= pair[1].weight);
+            }
+        }
+        let start = polyglot_key(&Board::new());
+        let weights: Vec<u16> = entries
+            .iter()
+            .filter(|e| e.key == start)
+            .map(|e| e.weight)
+            .collect();
+        assert_eq!(weights, [5, 2]);
+    }
+
+    #[test]
+    fn written_book_can_be_probed() {
+        let path = temp_path("probe");
+        builder(GAMES).write(path.to_str().unwrap()).unwrap();
+        let book = PolyglotBook::open(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(book.len(), 4);
+
+        let (start, e4) = position(&[], "e2e4");
+        assert_eq!(
+            book.probe(&start, true).map(|mv| encode_move(&mv)),
+            Some(e4)
+        );
+        let entries = book.entries_for(&start);
+        assert_eq!(entries[0].mv, e4);
+        assert_eq!(entries[0].weight, 5);
+
+        let (after_e4, c5) = position(&["e2e4"], "c7c5");
+        assert_eq!(
+            book.probe(&after_e4, false).map(|mv| encode_move(&mv)),
+            Some(c5)
+        );
+        assert_eq!(book.entries_for(&after_e4).len(), 1);
+
+        let (after_d4d5, _) = position(&["d2d4", "d7d5"], "e2e4");
+        assert_eq!(book.probe(&after_d4d5, true), None);
+    }
+}
@@ -0,0 +1,288 @@
+use rand::Rng;
+
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::chess::polyglot::polyglot_key;
+use crate::chess::square::Square;
+use crate::chess::table::{Board, Type};
+
+const ENTRY_SIZE: usize = 16;
+
+/// One 16 byte record of a Polyglot `.bin` file, all fields big-endian.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BookEntry {
+    pub key: u64,
+    pub mv: u16,
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        BookEntry {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            mv: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0u8; ENTRY_SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mv.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+pub struct PolyglotBook {
+    entries: Vec<BookEntry>,
+}
+
+impl PolyglotBook {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(path).map_err(|e| format!("Cannot read book {}: {}", path, e))?;
+        if bytes.len() % ENTRY_SIZE != 0 {
+            return Err(format!(
+                "Invalid book size for {}: {} bytes",
+                path,
+                bytes.len()
+            ));
+        }
+
+        let entries = bytes
+            .chunks_exact(ENTRY_SIZE)
+            .map(BookEntry::from_bytes)
+            .collect();
+        Ok(Self::from_entries(entries))
+    }
+
+    pub fn from_entries(mut entries: Vec<BookEntry>) -> Self {
+        // Books are sorted by key already, but a stable sort keeps bad files usable
+        entries.sort_by_key(|e| e.key);
+        PolyglotBook { entries }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// All entries stored for the position, in file order.
+    pub fn entries_for(&self, b: &Board) -> &[BookEntry] {
+        let key = polyglot_key(b);
+        let start = self.entries.partition_point(|e| e.key < key);
+        let end = self.entries.partition_point(|e| e.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// Picks a book move for the position: the highest weighted one when
+    /// `best` is set, otherwise a random one with probability proportional to
+    /// its weight. Entries that do not decode to a legal move are ignored.
+    pub fn probe(&self, b: &Board, best: bool) -> Option<Moves> {
+        let candidates: Vec<(Moves, u32)> = self
+            .entries_for(b)
+            .iter()
+            .filter_map(|e| decode_move(b, e.mv).map(|mv| (mv, e.weight as u32)))
+            .collect();
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        if best {
+            return candidates
+                .iter()
+                .max_by_key(|&&(_, w)| w)
+                .map(|&(mv, _)| mv);
+        }
+
+        let total: u32 = candidates.iter().map(|&(_, w)| w).sum();
+        if total == 0 {
+            return Some(candidates[rand::rng().random_range(0..candidates.len())].0);
+        }
+
+        let mut pick = rand::rng().random_range(0..total);
+        for &(mv, weight) in &candidates {
+            if pick < weight {
+                return Some(mv);
+            }
+            pick -= weight;
+        }
+        None
+    }
+}
+
+/// Encodes a move the Polyglot way: to square in bits 0-5, from square in
+/// bits 6-11, promotion piece in bits 12-14 and castling as king takes rook.
+pub fn encode_move(mv: &Moves) -> u16 {
+    let from = mv.from();
+    let mut to = mv.to();
+
+    if mv.is_castle() {
+        to = match to {
+            Square::G1 => Square::H1,
+            Square::C1 => Square::A1,
+            Square::G8 => Square::H8,
+            Square::C8 => Square::A8,
+            _ => to,
+        };
+    }
+
+    let promotion = match mv.promotion() {
+        Some(Type::Knight) => 1,
+        Some(Type::Bishop) => 2,
+        Some(Type::Rook) => 3,
+        Some(_) => 4,
+        None => 0,
+    };
+
+    to.index() as u16 | (from.index() as u16) << 6 | promotion << 12
+}
+
+/// Resolves a Polyglot move against the legal moves of the position.
+pub fn decode_move(b: &Board, raw: u16) -> Option<Moves> {
+    let to = (raw & 0x3F) as u8;
+    let from = Square::from_index(((raw >> 6) & 0x3F) as u8);
+    let promotion = match (raw >> 12) & 0x7 {
+        1 => Some(Type::Knight),
+        2 => Some(Type::Bishop),
+        3 => Some(Type::Rook),
+        4 => Some(Type::Queen),
+        _ => None,
+    };
+
+    let mut buffer = Vec::with_capacity(64);
+    let turn = b.get_side();
+    b.get_legal_moves(turn, &mut buffer)
+        .iter()
+        .find(|mv| {
+            mv.from() == from && encode_move(mv) & 0x3F == to as u16 && mv.promotion() == promotion
+        })
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::notation::parse_move;
+
+    fn entry(b: &Board, text: &str, weight: u16) -> BookEntry {
+        let mv = parse_move(b, text).unwrap();
+        BookEntry {
+            key: polyglot_key(b),
+            mv: encode_move(&mv),
+            weight,
+            learn: 0,
+        }
+    }
+
+    #[test]
+    fn encodes_castling_as_king_takes_rook() {
+        let cases = [
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1g1", Square::H1),
+            ("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "e1c1", Square::A1),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8g8", Square::H8),
+            ("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1", "e8c8", Square::A8),
+        ];
+        for (fen, text, rook) in cases {
+            let board = Board::new_from_fen(fen).unwrap();
+            let mv = parse_move(&board, text).unwrap();
+            let raw = encode_move(&mv);
+            assert_eq!(raw & 0x3F, rook.index() as u16, "{text}");
+            assert_eq!(decode_move(&board, raw), Some(mv), "{text}");
+        }
+    }
+
+    #[test]
+    fn encodes_promotions() {
+        let board = Board::new_from_fen("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let cases = [
+            ("b7b8n", 1),
+            ("b7b8b", 2),
+            ("b7b8r", 3),
+            ("b7b8q", 4),
+            ("b7a8n", 1),
+            ("b7a8q", 4),
+        ];
+        for (text, piece) in cases {
+            let mv = parse_move(&board, text).unwrap();
+            let raw = encode_move(&mv);
+            assert_eq!(raw >> 12, piece, "{text}");
+            assert_eq!(decode_move(&board, raw), Some(mv), "{text}");
+        }
+    }
+
+    #[test]
+    fn round_trips_every_legal_move() {
+        for fen in [
+            crate::chess::table::START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+        ] {
+            let board = Board::new_from_fen(fen).unwrap();
+            for mv in board.legal_moves() {
+                assert_eq!(
+                    decode_move(&board, encode_move(&mv)),
+                    Some(mv),
+                    "{fen} {mv:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let board = Board::new();
+        let e2e5 = Square::E5.index() as u16 | (Square::E2.index() as u16) << 6;
+        assert_eq!(decode_move(&board, e2e5), None);
+        assert_eq!(decode_move(&board, 0), None);
+    }
+
+    #[test]
+    fn probe_follows_the_weights() {
+        let board = Board::new();
+        let after_e4 = {
+            let mut b = board;
+            b.make_move_with_undo(&parse_move(&b, "e2e4").unwrap());
+            b
+        };
+        let illegal = BookEntry {
+            key: polyglot_key(&board),
+            mv: Square::E5.index() as u16 | (Square::E2.index() as u16) << 6,
+            weight: u16::MAX,
+            learn: 0,
+        };
+        let book = PolyglotBook::from_entries(vec![
+            entry(&after_e4, "e7e5", 10),
+            entry(&board, "d2d4", 1),
+            illegal,
+            entry(&board, "g1f3", 0),
+            entry(&board, "e2e4", 3),
+        ]);
+        assert_eq!(book.len(), 5);
+        assert_eq!(book.entries_for(&board).len(), 4);
+
+        let e4 = parse_move(&board, "e2e4").unwrap();
+        let d4 = parse_move(&board, "d2d4").unwrap();
+        assert_eq!(book.probe(&board, true), Some(e4));
+        assert_eq!(book.probe(&after_e4, true), parse_move(&after_e4, "e7e5"));
+
+        let (mut e4_count, mut d4_count) = (0, 0);
+        for _ in 0..2000 {
+            match book.probe(&board, false) {
+                Some(mv) if mv == e4 => e4_count += 1,
+                Some(mv) if mv == d4 => d4_count += 1,
+                other => panic!("unexpected book move {other:?}"),
+            }
+        }
+        assert!(
+            d4_count > 0 && e4_count > 2 * d4_count,
+            "{e4_count} {d4_count}"
+        );
+
+        let empty = Board::new_from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(book.probe(&empty, false), None);
+    }
+}
@@ -0,0 +1,442 @@
+use std::io::{self, Write};
+
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::chess::notation::parse_move;
+use crate::chess::table::{Board, Color, GameStatus};
+use crate::engine::find_best_move::{Engine, MAX_DEPTH, SearchLimits, allocate_time};
+use crate::uci::uci::ENGINE_NAME;
+
+// Kept back from the clock for input and output overhead
+const MOVE_OVERHEAD_MS: u64 = 50;
+
+/// Time control set by `level` or `st`.
+#[derive(Clone, Copy)]
+enum TimeControl {
+    /// `moves` per session (0 for the whole game) in `base` milliseconds,
+    /// plus `inc` milliseconds per move.
+    Conventional { moves: u32, base: u64, inc: u64 },
+    /// A fixed number of milliseconds per move.
+    PerMove(u64),
+}
+
+/// XBoard/WinBoard protocol version 2 (CECP).
+pub struct CecpEngine {
+    board: Board,
+    engine: Engine,
+    /// Every position reached, for `undo` and `remove`.
+    positions: Vec<Board>,
+    history: Vec<u64>,
+    /// The side the engine plays, `None` in force mode.
+    engine_color: Option<Color>,
+    time_control: TimeControl,
+    max_depth: u8,
+    engine_time: u64,
+    opponent_time: u64,
+    post: bool,
+    game_over: bool,
+}
+
+impl Default for CecpEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CecpEngine {
+    pub fn new() -> Self {
+        let board = Board::new();
+        Self {
+            board,
+            engine: Engine::new(),
+            positions: vec![board],
+            history: vec![board.get_hash()],
+            engine_color: Some(Color::Black),
+            time_control: TimeControl::Conventional {
+                moves: 40,
+                base: 300_000,
+                inc: 0,
+            },
+            max_depth: MAX_DEPTH,
+            engine_time: 300_000,
+            opponent_time: 300_000,
+            post: false,
+            game_over: false,
+        }
+    }
+
+    /// Answers commands until `quit` or the end of `lines`.
+    pub fn run(&mut self, lines: impl Iterator<Item = String>) {
+        let mut stdout = io::stdout();
+
+        for command in lines {
+            let trimmed = command.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            if trimmed == "quit" {
+                break;
+            }
+
+            for response in self.process_command(trimmed) {
+                writeln!(stdout, "{}", response).unwrap();
+            }
+            stdout.flush().unwrap();
+        }
+    }
+
+    fn process_command(&mut self, command: &str) -> Vec<String> {
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        let args = &parts[1..];
+        let mut output = Vec::new();
+
+        match parts[0] {
+            "xboard" | "accepted" | "rejected" | "random" | "hard" | "easy" | "computer"
+            | "name" | "rating" | "ics" | "?" => {}
+            "protover" => output.push(format!(
+                "feature myname=\"{}\" usermove=1 setboard=1 ping=1 playother=1 san=0 \
+                 colors=0 time=1 draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 done=1",
+                ENGINE_NAME
+            )),
+            "ping" => output.push(format!("pong {}", args.first().unwrap_or(&""))),
+            "new" => {
+                self.set_position(Board::new());
+                self.engine.clear();
+                self.engine_color = Some(Color::Black);
+                self.max_depth = MAX_DEPTH;
+                self.reset_clocks();
+            }
+            "setboard" => match Board::new_from_fen(&args.join(" ")) {
+                Ok(board) => self.set_position(board),
+                Err(e) => output.push(format!("tellusererror Illegal position: {}", e)),
+            },
+            "force" => self.engine_color = None,
+            "go" => {
+                self.engine_color = Some(self.board.get_side());
+                self.think(&mut output);
+            }
+            "playother" => self.engine_color = Some(self.board.get_side().opposite()),
+            "white" | "black" => {
+                // Protocol version 1: the named side moves, the engine plays the other one
+                let side = if parts[0] == "white" {
+                    Color::White
+                } else {
+                    Color::Black
+                };
+                self.engine_color = Some(side.opposite());
+            }
+            "level" => match parse_level(args) {
+                Some(time_control) => {
+                    self.time_control = time_control;
+                    self.reset_clocks();
+                }
+                None => output.push(format!("Error (invalid level): {}", command)),
+            },
+            "st" => match args.first().and_then(|s| s.parse::<f64>().ok()) {
+                Some(seconds) => {
+                    self.time_control = TimeControl::PerMove((seconds * 1000.0) as u64)
+                }
+                None => output.push(format!("Error (invalid time): {}", command)),
+            },
+            "sd" => match args.first().and_then(|d| d.parse::<u8>().ok()) {
+                Some(depth) => self.max_depth = depth.clamp(1, MAX_DEPTH),
+                None => output.push(format!("Error (invalid depth): {}", command)),
+            },
+            "time" | "otim" => match args.first().and_then(|t| t.parse::<u64>().ok()) {
+                Some(centiseconds) => {
+                    let clock = if parts[0] == "time" {
+                        &mut self.engine_time
+                    } else {
+                        &mut self.opponent_time
+                    };
+                    *clock = centiseconds * 10;
+                }
+                None => output.push(format!("Error (invalid time): {}", command)),
+            },
+            "undo" => self.take_back(1),
+            "remove" => self.take_back(2),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "result" => {
+                self.engine_color = None;
+                self.game_over = true;
+            }
+            "usermove" => match args.first() {
+                Some(text) => self.user_move(text, &mut output),
+                None => output.push("Error (missing move): usermove".to_string()),
+            },
+            _ => {
+                // Without usermove=1 moves arrive as bare words
+                if parse_move(&self.board, parts[0]).is_some() {
+                    self.user_move(parts[0], &mut output);
+                } else {
+                    output.push(format!("Error (unknown command): {}", parts[0]));
+                }
+            }
+        }
+        output
+    }
+
+    fn reset_clocks(&mut self) {
+        if let TimeControl::Conventional { base, .. } = self.time_control {
+            self.engine_time = base;
+            self.opponent_time = base;
+        }
+    }
+
+    fn set_position(&mut self, board: Board) {
+        self.board = board;
+        self.positions = vec![board];
+        self.history = vec![board.get_hash()];
+        self.game_over = false;
+    }
+
+    fn play(&mut self, mv: &Moves) {
+        self.board.make_move_with_undo(mv);
+        self.positions.push(self.board);
+        self.history.push(self.board.get_hash());
+    }
+
+    fn take_back(&mut self, plies: usize) {
+        for _ in 0..plies {
+            if self.positions.len() < 2 {
+                break;
+            }
+            self.positions.pop();
+            self.history.pop();
+        }
+        self.board = *self.positions.last().unwrap();
+        self.game_over = false;
+    }
+
+    fn user_move(&mut self, text: &str, output: &mut Vec<String>) {
+        let legal = self.board.legal_moves();
+        let Some(mv) = parse_move(&self.board, text).filter(|mv| legal.contains(mv)) else {
+            output.push(format!("Illegal move: {}", text));
+            return;
+        };
+
+        self.play(&mv);
+        if self.check_game_end(output) {
+            return;
+        }
+        if self.engine_color == Some(self.board.get_side()) {
+            self.think(output);
+        }
+    }
+
+    /// Announces the result once the game is over. Returns whether it is.
+    fn check_game_end(&mut self, output: &mut Vec<String>) -> bool {
+        let result = match self.board.game_status(&self.history) {
+            GameStatus::Ongoing => return false,
+            GameStatus::Checkmate => match self.board.get_side() {
+                Color::White => "0-1 {Black mates}",
+                Color::Black => "1-0 {White mates}",
+            },
+            GameStatus::Stalemate => "1/2-1/2 {Stalemate}",
+            GameStatus::ThreefoldRepetition => "1/2-1/2 {Draw by repetition}",
+            GameStatus::FiftyMove => "1/2-1/2 {Draw by fifty move rule}",
+            GameStatus::InsufficientMaterial => "1/2-1/2 {Insufficient material}",
+        };
+        if !self.game_over {
+            output.push(result.to_string());
+        }
+        self.game_over = true;
+        true
+    }
+
+    fn time_budget(&self) -> u64 {
+        match self.time_control {
+            TimeControl::PerMove(ms) => ms.saturating_sub(MOVE_OVERHEAD_MS).max(1),
+            TimeControl::Conventional { moves: 0, inc, .. } => allocate_time(self.engine_time, inc),
+            TimeControl::Conventional { moves, inc, .. } => {
+                // Moves left until the next time control, this one included
+                let played = (self.board.get_fullmove_number() as u32).saturating_sub(1) % moves;
+                let moves_to_go = (moves - played) as u64;
+                let time_left = self.engine_time.saturating_sub(MOVE_OVERHEAD_MS);
+                (time_left / (moves_to_go + 1) + inc / 2).max(1)
+            }
+        }
+    }
+
+    fn think(&mut self, output: &mut Vec<String>) {
+        if self.check_game_end(output) {
+            self.engine_color = None;
+            return;
+        }
+
+        let limits = SearchLimits {
+            depth: Some(self.max_depth),
+            time_ms: Some(self.time_budget()),
+            nodes: None,
+        };
+        let previous = &self.history[..self.history.len() - 1];
+        let result = self.engine.search(&self.board, previous, &limits);
+
+        if self.post {
+            // ply score time(centiseconds) nodes pv
+            output.push(format!(
+                "{} {} {} {} {}",
+                result.depth,
+                result.score,
+                result.time_ms / 10,
+                result.nodes,
+                result.best_move.to_string()
+            ));
+        }
+        output.push(format!("move {}", result.best_move.to_string()));
+        self.play(&result.best_move);
+        self.check_game_end(output);
+    }
+}
+
+/// Parses `level MPS BASE INC`, where BASE is minutes or `minutes:seconds`
+/// and INC seconds.
+fn parse_level(args: &[&str]) -> Option<TimeControl> {
+    let [moves, base, inc] = args else {
+        return None;
+    };
+    let base_ms = match base.split_once(':') {
+        Some((minutes, seconds)) => {
+            minutes.parse::<u64>().ok()? * 60_000 + seconds.parse::<u64>().ok()? * 1000
+        }
+        None => (base.parse::<f64>().ok()? * 60_000.0) as u64,
+    };
+    Some(TimeControl::Conventional {
+        moves: moves.parse().ok()?,
+        base: base_ms,
+        inc: (inc.parse::<f64>().ok()? * 1000.0) as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `commands` in order and returns everything the engine answered.
+    fn send(cecp: &mut CecpEngine, commands: &[&str]) -> Vec<String> {
+        commands
+            .iter()
+            .flat_map(|command| cecp.process_command(command))
+            .collect()
+    }
+
+    #[test]
+    fn replies_to_a_user_move() {
+        let mut cecp = CecpEngine::new();
+        let output = send(&mut cecp, &["new", "sd 1", "usermove e2e4"]);
+
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("move "), "{:?}", output);
+        assert_eq!(cecp.positions.len(), 3);
+        assert_eq!(cecp.board.get_side(), Color::White);
+    }
+
+    #[test]
+    fn rejects_an_illegal_move() {
+        let mut cecp = CecpEngine::new();
+        let output = send(&mut cecp, &["new", "usermove e2e5"]);
+
+        assert_eq!(output, ["Illegal move: e2e5"]);
+        assert_eq!(cecp.positions.len(), 1);
+    }
+
+    #[test]
+    fn go_plays_the_side_to_move() {
+        let mut cecp = CecpEngine::new();
+        let output = send(&mut cecp, &["new", "force", "e2e4", "sd 1", "go"]);
+
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("move "), "{:?}", output);
+        assert_eq!(cecp.engine_color, Some(Color::Black));
+        assert_eq!(cecp.board.get_side(), Color::White);
+    }
+
+    #[test]
+    fn undo_and_remove_take_back_moves() {
+        let mut cecp = CecpEngine::new();
+        send(&mut cecp, &["new", "force", "e2e4", "e7e5", "g1f3"]);
+        let after_e5 = cecp.positions[2];
+
+        assert!(send(&mut cecp, &["undo"]).is_empty());
+        assert_eq!(cecp.board.get_hash(), after_e5.get_hash());
+        assert_eq!(cecp.history.len(), 3);
+
+        send(&mut cecp, &["remove"]);
+        assert_eq!(cecp.board.get_hash(), Board::new().get_hash());
+
+        // Nothing left to take back
+        send(&mut cecp, &["remove"]);
+        assert_eq!(cecp.positions.len(), 1);
+    }
+
+    #[test]
+    fn setboard_replaces_the_game() {
+        let mut cecp = CecpEngine::new();
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+        send(&mut cecp, &["new", "force", "e2e4"]);
+
+        assert!(send(&mut cecp, &[&format!("setboard {}", fen)]).is_empty());
+        assert_eq!(cecp.board.get_fen(), fen);
+        assert_eq!(cecp.positions.len(), 1);
+
+        let output = send(&mut cecp, &["setboard 8/8/8 w - - 0 1"]);
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with("tellusererror"), "{:?}", output);
+        assert_eq!(cecp.board.get_fen(), fen);
+    }
+
+    #[test]
+    fn announces_mate_after_a_user_move() {
+        let mut cecp = CecpEngine::new();
+        let output = send(
+            &mut cecp,
+            &["new", "force", "f2f3", "e7e5", "g2g4", "usermove d8h4"],
+        );
+
+        assert_eq!(output, ["0-1 {Black mates}"]);
+        assert!(cecp.game_over);
+    }
+
+    #[test]
+    fn level_sets_the_time_control() {
+        let mut cecp = CecpEngine::new();
+
+        assert!(send(&mut cecp, &["level 40 5 0"]).is_empty());
+        assert!(matches!(
+            cecp.time_control,
+            TimeControl::Conventional {
+                moves: 40,
+                base: 300_000,
+                inc: 0
+            }
+        ));
+
+        send(&mut cecp, &["level 0 2:30 1.5"]);
+        assert!(matches!(
+            cecp.time_control,
+            TimeControl::Conventional {
+                moves: 0,
+                base: 150_000,
+                inc: 1500
+            }
+        ));
+        assert_eq!(cecp.engine_time, 150_000);
+
+        assert_eq!(
+            send(&mut cecp, &["level 40 5"]),
+            ["Error (invalid level): level 40 5"]
+        );
+    }
+
+    #[test]
+    fn time_budget_handles_a_zero_fullmove_number() {
+        let mut cecp = CecpEngine::new();
+        send(
+            &mut cecp,
+            &["level 40 5 0", "setboard 4k3/8/8/8/8/8/4P3/4K3 w - - 0 0"],
+        );
+
+        let budget = cecp.time_budget();
+        assert!(budget > 0 && budget < 300_000, "{}", budget);
+    }
+}
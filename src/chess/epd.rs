@@ -0,0 +1,164 @@
+use crate::chess::table::Board;
+
+/// One line of an EPD file: the position and its operations in file order.
+/// Quoted operands are stored without their quotes.
+pub struct EpdRecord {
+    pub fen: String,
+    pub board: Board,
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl EpdRecord {
+    pub fn operands(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(op, _)| op == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    /// First operand of `opcode`, for single valued operations like `id`.
+    pub fn operand(&self, opcode: &str) -> Option<&str> {
+        self.operands(opcode)
+            .and_then(|operands| operands.first())
+            .map(String::as_str)
+    }
+}
+
+/// Splits `bm Qd1+ Qxh7; id "WAC.001";` into operations, keeping `;` inside
+/// quoted strings.
+fn parse_operations(text: &str) -> Vec<(String, Vec<String>)> {
+    let mut operations = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut in_quotes = false;
+
+    for c in text.chars() {
+        match c {
+            '"' => {
+                if in_quotes {
+                    words.push(std::mem::take(&mut word));
+                }
+                in_quotes = !in_quotes;
+            }
+            _ if in_quotes => word.push(c),
+            ';' => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                if !words.is_empty() {
+                    let opcode = words.remove(0);
+                    operations.push((opcode, std::mem::take(&mut words)));
+                }
+            }
+            c if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            _ => word.push(c),
+        }
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+    if !words.is_empty() {
+        let opcode = words.remove(0);
+        operations.push((opcode, words));
+    }
+    operations
+}
+
+/// Parses an EPD line. The four position fields may be followed by the FEN
+/// move counters; otherwise they are taken from `hmvc` and `fmvn`.
+pub fn parse_epd_line(line: &str) -> Result<EpdRecord, String> {
+    let line = line.trim();
+    let mut rest = line;
+    let mut fields = Vec::with_capacity(6);
+
+    while fields.len() < 6 {
+        let trimmed = rest.trim_start();
+        let end = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+        let field = &trimmed[..end];
+        if field.is_empty() || (fields.len() >= 4 && field.parse::<u16>().is_err()) {
+            break;
+        }
+        fields.push(field);
+        rest = &trimmed[end..];
+    }
+
+    if fields.len() < 4 {
+        return Err(format!("Invalid EPD: {}", line));
+    }
+
+    let operations = parse_operations(rest);
+    let operand = |opcode: &str| {
+        operations
+            .iter()
+            .find(|(op, _)| op == opcode)
+            .and_then(|(_, operands)| operands.first())
+            .cloned()
+    };
+    let halfmove = fields
+        .get(4)
+        .map(|f| f.to_string())
+        .or_else(|| operand("hmvc"))
+        .unwrap_or_else(|| "0".to_string());
+    let fullmove = fields
+        .get(5)
+        .map(|f| f.to_string())
+        .or_else(|| operand("fmvn"))
+        .unwrap_or_else(|| "1".to_string());
+
+    let fen = format!("{} {} {}", fields[..4].join(" "), halfmove, fullmove);
+    let board = Board::new_from_fen(&fen)?;
+
+    Ok(EpdRecord {
+        fen,
+        board,
+        operations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_operations_and_keeps_quoted_semicolons() {
+        let record = parse_epd_line(
+            r#"2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6 Qxh6; id "WAC.001"; c0 "mate; in 3""#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            record.operands("bm"),
+            Some(&["Qg6".to_string(), "Qxh6".to_string()][..])
+        );
+        assert_eq!(record.operand("id"), Some("WAC.001"));
+        assert_eq!(record.operand("c0"), Some("mate; in 3"));
+        assert_eq!(record.operands("am"), None);
+    }
+
+    #[test]
+    fn reads_the_move_counters() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - -";
+
+        let record = parse_epd_line(&format!("{} 7 42 bm e4;", fen)).unwrap();
+        assert_eq!(record.fen, format!("{} 7 42", fen));
+        assert_eq!(record.operand("bm"), Some("e4"));
+
+        let record = parse_epd_line(&format!("{} hmvc 3; fmvn 12;", fen)).unwrap();
+        assert_eq!(record.fen, format!("{} 3 12", fen));
+
+        let record = parse_epd_line(&format!("{} id \"no counters\"", fen)).unwrap();
+        assert_eq!(record.fen, format!("{} 0 1", fen));
+        assert_eq!(record.operand("id"), Some("no counters"));
+    }
+
+    #[test]
+    fn rejects_a_short_position() {
+        assert!(parse_epd_line("4k3/8/8/8/8/8/4P3/4K3 w -").is_err());
+        assert!(parse_epd_line("").is_err());
+    }
+}
@@ -1,3 +1,10 @@
-pub(crate) mod moves_gen;
-pub(crate) mod table;
+#[cfg(feature = "cli")]
+pub mod epd;
+pub mod moves_gen;
+pub mod notation;
+pub mod polyglot;
+pub mod prng;
+pub mod san;
+pub mod square;
+pub mod table;
 pub mod zobrist;
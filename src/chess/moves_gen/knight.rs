@@ -1,8 +1,10 @@
-pub fn moves(knights: u64) -> u64 {
-    const NOT_A: u64 = 0xfefefefefefefefe;
-    const NOT_H: u64 = 0x7f7f7f7f7f7f7f7f;
-    const NOT_AB: u64 = 0xfcfcfcfcfcfcfcfc;
-    const NOT_GH: u64 = 0x3f3f3f3f3f3f3f3f;
+use crate::bitboard::bitboard::Bitboard;
+
+pub fn moves(knights: Bitboard) -> Bitboard {
+    const NOT_A: Bitboard = Bitboard::new(0xfefefefefefefefe);
+    const NOT_H: Bitboard = Bitboard::new(0x7f7f7f7f7f7f7f7f);
+    const NOT_AB: Bitboard = Bitboard::new(0xfcfcfcfcfcfcfcfc);
+    const NOT_GH: Bitboard = Bitboard::new(0x3f3f3f3f3f3f3f3f);
 
     ((knights << 15) & NOT_H)
         | ((knights << 17) & NOT_A)
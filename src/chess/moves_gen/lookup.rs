@@ -0,0 +1,252 @@
+//! Attack sets of a piece standing on a single square. Leaper and ray tables
+//! are built at compile time, sliders go through the magic bitboards.
+
+use crate::bitboard::bitboard::Bitboard;
+use crate::chess::moves_gen::magic_bitboards;
+use crate::chess::square::Square;
+use crate::chess::table::Color;
+
+const KNIGHT_STEPS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+
+const KING_STEPS: [(i8, i8); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+pub static KNIGHT_ATTACKS: [u64; 64] = leaper_table(&KNIGHT_STEPS);
+pub static KING_ATTACKS: [u64; 64] = leaper_table(&KING_STEPS);
+/// Squares a pawn of each colour attacks, indexed by `Color as usize`.
+pub static PAWN_ATTACKS: [[u64; 64]; 2] = [
+    leaper_table(&[(-1, 1), (1, 1)]),
+    leaper_table(&[(-1, -1), (1, -1)]),
+];
+const RAYS: ([[u64; 64]; 64], [[u64; 64]; 64]) = ray_tables();
+
+/// Squares strictly between `a` and `b`, empty when they share no rank, file or diagonal.
+pub static BETWEEN: [[u64; 64]; 64] = RAYS.0;
+/// The whole rank, file or diagonal through `a` and `b` from edge to edge,
+/// empty when they are not aligned.
+pub static LINE: [[u64; 64]; 64] = RAYS.1;
+
+#[inline(always)]
+pub fn knight(square: Square) -> Bitboard {
+    Bitboard::new(KNIGHT_ATTACKS[square.index() as usize])
+}
+
+#[inline(always)]
+pub fn king(square: Square) -> Bitboard {
+    Bitboard::new(KING_ATTACKS[square.index() as usize])
+}
+
+/// Squares a `color` pawn on `square` attacks.
+#[inline(always)]
+pub fn pawn(color: Color, square: Square) -> Bitboard {
+    Bitboard::new(PAWN_ATTACKS[color as usize][square.index() as usize])
+}
+
+#[inline(always)]
+pub fn bishop(square: Square, occupied: Bitboard) -> Bitboard {
+    Bitboard::new(magic_bitboards::bishop_attacks(
+        square.index() as usize,
+        occupied.get_value(),
+    ))
+}
+
+#[inline(always)]
+pub fn rook(square: Square, occupied: Bitboard) -> Bitboard {
+    Bitboard::new(magic_bitboards::rook_attacks(
+        square.index() as usize,
+        occupied.get_value(),
+    ))
+}
+
+#[inline(always)]
+pub fn queen(square: Square, occupied: Bitboard) -> Bitboard {
+    Bitboard::new(magic_bitboards::queen_attacks(
+        square.index() as usize,
+        occupied.get_value(),
+    ))
+}
+
+#[inline(always)]
+pub fn between(a: Square, b: Square) -> Bitboard {
+    Bitboard::new(BETWEEN[a.index() as usize][b.index() as usize])
+}
+
+#[inline(always)]
+pub fn line(a: Square, b: Square) -> Bitboard {
+    Bitboard::new(LINE[a.index() as usize][b.index() as usize])
+}
+
+/// The square `df` files and `dr` ranks away, `None` off the board.
+const fn step(square: usize, df: i8, dr: i8) -> Option<usize> {
+    let file = (square % 8) as i8 + df;
+    let rank = (square / 8) as i8 + dr;
+    if file < 0 || file > 7 || rank < 0 || rank > 7 {
+        None
+    } else {
+        Some((rank * 8 + file) as usize)
+    }
+}
+
+const fn leaper_table(steps: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0u64; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < steps.len() {
+            if let Some(target) = step(square, steps[i].0, steps[i].1) {
+                table[square] |= 1u64 << target;
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+/// Walks all eight directions from every square: the squares passed so far
+/// give `BETWEEN`, both half rays together give `LINE`.
+const fn ray_tables() -> ([[u64; 64]; 64], [[u64; 64]; 64]) {
+    let mut between = [[0u64; 64]; 64];
+    let mut line = [[0u64; 64]; 64];
+    let mut a = 0;
+    while a < 64 {
+        let mut d = 0;
+        while d < KING_STEPS.len() {
+            let (df, dr) = KING_STEPS[d];
+            let full = ray(a, df, dr) | ray(a, -df, -dr) | 1u64 << a;
+
+            let mut gap = 0u64;
+            let mut current = step(a, df, dr);
+            while let Some(b) = current {
+                between[a][b] = gap;
+                line[a][b] = full;
+                gap |= 1u64 << b;
+                current = step(b, df, dr);
+            }
+            d += 1;
+        }
+        a += 1;
+    }
+    (between, line)
+}
+
+const fn ray(square: usize, df: i8, dr: i8) -> u64 {
+    let mut squares = 0u64;
+    let mut current = step(square, df, dr);
+    while let Some(next) = current {
+        squares |= 1u64 << next;
+        current = step(next, df, dr);
+    }
+    squares
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::square::{File, Rank};
+
+    fn squares(list: &[Square]) -> Bitboard {
+        list.iter()
+            .fold(Bitboard::empty(), |bb, &sq| bb | Bitboard::from(sq))
+    }
+
+    #[test]
+    fn between_aligned_squares() {
+        assert_eq!(
+            between(Square::A1, Square::A4),
+            squares(&[Square::A2, Square::A3])
+        );
+        assert_eq!(
+            between(Square::H1, Square::D1),
+            squares(&[Square::E1, Square::F1, Square::G1])
+        );
+        assert_eq!(
+            between(Square::B2, Square::E5),
+            squares(&[Square::C3, Square::D4])
+        );
+        assert_eq!(
+            between(Square::H1, Square::A8),
+            squares(&[
+                Square::G2,
+                Square::F3,
+                Square::E4,
+                Square::D5,
+                Square::C6,
+                Square::B7
+            ])
+        );
+    }
+
+    #[test]
+    fn line_runs_from_edge_to_edge() {
+        assert_eq!(line(Square::C2, Square::C5), Bitboard::file(File::C));
+        assert_eq!(line(Square::G4, Square::B4), Bitboard::rank(Rank::FOURTH));
+        assert_eq!(line(Square::C3, Square::E5), Bitboard::diagonal(Square::C3));
+        assert_eq!(
+            line(Square::F3, Square::D5),
+            Bitboard::anti_diagonal(Square::F3)
+        );
+    }
+
+    #[test]
+    fn adjacent_squares_have_nothing_between() {
+        for (a, b) in [
+            (Square::E4, Square::E5),
+            (Square::E4, Square::D4),
+            (Square::E4, Square::F5),
+            (Square::E4, Square::D5),
+            (Square::A1, Square::B2),
+        ] {
+            assert!(between(a, b).is_empty(), "{a} {b}");
+            assert!(line(a, b).contains(a) && line(a, b).contains(b), "{a} {b}");
+        }
+        assert_eq!(line(Square::A1, Square::B2), Bitboard::diagonal(Square::A1));
+    }
+
+    #[test]
+    fn unaligned_squares_give_empty_sets() {
+        for (a, b) in [
+            (Square::A1, Square::B3),
+            (Square::E4, Square::F6),
+            (Square::A1, Square::H7),
+            (Square::C1, Square::H8),
+            (Square::E4, Square::E4),
+        ] {
+            assert!(between(a, b).is_empty(), "{a} {b}");
+            assert!(line(a, b).is_empty(), "{a} {b}");
+        }
+    }
+
+    #[test]
+    fn tables_are_symmetric_and_consistent() {
+        for a in Square::iter() {
+            for b in Square::iter() {
+                assert_eq!(between(a, b), between(b, a), "{a} {b}");
+                assert_eq!(line(a, b), line(b, a), "{a} {b}");
+                let gap = between(a, b);
+                assert_eq!(gap & line(a, b), gap, "{a} {b}");
+                assert!(!gap.contains(a) && !gap.contains(b), "{a} {b}");
+                if !line(a, b).is_empty() {
+                    assert_eq!(gap.count_ones(), a.distance(b) as u64 - 1, "{a} {b}");
+                }
+            }
+        }
+    }
+}
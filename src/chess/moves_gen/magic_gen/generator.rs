@@ -1,208 +1,211 @@
-use rand::Rng;
+//! Finds magic numbers for rook and bishop attacks and writes them, together
+//! with the attack tables they index, as Rust source. The engine's `build.rs`
+//! includes this file and calls `write_tables`; `main.rs` runs it on its own.
+//!
+//! The search uses a fixed seed, so every build produces the same tables.
 
-fn generate_rook_mask(square: usize) -> u64 {
+use std::io::{self, Write};
+
+#[path = "../../prng.rs"]
+mod prng;
+
+use prng::Prng;
+
+const SEED: u64 = 0x2545_F491_4F6C_DD1D;
+
+struct Magic {
+    mask: u64,
+    magic: u64,
+    shift: u8,
+    offset: u32,
+}
+
+/// Magics for all 64 squares and the attack table they share, each square
+/// owning `1 << mask.count_ones()` entries from its offset on.
+struct Tables {
+    magics: Vec<Magic>,
+    attacks: Vec<u64>,
+}
+
+/// Candidates with few bits set are far more likely to be magics.
+fn sparse(rng: &mut Prng) -> u64 {
+    rng.next_u64() & rng.next_u64() & rng.next_u64()
+}
+
+fn rook_mask(square: usize) -> u64 {
     let rank = square / 8;
     let file = square % 8;
     let mut mask = 0u64;
     for f in 1..7 {
-        if f != file { mask |= 1u64 << (rank * 8 + f); }
+        if f != file {
+            mask |= 1u64 << (rank * 8 + f);
+        }
     }
     for r in 1..7 {
-        if r != rank { mask |= 1u64 << (r * 8 + file); }
+        if r != rank {
+            mask |= 1u64 << (r * 8 + file);
+        }
     }
     mask
 }
 
-fn generate_bishop_mask(square: usize) -> u64 {
-    let rank = square / 8;
-    let file = square % 8;
-    let mut mask = 0u64;
-    let mut r = rank + 1;
-    let mut f = file + 1;
-    while r <= 6 && f <= 6 {
-        mask |= 1u64 << (r * 8 + f);
-        r += 1; f += 1;
-    }
-    r = rank.wrapping_sub(1);
-    f = file.wrapping_sub(1);
-    while r >= 1 && r < 8 && f >= 1 && f < 8 {
-        mask |= 1u64 << (r * 8 + f);
-        r = r.wrapping_sub(1); f = f.wrapping_sub(1);
-    }
-    r = rank + 1;
-    f = file.wrapping_sub(1);
-    while r <= 6 && f >= 1 && f < 8 {
-        mask |= 1u64 << (r * 8 + f);
-        r += 1; f = f.wrapping_sub(1);
-    }
-    r = rank.wrapping_sub(1);
-    f = file + 1;
-    while r >= 1 && r < 8 && f <= 6 {
-        mask |= 1u64 << (r * 8 + f);
-        r = r.wrapping_sub(1); f += 1;
+/// The bishop rays without the edge squares, which never block anything.
+fn bishop_mask(square: usize) -> u64 {
+    const INNER: u64 = 0x007E_7E7E_7E7E_7E00;
+    bishop_attacks(square, 0) & INNER
+}
+
+fn slide(square: usize, occupancy: u64, directions: &[(i32, i32)]) -> u64 {
+    let mut attacks = 0u64;
+    for &(df, dr) in directions {
+        let mut file = (square % 8) as i32 + df;
+        let mut rank = (square / 8) as i32 + dr;
+        while (0..8).contains(&file) && (0..8).contains(&rank) {
+            let bit = 1u64 << (rank * 8 + file);
+            attacks |= bit;
+            if occupancy & bit != 0 {
+                break;
+            }
+            file += df;
+            rank += dr;
+        }
     }
-    mask
+    attacks
+}
+
+fn rook_attacks(square: usize, occupancy: u64) -> u64 {
+    slide(square, occupancy, &[(1, 0), (-1, 0), (0, 1), (0, -1)])
+}
+
+fn bishop_attacks(square: usize, occupancy: u64) -> u64 {
+    slide(square, occupancy, &[(1, 1), (1, -1), (-1, 1), (-1, -1)])
 }
 
+/// The `index`-th subset of `mask`, taking the bits of `index` in order.
 fn index_to_occupancy(index: u32, mut mask: u64) -> u64 {
     let mut result = 0u64;
-    let mut bit_count = 0;
+    let mut bit = 0;
     while mask != 0 {
         let square = mask.trailing_zeros();
         mask &= mask - 1;
-        if (index & (1 << bit_count)) != 0 { result |= 1u64 << square; }
-        bit_count += 1;
+        if index & (1 << bit) != 0 {
+            result |= 1u64 << square;
+        }
+        bit += 1;
     }
     result
 }
 
-fn calculate_rook_attacks(square: usize, occupancy: u64) -> u64 {
-    let mut attacks = 0u64;
-    let rank = square / 8;
-    let file = square % 8;
-    for r in (rank + 1)..8 {
-        let sq = r * 8 + file;
-        attacks |= 1u64 << sq;
-        if (occupancy & (1u64 << sq)) != 0 { break; }
-    }
-    for r in (0..rank).rev() {
-        let sq = r * 8 + file;
-        attacks |= 1u64 << sq;
-        if (occupancy & (1u64 << sq)) != 0 { break; }
-    }
-    for f in (file + 1)..8 {
-        let sq = rank * 8 + f;
-        attacks |= 1u64 << sq;
-        if (occupancy & (1u64 << sq)) != 0 { break; }
-    }
-    for f in (0..file).rev() {
-        let sq = rank * 8 + f;
-        attacks |= 1u64 << sq;
-        if (occupancy & (1u64 << sq)) != 0 { break; }
-    }
-    attacks
+fn generate(
+    rng: &mut Prng,
+    mask_of: fn(usize) -> u64,
+    attacks_of: fn(usize, u64) -> u64,
+) -> Tables {
+    let mut magics = Vec::with_capacity(64);
+    let mut attacks = Vec::new();
+    for square in 0..64 {
+        let mask = mask_of(square);
+        let bits = mask.count_ones();
+        let occupancies: Vec<u64> = (0..1u32 << bits)
+            .map(|index| index_to_occupancy(index, mask))
+            .collect();
+        let reference: Vec<u64> = occupancies
+            .iter()
+            .map(|&occupancy| attacks_of(square, occupancy))
+            .collect();
+        let shift = (64 - bits) as u8;
+        let (magic, table) = find_magic(rng, mask, shift, &occupancies, &reference);
+
+        magics.push(Magic {
+            mask,
+            magic,
+            shift,
+            offset: attacks.len() as u32,
+        });
+        attacks.extend(table);
+    }
+    Tables { magics, attacks }
 }
 
-fn calculate_bishop_attacks(square: usize, occupancy: u64) -> u64 {
-    let mut attacks = 0u64;
-    let rank = square / 8;
-    let file = square % 8;
-    let mut r = rank + 1;
-    let mut f = file + 1;
-    while r < 8 && f < 8 {
-        let sq = r * 8 + f;
-        attacks |= 1u64 << sq;
-        if (occupancy & (1u64 << sq)) != 0 { break; }
-        r += 1; f += 1;
-    }
-    r = rank.wrapping_sub(1);
-    f = file.wrapping_sub(1);
-    while r < 8 && f < 8 {
-        let sq = r * 8 + f;
-        attacks |= 1u64 << sq;
-        if (occupancy & (1u64 << sq)) != 0 { break; }
-        r = r.wrapping_sub(1); f = f.wrapping_sub(1);
-    }
-    r = rank + 1;
-    f = file.wrapping_sub(1);
-    while r < 8 && f < 8 {
-        let sq = r * 8 + f;
-        attacks |= 1u64 << sq;
-        if (occupancy & (1u64 << sq)) != 0 { break; }
-        r += 1; f = f.wrapping_sub(1);
-    }
-    r = rank.wrapping_sub(1);
-    f = file + 1;
-    while r < 8 && f < 8 {
-        let sq = r * 8 + f;
-        attacks |= 1u64 << sq;
-        if (occupancy & (1u64 << sq)) != 0 { break; }
-        r = r.wrapping_sub(1); f += 1;
+/// Tries random candidates until one maps every occupancy to a slot that
+/// no occupancy with different attacks uses, and returns it with its table.
+fn find_magic(
+    rng: &mut Prng,
+    mask: u64,
+    shift: u8,
+    occupancies: &[u64],
+    reference: &[u64],
+) -> (u64, Vec<u64>) {
+    let mut table = vec![0u64; occupancies.len()];
+    // Slot `i` holds an entry of the current attempt when `used[i] == attempt`
+    let mut used = vec![0u32; occupancies.len()];
+
+    for attempt in 1..=100_000_000u32 {
+        let magic = sparse(rng);
+        if (mask.wrapping_mul(magic) & 0xFF00_0000_0000_0000).count_ones() < 6 {
+            continue;
+        }
+        let fits = occupancies
+            .iter()
+            .zip(reference)
+            .all(|(&occupancy, &attacks)| {
+                let index = (occupancy.wrapping_mul(magic) >> shift) as usize;
+                if used[index] == attempt {
+                    table[index] == attacks
+                } else {
+                    used[index] = attempt;
+                    table[index] = attacks;
+                    true
+                }
+            });
+        if fits {
+            for (entry, &stamp) in table.iter_mut().zip(&used) {
+                if stamp != attempt {
+                    *entry = 0;
+                }
+            }
+            return (magic, table);
+        }
     }
-    attacks
+    panic!("no magic found for mask {mask:#018X}");
 }
 
-fn random_u64_fewbits() -> u64 {
-    let mut rng = rand::thread_rng();
-    rng.gen::<u64>() & rng.gen::<u64>() & rng.gen::<u64>()
+fn write_magics(out: &mut impl Write, name: &str, magics: &[Magic]) -> io::Result<()> {
+    writeln!(out, "pub static {name}: [Magic; 64] = [")?;
+    for m in magics {
+        writeln!(
+            out,
+            "    Magic {{ mask: 0x{:016X}, magic: 0x{:016X}, shift: {}, offset: {} }},",
+            m.mask, m.magic, m.shift, m.offset
+        )?;
+    }
+    writeln!(out, "];")
 }
 
-fn test_magic(square: usize, magic: u64, mask: u64, is_rook: bool) -> bool {
-    let n_bits = mask.count_ones();
-    let shift = 64 - n_bits;
-    let size = 1usize << n_bits;
-    let mut table = vec![0u64; size];
-    let mut used = vec![false; size];
-    
-    for i in 0..size {
-        let occ = index_to_occupancy(i as u32, mask);
-        let att = if is_rook { calculate_rook_attacks(square, occ) } else { calculate_bishop_attacks(square, occ) };
-        let idx = ((occ.wrapping_mul(magic)) >> shift) as usize;
-        
-        if used[idx] {
-            if table[idx] != att { return false; }
-        } else {
-            used[idx] = true;
-            table[idx] = att;
+fn write_attacks(out: &mut impl Write, name: &str, attacks: &[u64]) -> io::Result<()> {
+    writeln!(out, "pub static {name}: [u64; {}] = [", attacks.len())?;
+    for row in attacks.chunks(8) {
+        for entry in row {
+            write!(out, "0x{entry:016X},")?;
         }
+        writeln!(out)?;
     }
-    true
+    writeln!(out, "];")
 }
 
-fn find_magic(square: usize, is_rook: bool) -> u64 {
-    let mask = if is_rook { generate_rook_mask(square) } else { generate_bishop_mask(square) };
-    let p = if is_rook { "R" } else { "B" };
-    let r = square / 8;
-    let f = (b'a' + (square % 8) as u8) as char;
-    print!("{}{}{} ", p, f, r + 1);
-    use std::io::Write;
-    std::io::stdout().flush().unwrap();
-    
-    for _ in 0..100_000_000 {
-        let m = random_u64_fewbits();
-        if ((mask.wrapping_mul(m)) & 0xFF00000000000000).count_ones() < 6 { continue; }
-        if test_magic(square, m, mask, is_rook) { return m; }
-    }
-    panic!("No magic found for square {}", square);
-}
+/// Writes `ROOK_MAGICS`, `BISHOP_MAGICS`, `ROOK_ATTACKS` and `BISHOP_ATTACKS`
+/// as statics. The including module has to define `Magic`.
+pub fn write_tables(out: &mut impl Write) -> io::Result<()> {
+    let mut rng = Prng::new(SEED);
+    let rook = generate(&mut rng, rook_mask, rook_attacks);
+    let bishop = generate(&mut rng, bishop_mask, bishop_attacks);
 
-#[derive(Clone, Copy)]
-struct Magic { mask: u64, magic: u64, shift: u8, offset: u32 }
-
-fn main() {
-    println!("Generating...");
-    let t = std::time::Instant::now();
-    
-    let mut rm = Vec::new();
-    let mut off = 0;
-    for sq in 0..64 {
-        let mask = generate_rook_mask(sq);
-        let nb = mask.count_ones();
-        rm.push(Magic { mask, magic: find_magic(sq, true), shift: (64 - nb) as u8, offset: off });
-        off += 1 << nb;
-    }
-    println!("\nRook: {} entries", off);
-    
-    let mut bm = Vec::new();
-    off = 0;
-    for sq in 0..64 {
-        let mask = generate_bishop_mask(sq);
-        let nb = mask.count_ones();
-        bm.push(Magic { mask, magic: find_magic(sq, false), shift: (64 - nb) as u8, offset: off });
-        off += 1 << nb;
-    }
-    println!("\nBishop: {} entries", off);
-    println!("Done in {:.1}m\n", t.elapsed().as_secs_f64() / 60.0);
-    
-    println!("pub static ROOK_MAGICS: [Magic; 64] = [");
-    for m in &rm {
-        println!("    Magic {{ mask: 0x{:016X}, magic: 0x{:016X}, shift: {:2}, offset: {:6} }},", m.mask, m.magic, m.shift, m.offset);
-    }
-    println!("];\n");
-    
-    println!("pub static BISHOP_MAGICS: [Magic; 64] = [");
-    for m in &bm {
-        println!("    Magic {{ mask: 0x{:016X}, magic: 0x{:016X}, shift: {:2}, offset: {:6} }},", m.mask, m.magic, m.shift, m.offset);
-    }
-    println!("];");
+    writeln!(
+        out,
+        "// Generated from magic_gen/generator.rs, do not edit."
+    )?;
+    write_magics(out, "ROOK_MAGICS", &rook.magics)?;
+    write_magics(out, "BISHOP_MAGICS", &bishop.magics)?;
+    write_attacks(out, "ROOK_ATTACKS", &rook.attacks)?;
+    write_attacks(out, "BISHOP_ATTACKS", &bishop.attacks)
 }
@@ -0,0 +1,9 @@
+//! Prints the generated magic tables, the same source `build.rs` writes.
+
+use std::io::{self, BufWriter};
+
+mod generator;
+
+fn main() -> io::Result<()> {
+    generator::write_tables(&mut BufWriter::new(io::stdout().lock()))
+}
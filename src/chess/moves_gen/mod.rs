@@ -1,8 +1,6 @@
-pub mod bishop;
 pub mod king;
 pub mod knight;
+pub mod lookup;
 pub mod magic_bitboards;
 pub mod moves_struct;
 pub mod pawn;
-pub mod queen;
-pub mod rook;
@@ -5,6 +5,7 @@
 // Bit 14:     is_promotion flag (0/1)
 // Bits 15-16: special flags
 
+use crate::chess::square::Square;
 use crate::chess::table::{Board, Type};
 
 const FROM_MASK: u32 = 0x3F; // 000000...111111
@@ -13,15 +14,17 @@ const PROMO_MASK: u32 = 0x3000; // promotion (bits 12–13)
 const IS_PROMO_MASK: u32 = 0x4000; // bit 14
 const FLAGS_MASK: u32 = 0x18000; // bits 15–16
 
+/// A quiet move, promotions to an empty square included.
 pub const FLAG_NORMAL: u8 = 0;
+/// A capture other than en passant.
 pub const FLAG_CAPTURE: u8 = 1;
+/// Castling, encoded as the king's move.
 pub const FLAG_CASTLE: u8 = 2;
+/// An en passant capture, `to` being the square the pawn lands on.
 pub const FLAG_EN_PASSANT: u8 = 3;
 
-pub const PROMOTE_QUEEN: u8 = 0;
-pub const PROMOTE_ROOK: u8 = 1;
-pub const PROMOTE_BISHOP: u8 = 2;
-pub const PROMOTE_KNIGHT: u8 = 3;
+/// The pieces a pawn can promote to, indexed by their code in the move.
+pub const PROMOTION_TYPES: [Type; 4] = [Type::Queen, Type::Rook, Type::Bishop, Type::Knight];
 
 const MVV_LVA: [[i32; 6]; 6] = [
     // P   N    B    R    Q    K
@@ -33,70 +36,86 @@ const MVV_LVA: [[i32; 6]; 6] = [
     [100, 200, 300, 400, 500, 600], // K
 ];
 
+/// A move packed into 32 bits: from and to squares, promotion piece and one
+/// of the `FLAG_*` kinds. Moves only make sense in the position they were
+/// generated for.
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct Moves(u32);
 
 impl Moves {
-    pub fn new(from: u8, to: u8, promotion: u8, flags: u8, is_promotion: bool) -> Self {
+    /// The null move, which no position allows. It stands for "no move" in
+    /// the search tables and in results.
+    pub const NULL: Moves = Moves(0);
+
+    /// A move of the kind given by `flags`, one of the `FLAG_*` constants.
+    #[inline(always)]
+    pub fn new(from: Square, to: Square, flags: u8) -> Self {
         let mut m = 0u32;
 
-        m |= (from as u32) & FROM_MASK;
-        m |= ((to as u32) << 6) & TO_MASK;
-        m |= ((promotion as u32) << 12) & PROMO_MASK;
+        m |= (from.index() as u32) & FROM_MASK;
+        m |= ((to.index() as u32) << 6) & TO_MASK;
         m |= ((flags as u32) << 15) & FLAGS_MASK;
 
-        if is_promotion {
-            m |= IS_PROMO_MASK;
-        }
-
         Moves(m)
     }
 
-    pub fn from(&self) -> u8 {
-        (self.0 & FROM_MASK) as u8
+    /// A promotion to `piece`, which must be one of `PROMOTION_TYPES`.
+    #[inline(always)]
+    pub fn new_promotion(from: Square, to: Square, piece: Type, flags: u8) -> Self {
+        let Some(code) = PROMOTION_TYPES.iter().position(|&p| p == piece) else {
+            panic!("cannot promote to {:?}", piece);
+        };
+        let Moves(m) = Moves::new(from, to, flags);
+        Moves(m | ((code as u32) << 12) | IS_PROMO_MASK)
     }
 
-    pub fn to(&self) -> u8 {
-        ((self.0 & TO_MASK) >> 6) as u8
+    /// The square the piece leaves.
+    #[inline(always)]
+    pub fn from(&self) -> Square {
+        Square::from_index((self.0 & FROM_MASK) as u8)
     }
 
-    pub fn promotion_piece(&self) -> u8 {
-        ((self.0 & PROMO_MASK) >> 12) as u8
+    /// The square the piece lands on; the king's destination for castling.
+    #[inline(always)]
+    pub fn to(&self) -> Square {
+        Square::from_index(((self.0 & TO_MASK) >> 6) as u8)
     }
 
-    pub fn promotion_piece_type(&self) -> Type {
-        match self.promotion_piece() {
-            PROMOTE_BISHOP => Type::Bishop,
-            PROMOTE_QUEEN => Type::Queen,
-            PROMOTE_ROOK => Type::Rook,
-            PROMOTE_KNIGHT => Type::Knight,
-            _ => panic!("promotion_piece_type error"),
-        }
+    /// The piece promoted to, `None` for other moves.
+    #[inline(always)]
+    pub fn promotion(&self) -> Option<Type> {
+        self.is_promotion()
+            .then(|| PROMOTION_TYPES[((self.0 & PROMO_MASK) >> 12) as usize])
     }
 
+    /// Whether a pawn promotes.
     pub fn is_promotion(&self) -> bool {
         (self.0 & IS_PROMO_MASK) != 0
     }
 
+    /// Whether a piece is taken, en passant included.
     pub fn is_capture(&self) -> bool {
         (self.flags() == FLAG_CAPTURE) || (self.flags() == FLAG_EN_PASSANT)
     }
 
+    /// Whether this is castling.
     pub fn is_castle(&self) -> bool {
         self.flags() == FLAG_CASTLE
     }
 
+    /// Whether this is an en passant capture.
     pub fn is_enpassant(&self) -> bool {
         self.flags() == FLAG_EN_PASSANT
     }
 
+    /// The kind of move, one of the `FLAG_*` constants.
     pub fn flags(&self) -> u8 {
         ((self.0 & FLAGS_MASK) >> 15) as u8
     }
 
-    pub fn score(&self, b: &Board) -> i32 {
-        if self.is_promotion() {
-            let promo_value = self.promotion_piece_type().value();
+    pub(crate) fn score(&self, b: &Board) -> i32 {
+        if let Some(promotion) = self.promotion() {
+            let promo_value = promotion.value();
 
             if self.is_capture() {
                 if let Some(victim) = b.get_piece_type_at_square(self.to()) {
@@ -128,31 +147,25 @@ impl Moves {
         0
     }
 
-    #[allow(dead_code)]
+    /// Long algebraic notation as used by UCI, such as `e7e8q`.
     pub fn to_string(&self) -> String {
-        let from = self.index_to_algebraic(self.from());
-        let to = self.index_to_algebraic(self.to());
-
-        let mut s = format!("{}{}", from, to);
-
-        if self.is_promotion() {
-            let p = match self.promotion_piece() {
-                0 => 'q',
-                1 => 'r',
-                2 => 'b',
-                3 => 'n',
-                _ => '?',
-            };
-            s.push(p);
+        let mut s = format!("{}{}", self.from(), self.to());
+        if let Some(piece) = self.promotion() {
+            s.push(piece.to_char());
         }
-
         s
     }
+}
+
+/// The packed encoding, as handed across the C interface.
+impl From<Moves> for u32 {
+    fn from(mv: Moves) -> u32 {
+        mv.0
+    }
+}
 
-    #[allow(dead_code)]
-    fn index_to_algebraic(&self, index: u8) -> String {
-        let file = (b'a' + (index % 8)) as char;
-        let rank = (b'1' + (index / 8)) as char;
-        format!("{}{}", file, rank)
+impl From<u32> for Moves {
+    fn from(raw: u32) -> Moves {
+        Moves(raw)
     }
 }
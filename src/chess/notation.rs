@@ -0,0 +1,83 @@
+//! Coordinate move notation as UCI, CECP and the C API write it: `e2e4`,
+//! `e1g1` for castling and `e7e8q` for promotions.
+
+use crate::chess::moves_gen::moves_struct::{
+    FLAG_CAPTURE, FLAG_CASTLE, FLAG_EN_PASSANT, FLAG_NORMAL, Moves, PROMOTION_TYPES,
+};
+use crate::chess::square::Square;
+use crate::chess::table::{Board, Type};
+
+/// Reads a move in coordinate notation and sets its flags from `board`.
+/// The move is not checked for legality.
+#[cfg_attr(not(any(feature = "cli", feature = "ffi")), allow(dead_code))]
+pub fn parse_move(board: &Board, move_str: &str) -> Option<Moves> {
+    if move_str.len() < 4 {
+        return None;
+    }
+
+    let from = move_str.get(0..2)?.parse::<Square>().ok()?;
+    let to = move_str.get(2..4)?.parse::<Square>().ok()?;
+
+    let mut promotion = None;
+    let mut flags = FLAG_NORMAL;
+
+    if move_str.len() == 5 {
+        let piece = move_str.chars().nth(4).and_then(Type::from_char)?;
+        if !PROMOTION_TYPES.contains(&piece) {
+            return None;
+        }
+        promotion = Some(piece);
+    }
+
+    let moving = board.get_piece_type_at_square(from);
+    if moving == Some(Type::King) && from.file().distance(to.file()) == 2 {
+        flags = FLAG_CASTLE;
+    }
+
+    if flags != FLAG_CASTLE {
+        if board.get_piece_color_at_square(to) == Some(board.get_side().opposite()) {
+            flags = FLAG_CAPTURE;
+        }
+
+        if board.en_passant_square() == Some(to) && moving == Some(Type::Pawn) {
+            flags = FLAG_EN_PASSANT;
+        }
+    }
+
+    Some(match promotion {
+        Some(piece) => Moves::new_promotion(from, to, piece, flags),
+        None => Moves::new(from, to, flags),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::table::START_FEN;
+
+    fn parse(fen: &str, text: &str) -> Option<Moves> {
+        parse_move(&Board::new_from_fen(fen).unwrap(), text)
+    }
+
+    #[test]
+    fn sets_the_flags_from_the_position() {
+        let fen = "r3k2r/1P6/8/3pP3/8/8/8/R3K2R w KQkq d6 0 1";
+
+        let castle = parse(fen, "e1g1").unwrap();
+        assert!(castle.is_castle());
+        assert!(parse(fen, "e5d6").unwrap().is_enpassant());
+        assert!(parse(fen, "a1a8").unwrap().is_capture());
+
+        let promotion = parse(fen, "b7a8n").unwrap();
+        assert!(promotion.is_capture());
+        assert_eq!(promotion.promotion(), Some(Type::Knight));
+        assert_eq!(parse(fen, "e1e2").unwrap().flags(), FLAG_NORMAL);
+    }
+
+    #[test]
+    fn rejects_malformed_moves() {
+        for text in ["", "e2", "e2e9", "i2e4", "b7b8k", "b7b8x"] {
+            assert!(parse(START_FEN, text).is_none(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+//! xorshift64* generator behind the Zobrist keys and the magic number search.
+//! It has no dependencies so that `build.rs` can include it, and it is
+//! written out here rather than taken from `rand` so that the numbers never
+//! change with a dependency update.
+
+pub struct Prng(u64);
+
+impl Prng {
+    pub fn new(seed: u64) -> Self {
+        // An all zero state would only ever produce zeros
+        Prng(if seed == 0 {
+            0x2545_F491_4F6C_DD1D
+        } else {
+            seed
+        })
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
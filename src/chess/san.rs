@@ -0,0 +1,290 @@
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::chess::square::{File, Rank, Square};
+use crate::chess::table::{Board, Type};
+
+fn piece_letter(piece_type: Type) -> char {
+    match piece_type {
+        Type::Knight => 'N',
+        Type::Bishop => 'B',
+        Type::Rook => 'R',
+        Type::Queen => 'Q',
+        Type::King => 'K',
+        _ => ' ',
+    }
+}
+
+impl Board {
+    /// Formats a legal move in Standard Algebraic Notation (`Nbd7`, `exd6`,
+    /// `e8=Q+`, `O-O-O#`). The move is not checked for legality.
+    pub fn move_to_san(&self, mv: &Moves) -> String {
+        let mut board = *self;
+        let turn = board.get_side();
+        let mut buffer = Vec::with_capacity(64);
+        let legal = board.get_legal_moves(turn, &mut buffer);
+
+        let from = mv.from();
+        let to = mv.to();
+        let piece_type = self.get_piece_type_at_square(from).unwrap_or(Type::Pawn);
+        let mut san = String::new();
+
+        if mv.is_castle() {
+            san.push_str(if to.file() == File::C { "O-O-O" } else { "O-O" });
+        } else if piece_type == Type::Pawn {
+            if mv.is_capture() {
+                san.push(from.file().to_char());
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+            if let Some(promotion) = mv.promotion() {
+                san.push('=');
+                san.push(piece_letter(promotion));
+            }
+        } else {
+            san.push(piece_letter(piece_type));
+
+            // Other pieces of the same type that can reach the same square
+            let rivals: Vec<Square> = legal
+                .iter()
+                .filter(|other| {
+                    other.to() == to
+                        && other.from() != from
+                        && self.get_piece_type_at_square(other.from()) == Some(piece_type)
+                })
+                .map(|other| other.from())
+                .collect();
+
+            if !rivals.is_empty() {
+                if rivals.iter().all(|sq| sq.file() != from.file()) {
+                    san.push(from.file().to_char());
+                } else if rivals.iter().all(|sq| sq.rank() != from.rank()) {
+                    san.push(from.rank().to_char());
+                } else {
+                    san.push_str(&from.to_string());
+                }
+            }
+
+            if mv.is_capture() {
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+        }
+
+        let undo = board.make_move_with_undo(mv);
+        let opponent = turn.opposite();
+        if board.is_king_in_check(opponent) {
+            if board.get_legal_moves(opponent, &mut buffer).is_empty() {
+                san.push('#');
+            } else {
+                san.push('+');
+            }
+        }
+        board.unmake_move(mv, undo);
+
+        san
+    }
+
+    /// Resolves a SAN move against the legal moves of the position. Check and
+    /// annotation suffixes are optional, castling may be written with zeros,
+    /// and promotions are accepted with or without `=`.
+    pub fn parse_san(&self, san: &str) -> Result<Moves, String> {
+        let turn = self.get_side();
+        let mut buffer = Vec::with_capacity(64);
+        let legal = self.get_legal_moves(turn, &mut buffer);
+
+        let text = san.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        match text {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let long = text.len() > 3;
+                return legal
+                    .iter()
+                    .find(|mv| mv.is_castle() && (mv.to().file() == File::C) == long)
+                    .copied()
+                    .ok_or_else(|| format!("Illegal castling: {}", san));
+            }
+            _ => {}
+        }
+
+        let mut chars: Vec<char> = text
+            .chars()
+            .filter(|&c| c != 'x' && c != '-' && c != ':')
+            .collect();
+
+        let mut promotion = None;
+        if let Some(&last) = chars.last()
+            && "NBRQnbrq".contains(last)
+            && chars.len() > 2
+        {
+            promotion = Some(match last.to_ascii_uppercase() {
+                'N' => Type::Knight,
+                'B' => Type::Bishop,
+                'R' => Type::Rook,
+                _ => Type::Queen,
+            });
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        let piece_type = match chars.first() {
+            Some('N') => Type::Knight,
+            Some('B') => Type::Bishop,
+            Some('R') => Type::Rook,
+            Some('Q') => Type::Queen,
+            Some('K') => Type::King,
+            Some(_) => Type::Pawn,
+            None => return Err(format!("Empty move: {}", san)),
+        };
+        if piece_type != Type::Pawn {
+            chars.remove(0);
+        }
+
+        if chars.len() < 2 {
+            return Err(format!("Invalid SAN: {}", san));
+        }
+        let (hint, target) = chars.split_at(chars.len() - 2);
+        let (Some(file), Some(rank)) = (File::from_char(target[0]), Rank::from_char(target[1]))
+        else {
+            return Err(format!("Invalid SAN: {}", san));
+        };
+        let to = Square::new(file, rank);
+
+        let mut candidates = legal.iter().filter(|mv| {
+            let from = mv.from();
+            mv.to() == to
+                && !mv.is_castle()
+                && self.get_piece_type_at_square(from) == Some(piece_type)
+                && hint.iter().all(|&c| {
+                    File::from_char(c) == Some(from.file())
+                        || Rank::from_char(c) == Some(from.rank())
+                })
+                && mv.promotion() == promotion
+        });
+
+        let mv = candidates
+            .next()
+            .copied()
+            .ok_or_else(|| format!("Illegal move: {}", san))?;
+        if candidates.next().is_some() {
+            return Err(format!("Ambiguous move: {}", san));
+        }
+        Ok(mv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::notation::parse_move;
+    use crate::chess::table::START_FEN;
+
+    fn san(fen: &str, text: &str) -> String {
+        let board = Board::new_from_fen(fen).unwrap();
+        board.move_to_san(&parse_move(&board, text).unwrap())
+    }
+
+    fn parse(fen: &str, san: &str) -> Result<Moves, String> {
+        Board::new_from_fen(fen).unwrap().parse_san(san)
+    }
+
+    #[test]
+    fn disambiguates_by_file_rank_and_square() {
+        let rooks = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1";
+        assert_eq!(san(rooks, "a1d1"), "Rad1");
+        assert_eq!(san(rooks, "h1d1"), "Rhd1");
+
+        let stacked = "4k3/8/8/R7/8/8/4K3/R7 w - - 0 1";
+        assert_eq!(san(stacked, "a1a3"), "R1a3");
+        assert_eq!(san(stacked, "a5a3"), "R5a3");
+
+        let queens = "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1";
+        assert_eq!(san(queens, "a1b2"), "Qa1b2");
+        assert_eq!(san(queens, "a3b2"), "Q3b2");
+        assert_eq!(san(queens, "c1b2"), "Qcb2");
+
+        assert_eq!(parse(rooks, "Rad1").unwrap().from(), Square::A1);
+        assert_eq!(parse(rooks, "Rhd1").unwrap().from(), Square::H1);
+        assert_eq!(parse(stacked, "R5a3").unwrap().from(), Square::A5);
+        assert_eq!(parse(queens, "Qa1b2").unwrap().from(), Square::A1);
+        assert_eq!(parse(queens, "Q3b2").unwrap().from(), Square::A3);
+    }
+
+    #[test]
+    fn marks_check_and_mate() {
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"), "Ra8#");
+        assert_eq!(san(START_FEN, "g1f3"), "Nf3");
+    }
+
+    #[test]
+    fn formats_and_parses_castling() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1";
+        assert_eq!(san(fen, "e1g1"), "O-O");
+        assert_eq!(san(fen, "e1c1"), "O-O-O");
+        for text in ["O-O", "0-0", "O-O+"] {
+            assert_eq!(parse(fen, text).unwrap().to(), Square::G1, "{text}");
+        }
+        for text in ["O-O-O", "0-0-0"] {
+            assert_eq!(parse(fen, text).unwrap().to(), Square::C1, "{text}");
+        }
+    }
+
+    #[test]
+    fn formats_and_parses_promotions() {
+        let fen = "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(san(fen, "b7b8q"), "b8=Q+");
+        assert_eq!(san(fen, "b7b8n"), "b8=N");
+        assert_eq!(san(fen, "b7a8q"), "bxa8=Q+");
+
+        for text in ["b8=Q", "b8Q", "b8=Q+", "b8q"] {
+            assert_eq!(
+                parse(fen, text).unwrap().promotion(),
+                Some(Type::Queen),
+                "{text}"
+            );
+        }
+        assert_eq!(parse(fen, "b8=N").unwrap().promotion(), Some(Type::Knight));
+        let capture = parse(fen, "bxa8=R").unwrap();
+        assert!(capture.is_capture());
+        assert_eq!(capture.promotion(), Some(Type::Rook));
+    }
+
+    #[test]
+    fn formats_en_passant_as_a_pawn_capture() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(san(fen, "e5d6"), "exd6");
+        assert!(parse(fen, "exd6").unwrap().is_enpassant());
+    }
+
+    #[test]
+    fn rejects_ambiguous_and_illegal_moves() {
+        let rooks = "4k3/8/8/8/8/8/4K3/R6R w - - 0 1";
+        assert!(parse(rooks, "Rd1").unwrap_err().starts_with("Ambiguous"));
+        assert!(parse("4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1", "Qb2").is_err());
+
+        let promotion = "r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1";
+        assert!(parse(promotion, "b8").is_err());
+        assert!(parse(promotion, "b8=K").is_err());
+
+        for text in ["", "e5", "Nf4", "O-O", "Ke2", "Zz9", "e9", "x"] {
+            assert!(parse(START_FEN, text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn parses_its_own_output() {
+        for fen in [
+            START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1",
+        ] {
+            let board = Board::new_from_fen(fen).unwrap();
+            for mv in board.legal_moves() {
+                let text = board.move_to_san(&mv);
+                assert_eq!(board.parse_san(&text), Ok(mv), "{fen} {text}");
+            }
+        }
+    }
+}
@@ -0,0 +1,418 @@
+//! Typed board coordinates. Squares count from a1 = 0 to h8 = 63, file by
+//! file within a rank, which is the bit order of every bitboard.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::chess::table::Color;
+
+/// A file, a to h.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct File(u8);
+
+// The named constants need no further description
+#[allow(missing_docs)]
+impl File {
+    pub const A: File = File(0);
+    pub const B: File = File(1);
+    pub const C: File = File(2);
+    pub const D: File = File(3);
+    pub const E: File = File(4);
+    pub const F: File = File(5);
+    pub const G: File = File(6);
+    pub const H: File = File(7);
+}
+
+impl File {
+    /// All files, a first.
+    pub const ALL: [File; 8] = [
+        File::A,
+        File::B,
+        File::C,
+        File::D,
+        File::E,
+        File::F,
+        File::G,
+        File::H,
+    ];
+
+    /// The file with the given index, a = 0, `None` from 8 on.
+    pub const fn new(index: u8) -> Option<File> {
+        if index < 8 { Some(File(index)) } else { None }
+    }
+
+    /// Index from a = 0 to h = 7.
+    #[inline(always)]
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
+    /// Parses a lowercase file letter.
+    pub fn from_char(c: char) -> Option<File> {
+        match c {
+            'a'..='h' => Some(File(c as u8 - b'a')),
+            _ => None,
+        }
+    }
+
+    /// The lowercase file letter.
+    pub fn to_char(self) -> char {
+        (b'a' + self.0) as char
+    }
+
+    /// The file `delta` files to the right, `None` past the edge.
+    pub fn offset(self, delta: i8) -> Option<File> {
+        let index = self.0 as i8 + delta;
+        if (0..8).contains(&index) {
+            Some(File(index as u8))
+        } else {
+            None
+        }
+    }
+
+    /// How many files apart the two are.
+    pub fn distance(self, other: File) -> u8 {
+        self.0.abs_diff(other.0)
+    }
+
+    /// This file and the ones next to it.
+    pub fn with_neighbours(self) -> impl Iterator<Item = File> {
+        (-1..=1).filter_map(move |delta| self.offset(delta))
+    }
+
+    /// Bitboard of the whole file.
+    #[inline(always)]
+    pub const fn mask(self) -> u64 {
+        0x0101010101010101 << self.0
+    }
+}
+
+/// A rank, first to eighth.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Rank(u8);
+
+// The named constants need no further description
+#[allow(missing_docs)]
+impl Rank {
+    pub const FIRST: Rank = Rank(0);
+    pub const SECOND: Rank = Rank(1);
+    pub const THIRD: Rank = Rank(2);
+    pub const FOURTH: Rank = Rank(3);
+    pub const FIFTH: Rank = Rank(4);
+    pub const SIXTH: Rank = Rank(5);
+    pub const SEVENTH: Rank = Rank(6);
+    pub const EIGHTH: Rank = Rank(7);
+}
+
+impl Rank {
+    /// All ranks, the first one first.
+    pub const ALL: [Rank; 8] = [
+        Rank::FIRST,
+        Rank::SECOND,
+        Rank::THIRD,
+        Rank::FOURTH,
+        Rank::FIFTH,
+        Rank::SIXTH,
+        Rank::SEVENTH,
+        Rank::EIGHTH,
+    ];
+
+    /// The rank with the given index, first = 0, `None` from 8 on.
+    pub const fn new(index: u8) -> Option<Rank> {
+        if index < 8 { Some(Rank(index)) } else { None }
+    }
+
+    /// Index from first = 0 to eighth = 7.
+    #[inline(always)]
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
+    /// Parses a rank digit.
+    pub fn from_char(c: char) -> Option<Rank> {
+        match c {
+            '1'..='8' => Some(Rank(c as u8 - b'1')),
+            _ => None,
+        }
+    }
+
+    /// The rank digit.
+    pub fn to_char(self) -> char {
+        (b'1' + self.0) as char
+    }
+
+    /// The rank `delta` ranks towards the eighth, `None` past the edge.
+    pub fn offset(self, delta: i8) -> Option<Rank> {
+        let index = self.0 as i8 + delta;
+        if (0..8).contains(&index) {
+            Some(Rank(index as u8))
+        } else {
+            None
+        }
+    }
+
+    /// How many ranks apart the two are.
+    pub fn distance(self, other: Rank) -> u8 {
+        self.0.abs_diff(other.0)
+    }
+
+    /// The rank as seen by `color`, so the first rank is always its own back rank.
+    pub fn relative_to(self, color: Color) -> Rank {
+        color.match_color(self, Rank(7 - self.0))
+    }
+
+    /// Bitboard of the whole rank.
+    #[inline(always)]
+    pub const fn mask(self) -> u64 {
+        0xff << (self.0 * 8)
+    }
+}
+
+/// One of the 64 squares.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Square(u8);
+
+// The named constants need no further description
+#[allow(missing_docs)]
+impl Square {
+    pub const A1: Square = Square(0);
+    pub const B1: Square = Square(1);
+    pub const C1: Square = Square(2);
+    pub const D1: Square = Square(3);
+    pub const E1: Square = Square(4);
+    pub const F1: Square = Square(5);
+    pub const G1: Square = Square(6);
+    pub const H1: Square = Square(7);
+    pub const A2: Square = Square(8);
+    pub const B2: Square = Square(9);
+    pub const C2: Square = Square(10);
+    pub const D2: Square = Square(11);
+    pub const E2: Square = Square(12);
+    pub const F2: Square = Square(13);
+    pub const G2: Square = Square(14);
+    pub const H2: Square = Square(15);
+    pub const A3: Square = Square(16);
+    pub const B3: Square = Square(17);
+    pub const C3: Square = Square(18);
+    pub const D3: Square = Square(19);
+    pub const E3: Square = Square(20);
+    pub const F3: Square = Square(21);
+    pub const G3: Square = Square(22);
+    pub const H3: Square = Square(23);
+    pub const A4: Square = Square(24);
+    pub const B4: Square = Square(25);
+    pub const C4: Square = Square(26);
+    pub const D4: Square = Square(27);
+    pub const E4: Square = Square(28);
+    pub const F4: Square = Square(29);
+    pub const G4: Square = Square(30);
+    pub const H4: Square = Square(31);
+    pub const A5: Square = Square(32);
+    pub const B5: Square = Square(33);
+    pub const C5: Square = Square(34);
+    pub const D5: Square = Square(35);
+    pub const E5: Square = Square(36);
+    pub const F5: Square = Square(37);
+    pub const G5: Square = Square(38);
+    pub const H5: Square = Square(39);
+    pub const A6: Square = Square(40);
+    pub const B6: Square = Square(41);
+    pub const C6: Square = Square(42);
+    pub const D6: Square = Square(43);
+    pub const E6: Square = Square(44);
+    pub const F6: Square = Square(45);
+    pub const G6: Square = Square(46);
+    pub const H6: Square = Square(47);
+    pub const A7: Square = Square(48);
+    pub const B7: Square = Square(49);
+    pub const C7: Square = Square(50);
+    pub const D7: Square = Square(51);
+    pub const E7: Square = Square(52);
+    pub const F7: Square = Square(53);
+    pub const G7: Square = Square(54);
+    pub const H7: Square = Square(55);
+    pub const A8: Square = Square(56);
+    pub const B8: Square = Square(57);
+    pub const C8: Square = Square(58);
+    pub const D8: Square = Square(59);
+    pub const E8: Square = Square(60);
+    pub const F8: Square = Square(61);
+    pub const G8: Square = Square(62);
+    pub const H8: Square = Square(63);
+}
+
+impl Square {
+    /// The square on `file` and `rank`.
+    #[inline(always)]
+    pub const fn new(file: File, rank: Rank) -> Square {
+        Square(rank.0 * 8 + file.0)
+    }
+
+    /// The square with the given bit index, which must be below 64.
+    #[inline(always)]
+    pub const fn from_index(index: u8) -> Square {
+        debug_assert!(index < 64);
+        Square(index)
+    }
+
+    /// The lowest set square of a non-empty bitboard.
+    #[inline(always)]
+    pub const fn from_lsb(bb: u64) -> Square {
+        debug_assert!(bb != 0);
+        Square(bb.trailing_zeros() as u8)
+    }
+
+    /// Index from a1 = 0 to h8 = 63, the bit of the square in a bitboard.
+    #[inline(always)]
+    pub const fn index(self) -> u8 {
+        self.0
+    }
+
+    /// The file the square is on.
+    #[inline(always)]
+    pub const fn file(self) -> File {
+        File(self.0 % 8)
+    }
+
+    /// The rank the square is on.
+    #[inline(always)]
+    pub const fn rank(self) -> Rank {
+        Rank(self.0 / 8)
+    }
+
+    /// Bitboard with only this square set.
+    #[inline(always)]
+    pub const fn bit(self) -> u64 {
+        1u64 << self.0
+    }
+
+    /// The square `files` to the right and `ranks` up, `None` off the board.
+    pub fn offset(self, files: i8, ranks: i8) -> Option<Square> {
+        Some(Square::new(
+            self.file().offset(files)?,
+            self.rank().offset(ranks)?,
+        ))
+    }
+
+    /// The same square seen from the other side, a1 <-> a8.
+    pub const fn flip_rank(self) -> Square {
+        Square(self.0 ^ 56)
+    }
+
+    /// The same square mirrored left to right, a1 <-> h1.
+    pub const fn flip_file(self) -> Square {
+        Square(self.0 ^ 7)
+    }
+
+    /// Number of king steps between the two squares.
+    pub fn distance(self, other: Square) -> u8 {
+        self.file()
+            .distance(other.file())
+            .max(self.rank().distance(other.rank()))
+    }
+
+    /// Files plus ranks between the two squares.
+    pub fn manhattan_distance(self, other: Square) -> u8 {
+        self.file().distance(other.file()) + self.rank().distance(other.rank())
+    }
+
+    /// All 64 squares, a1 first.
+    pub fn iter() -> impl DoubleEndedIterator<Item = Square> {
+        (0..64).map(Square)
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.file().to_char(), self.rank().to_char())
+    }
+}
+
+/// Parses algebraic coordinates such as `e4`.
+impl FromStr for Square {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Square, String> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(file), Some(rank), None) => {
+                match (File::from_char(file), Rank::from_char(rank)) {
+                    (Some(file), Some(rank)) => Ok(Square::new(file, rank)),
+                    _ => Err(format!("Invalid square: {}", s)),
+                }
+            }
+            _ => Err(format!("Invalid square: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<u8> for Square {
+    type Error = String;
+
+    fn try_from(index: u8) -> Result<Square, String> {
+        if index < 64 {
+            Ok(Square(index))
+        } else {
+            Err(format!("Square index out of range: {}", index))
+        }
+    }
+}
+
+impl From<Square> for u8 {
+    fn from(square: Square) -> u8 {
+        square.0
+    }
+}
+
+impl From<Square> for usize {
+    fn from(square: Square) -> usize {
+        square.0 as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_and_parse_round_trip() {
+        for square in Square::iter() {
+            let text = square.to_string();
+            assert_eq!(text.parse::<Square>(), Ok(square), "{text}");
+        }
+        assert_eq!(Square::A1.to_string(), "a1");
+        assert_eq!(Square::H8.to_string(), "h8");
+        assert_eq!("e4".parse::<Square>(), Ok(Square::E4));
+    }
+
+    #[test]
+    fn rejects_squares_off_the_board() {
+        for text in ["", "i1", "a9", "a0", "e", "e44", "E4", "4e", " e4"] {
+            assert!(text.parse::<Square>().is_err(), "{text:?}");
+        }
+        assert!(Square::try_from(64).is_err());
+        assert_eq!(Square::try_from(63), Ok(Square::H8));
+    }
+
+    #[test]
+    fn index_file_and_rank_agree() {
+        for square in Square::iter() {
+            assert_eq!(Square::new(square.file(), square.rank()), square);
+            assert_eq!(Square::from_index(square.index()), square);
+            assert_eq!(Square::from_lsb(square.bit()), square);
+        }
+        assert_eq!(Square::E4.file(), File::E);
+        assert_eq!(Square::E4.rank(), Rank::FOURTH);
+    }
+
+    #[test]
+    fn offsets_stop_at_the_edge() {
+        assert_eq!(Square::A1.offset(1, 2), Some(Square::B3));
+        assert_eq!(Square::A1.offset(-1, 0), None);
+        assert_eq!(Square::H8.offset(0, 1), None);
+        assert_eq!(Square::A1.flip_rank(), Square::A8);
+        assert_eq!(Square::A1.flip_file(), Square::H1);
+        assert_eq!(Square::A1.distance(Square::H8), 7);
+        assert_eq!(Square::A1.manhattan_distance(Square::H8), 14);
+    }
+}
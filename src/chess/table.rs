@@ -1,28 +1,30 @@
 use crate::bitboard::bitboard::Bitboard;
-use crate::chess::moves_gen::magic_bitboards::{bishop_moves, rook_moves};
 use crate::chess::moves_gen::moves_struct::{
-    FLAG_CAPTURE, FLAG_CASTLE, FLAG_EN_PASSANT, FLAG_NORMAL, Moves, PROMOTE_BISHOP, PROMOTE_KNIGHT,
-    PROMOTE_QUEEN, PROMOTE_ROOK,
+    FLAG_CAPTURE, FLAG_CASTLE, FLAG_EN_PASSANT, FLAG_NORMAL, Moves, PROMOTION_TYPES,
 };
-use crate::chess::moves_gen::{self};
+use crate::chess::moves_gen::{self, lookup};
+use crate::chess::square::{File, Rank, Square};
 use crate::chess::zobrist::ZOBRIST;
-use either::Either;
 use std::cmp::PartialEq;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-const FIRSTRANK: u64 = 0xff;
-const LASTRANK: u64 = 0xff00000000000000;
-pub const FILE_A: u64 = 0x0101010101010101;
-pub const FILE_H: u64 = 0x8080808080808080;
+const FIRSTRANK: Bitboard = Bitboard::rank(Rank::FIRST);
+const LASTRANK: Bitboard = Bitboard::rank(Rank::EIGHTH);
+/// FEN of the initial position.
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
+/// The side a piece belongs to or that is to move.
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Color {
+    #[allow(missing_docs)]
     White,
+    #[allow(missing_docs)]
     Black,
 }
 
 impl Color {
+    /// `white` for White and `black` for Black.
     pub fn match_color<T>(&self, white: T, black: T) -> T {
         match self {
             Color::White => white,
@@ -30,6 +32,7 @@ impl Color {
         }
     }
 
+    /// The other side.
     pub fn opposite(&self) -> Color {
         match self {
             Color::White => Color::Black,
@@ -40,12 +43,14 @@ impl Color {
 
 // TODO: Move in moves_struct
 #[derive(Clone, Copy)]
-pub struct NullMoveUndo {
-    enpassant: u64,
+pub(crate) struct NullMoveUndo {
+    enpassant: Bitboard,
     hash: u64,
 }
 
+/// A kind of piece.
 #[derive(Debug, EnumIter, Copy, Clone, PartialEq)]
+#[allow(missing_docs)]
 pub enum Type {
     Pawn,
     Bishop,
@@ -53,18 +58,19 @@ pub enum Type {
     Rook,
     Queen,
     King,
+    /// Pieces of every type, for `Board::get_pieces`.
     Any,
 }
 
 impl Type {
-    pub const PAWN_VALUE: i32 = 100;
-    pub const KNIGHT_VALUE: i32 = 320;
-    pub const BISHOP_VALUE: i32 = 330;
-    pub const ROOK_VALUE: i32 = 500;
-    pub const QUEEN_VALUE: i32 = 999;
-    pub const KING_VALUE: i32 = 20000;
-
-    pub fn value(self) -> i32 {
+    pub(crate) const PAWN_VALUE: i32 = 100;
+    pub(crate) const KNIGHT_VALUE: i32 = 320;
+    pub(crate) const BISHOP_VALUE: i32 = 330;
+    pub(crate) const ROOK_VALUE: i32 = 500;
+    pub(crate) const QUEEN_VALUE: i32 = 999;
+    pub(crate) const KING_VALUE: i32 = 20000;
+
+    pub(crate) fn value(self) -> i32 {
         match self {
             Type::Pawn => Self::PAWN_VALUE,
             Type::Knight => Self::KNIGHT_VALUE,
@@ -76,7 +82,7 @@ impl Type {
         }
     }
 
-    pub fn id(self) -> u8 {
+    pub(crate) fn id(self) -> u8 {
         match self {
             Type::Pawn => 0,
             Type::Knight => 1,
@@ -88,7 +94,34 @@ impl Type {
         }
     }
 
-    pub fn from_id(id: u8) -> Option<Type> {
+    /// Lowercase FEN letter.
+    pub fn to_char(self) -> char {
+        match self {
+            Type::Pawn => 'p',
+            Type::Knight => 'n',
+            Type::Bishop => 'b',
+            Type::Rook => 'r',
+            Type::Queen => 'q',
+            Type::King => 'k',
+            Type::Any => '?',
+        }
+    }
+
+    /// Reads a FEN letter of either case.
+    pub fn from_char(c: char) -> Option<Type> {
+        match c.to_ascii_lowercase() {
+            'p' => Some(Type::Pawn),
+            'n' => Some(Type::Knight),
+            'b' => Some(Type::Bishop),
+            'r' => Some(Type::Rook),
+            'q' => Some(Type::Queen),
+            'k' => Some(Type::King),
+            _ => None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn from_id(id: u8) -> Option<Type> {
         match id {
             0 => Some(Type::Pawn),
             1 => Some(Type::Knight),
@@ -101,83 +134,198 @@ impl Type {
     }
 }
 
+/// A piece on the board, as stored in `Board`'s mailbox.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Piece {
+    #[allow(missing_docs)]
+    pub color: Color,
+    #[allow(missing_docs)]
+    pub piece_type: Type,
+}
+
+impl Piece {
+    /// A piece of `piece_type`, which must not be `Type::Any`.
+    pub fn new(color: Color, piece_type: Type) -> Self {
+        Piece { color, piece_type }
+    }
+
+    /// FEN letter, uppercase for white.
+    pub fn from_char(c: char) -> Option<Piece> {
+        let piece_type = Type::from_char(c)?;
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        Some(Piece::new(color, piece_type))
+    }
+
+    /// FEN letter, uppercase for white.
+    pub fn to_char(self) -> char {
+        let letter = self.piece_type.to_char();
+        self.color.match_color(letter.to_ascii_uppercase(), letter)
+    }
+}
+
 pub enum Side {
     Long,
     Short,
 }
 
+/// Which legal moves `Board::generate_legal` produces.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GenType {
+    All,
+    /// Captures, en passant and promotions.
+    Captures,
+    /// Every other move, castling included.
+    Quiets,
+}
+
+/// Whether the game goes on and, if not, why it ended.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameStatus {
+    /// The side to move has a legal move and no draw rule applies.
+    Ongoing,
+    /// The side to move is in check without a legal move and has lost.
+    Checkmate,
+    /// The side to move is not in check but has no legal move.
+    Stalemate,
+    /// The position occurred for the third time.
+    ThreefoldRepetition,
+    /// 100 halfmoves passed without a capture or pawn move.
+    FiftyMove,
+    /// Neither side can mate any more.
+    InsufficientMaterial,
+}
+
 struct MoveContext {
-    own_pieces: u64,
-    enemy_pieces: u64,
-    occupied: u64,
+    own_pieces: Bitboard,
+    enemy_pieces: Bitboard,
+    occupied: Bitboard,
+    king_square: Square,
+    checkers: Bitboard,
+    /// Squares a piece other than the king may move to: everything when not
+    /// in check, the checker and the squares up to it in single check and
+    /// nothing in double check.
+    check_mask: Bitboard,
+    /// Own pieces that may only move along the line to their king.
+    pinned: Bitboard,
+    /// Squares attacked by the enemy, seen through the own king.
+    king_danger: Bitboard,
+    legal: bool,
+    gen_type: GenType,
 }
 
+impl MoveContext {
+    #[inline(always)]
+    fn allowed(&self, from: Square) -> Bitboard {
+        if self.pinned.contains(from) {
+            self.check_mask & lookup::line(self.king_square, from)
+        } else {
+            self.check_mask
+        }
+    }
+
+    #[inline(always)]
+    fn targets(&self, from: Square) -> Bitboard {
+        self.allowed(from) & self.filter()
+    }
+
+    #[inline(always)]
+    fn filter(&self) -> Bitboard {
+        match self.gen_type {
+            GenType::All => Bitboard::full(),
+            GenType::Captures => self.enemy_pieces,
+            GenType::Quiets => !self.occupied,
+        }
+    }
+
+    /// Pushes to the last rank count as captures, so promotions come with them.
+    #[inline(always)]
+    fn pawn_targets(&self, from: Square) -> Bitboard {
+        let promotions = FIRSTRANK | LASTRANK;
+        let filter = match self.gen_type {
+            GenType::All => Bitboard::full(),
+            GenType::Captures => self.enemy_pieces | promotions,
+            GenType::Quiets => !self.occupied & !promotions,
+        };
+        self.allowed(from) & filter
+    }
+}
+
+/// Adds a move from `from` to every square in `targets`, flagged as a
+/// capture where an enemy piece stands.
+#[inline(always)]
+fn push_moves(from: Square, targets: Bitboard, enemy: Bitboard, buffer: &mut Vec<Moves>) {
+    for to in targets {
+        let flag = if enemy.contains(to) {
+            FLAG_CAPTURE
+        } else {
+            FLAG_NORMAL
+        };
+        buffer.push(Moves::new(from, to, flag));
+    }
+}
+
+/// A chess position: the pieces, side to move, castling rights, en passant
+/// square, move counters and Zobrist hash.
 #[derive(Clone, Copy)]
 pub struct Board {
-    pub pawn: Bitboard,
-    pub bishop: Bitboard,
-    pub knight: Bitboard,
-    pub rook: Bitboard,
-    pub queen: Bitboard,
-    pub king: Bitboard,
-    pub white: Bitboard,
-    pub black: Bitboard,
-    pub is_white_turn: bool,
-    pub white_rook_long_side: bool,
-    pub white_rook_short_side: bool,
-    pub black_rook_long_side: bool,
-    pub black_rook_short_side: bool,
+    pawn: Bitboard,
+    bishop: Bitboard,
+    knight: Bitboard,
+    rook: Bitboard,
+    queen: Bitboard,
+    king: Bitboard,
+    white: Bitboard,
+    black: Bitboard,
+    is_white_turn: bool,
+    white_rook_long_side: bool,
+    white_rook_short_side: bool,
+    black_rook_long_side: bool,
+    black_rook_short_side: bool,
     white_king: bool,
     black_king: bool,
-    pub enpassant: Bitboard,
+    enpassant: Bitboard,
     white_has_castled: bool,
     black_has_castled: bool,
+    halfmove_clock: u16,
+    fullmove_number: u16,
     hash: u64,
+    /// The piece on each square, kept in step with the bitboards.
+    mailbox: [Option<Piece>; 64],
 }
 
 #[derive(Clone, Copy)]
-pub struct UndoInfo {
-    moving_piece_type: u8,
-    captured_piece_type: u8,
-    captured_on_white: bool,
+pub(crate) struct UndoInfo {
+    captured: Option<Piece>,
     white_rook_long_side: bool,
     white_rook_short_side: bool,
     black_rook_long_side: bool,
     black_rook_short_side: bool,
     white_king: bool,
     black_king: bool,
-    old_enpassant: u64,
+    old_enpassant: Bitboard,
     was_white_turn: bool,
+    old_halfmove_clock: u16,
     old_hash: u64,
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Board {
+    /// The initial position.
     pub fn new() -> Self {
-        let mut b = Board {
-            pawn: Bitboard::new(0xff00000000ff00),
-            bishop: Bitboard::new(0x2400000000000024),
-            knight: Bitboard::new(0x4200000000000042),
-            rook: Bitboard::new(0x8100000000000081),
-            queen: Bitboard::new(0x800000000000008),
-            king: Bitboard::new(0x1000000000000010),
-            white: Bitboard::new(0xffff),
-            black: Bitboard::new(0xffff000000000000),
-            is_white_turn: true,
-            white_rook_long_side: true,
-            white_rook_short_side: true,
-            black_rook_long_side: true,
-            black_rook_short_side: true,
-            white_king: true,
-            black_king: true,
-            white_has_castled: false,
-            black_has_castled: false,
-            enpassant: Bitboard::new(0),
-            hash: 0,
-        };
-        b.hash = ZOBRIST.compute_hash(&b);
-        b
+        Self::new_from_fen(START_FEN).expect("start position is valid")
     }
 
+    /// Parses a FEN string. Positions without exactly one king per side or
+    /// with the side not to move in check are rejected.
     pub fn new_from_fen(fen: &str) -> Result<Self, String> {
         let mut board = Board {
             pawn: Bitboard::new(0),
@@ -198,7 +346,10 @@ impl Board {
             white_has_castled: false,
             black_has_castled: false,
             enpassant: Bitboard::new(0),
+            halfmove_clock: 0,
+            fullmove_number: 1,
             hash: 0,
+            mailbox: [None; 64],
         };
 
         let parts: Vec<&str> = fen.split(' ').collect();
@@ -208,76 +359,38 @@ impl Board {
 
         let piece_placement = parts[0];
 
-        let mut rank_start_index: i32 = 56;
+        let ranks: Vec<&str> = piece_placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err("FEN piece placement must have 8 ranks.".to_string());
+        }
 
-        for rank_str in piece_placement.split('/') {
-            let mut file_index: i32 = 0;
+        for (rank, rank_str) in Rank::ALL.into_iter().rev().zip(ranks) {
+            let mut file_index: u8 = 0;
 
             for piece_char in rank_str.chars() {
-                if piece_char.is_digit(10) {
-                    let skip = piece_char.to_digit(10).unwrap() as i32;
-                    file_index += skip;
+                if let Some(skip) = piece_char.to_digit(10) {
+                    file_index = file_index.saturating_add(skip as u8);
                 } else {
-                    let square_index = (rank_start_index + file_index) as u8;
-
-                    match piece_char {
-                        'P' => {
-                            board.pawn.set_bit(square_index);
-                            board.white.set_bit(square_index);
-                        }
-                        'N' => {
-                            board.knight.set_bit(square_index);
-                            board.white.set_bit(square_index);
-                        }
-                        'B' => {
-                            board.bishop.set_bit(square_index);
-                            board.white.set_bit(square_index);
-                        }
-                        'R' => {
-                            board.rook.set_bit(square_index);
-                            board.white.set_bit(square_index);
-                        }
-                        'Q' => {
-                            board.queen.set_bit(square_index);
-                            board.white.set_bit(square_index);
-                        }
-                        'K' => {
-                            board.king.set_bit(square_index);
-                            board.white.set_bit(square_index);
-                            board.white_king = true;
-                        }
-
-                        'p' => {
-                            board.pawn.set_bit(square_index);
-                            board.black.set_bit(square_index);
-                        }
-                        'n' => {
-                            board.knight.set_bit(square_index);
-                            board.black.set_bit(square_index);
-                        }
-                        'b' => {
-                            board.bishop.set_bit(square_index);
-                            board.black.set_bit(square_index);
-                        }
-                        'r' => {
-                            board.rook.set_bit(square_index);
-                            board.black.set_bit(square_index);
-                        }
-                        'q' => {
-                            board.queen.set_bit(square_index);
-                            board.black.set_bit(square_index);
-                        }
-                        'k' => {
-                            board.king.set_bit(square_index);
-                            board.black.set_bit(square_index);
-                            board.black_king = true;
-                        }
-                        _ => return Err(format!("Unknown piece character: {}", piece_char)),
-                    }
+                    let Some(file) = File::new(file_index) else {
+                        return Err(format!("Too many squares on rank: {}", rank_str));
+                    };
+                    let Some(piece) = Piece::from_char(piece_char) else {
+                        return Err(format!("Unknown piece character: {}", piece_char));
+                    };
+                    board.put_piece(Square::new(file, rank), piece);
                     file_index += 1;
                 }
             }
-            rank_start_index -= 8;
+            if file_index != 8 {
+                return Err(format!("Rank does not have 8 squares: {}", rank_str));
+            }
+        }
+
+        // Move generation and search rely on both kings being on the board
+        for (color, name) in [(Color::White, "white"), (Color::Black, "black")] {
+            if board.get_pieces(color, Type::King).count_ones() != 1 {
+                return Err(format!("FEN must place exactly one {} king.", name));
+            }
         }
 
         board.is_white_turn = match parts[1] {
@@ -286,34 +399,120 @@ impl Board {
             _ => return Err("Invalid side to move field.".to_string()),
         };
 
+        // Rights whose king or rook is not on its home square are dropped,
+        // castling moves both pieces from there
         let castling_rights = parts[2];
-        if castling_rights != "-" {
-            if castling_rights.contains('K') {
-                board.white_rook_short_side = true
-            }
-            if castling_rights.contains('Q') {
-                board.white_rook_long_side = true
-            }
-            if castling_rights.contains('k') {
-                board.black_rook_short_side = true
-            }
-            if castling_rights.contains('q') {
-                board.black_rook_long_side = true
-            }
+        if castling_rights != "-" && castling_rights.chars().any(|c| !"KQkq".contains(c)) {
+            return Err(format!("Invalid castling field: {}", castling_rights));
         }
+        let mailbox = board.mailbox;
+        let on = |square: Square, color: Color, piece_type: Type| {
+            mailbox[square.index() as usize] == Some(Piece::new(color, piece_type))
+        };
+        board.white_king = on(Square::E1, Color::White, Type::King);
+        board.black_king = on(Square::E8, Color::Black, Type::King);
+        board.white_rook_short_side =
+            castling_rights.contains('K') && on(Square::H1, Color::White, Type::Rook);
+        board.white_rook_long_side =
+            castling_rights.contains('Q') && on(Square::A1, Color::White, Type::Rook);
+        board.black_rook_short_side =
+            castling_rights.contains('k') && on(Square::H8, Color::Black, Type::Rook);
+        board.black_rook_long_side =
+            castling_rights.contains('q') && on(Square::A8, Color::Black, Type::Rook);
 
         let en_passant_square = parts[3];
         if en_passant_square != "-" {
-            if let Some(sq_index) = algebraic_to_index(en_passant_square) {
-                board.enpassant.set_bit(sq_index);
-            } else {
+            let square: Square = en_passant_square
+                .parse()
+                .map_err(|_| format!("Invalid en passant square: {}", en_passant_square))?;
+            // The pawn that just moved two squares stands right past it
+            let side = board.get_side();
+            let pushed = Piece::new(side.opposite(), Type::Pawn);
+            let expected_rank = Rank::SIXTH.relative_to(side);
+            let pawn_square = square.offset(0, side.match_color(-1, 1));
+            if square.rank() != expected_rank
+                || board.piece_at(square).is_some()
+                || pawn_square.and_then(|sq| board.piece_at(sq)) != Some(pushed)
+            {
                 return Err(format!("Invalid en passant square: {}", en_passant_square));
             }
+            board.enpassant.set_bit(square.index());
+        }
+
+        if let Some(halfmove) = parts.get(4) {
+            board.halfmove_clock = halfmove
+                .parse()
+                .map_err(|_| format!("Invalid halfmove clock: {}", halfmove))?;
+        }
+
+        if let Some(fullmove) = parts.get(5) {
+            board.fullmove_number = fullmove
+                .parse()
+                .map_err(|_| format!("Invalid fullmove number: {}", fullmove))?;
+        }
+
+        let back_ranks = Bitboard::rank(Rank::FIRST) | Bitboard::rank(Rank::EIGHTH);
+        if !(board.pawn & back_ranks).is_empty() {
+            return Err("Pawns cannot stand on the first or last rank.".to_string());
+        }
+        if board.is_king_in_check(board.get_side().opposite()) {
+            return Err("The side not to move is in check.".to_string());
         }
+
         board.hash = ZOBRIST.compute_hash(&board);
         Ok(board)
     }
 
+    /// Formats the position as a FEN string, the inverse of `new_from_fen`.
+    pub fn get_fen(&self) -> String {
+        let mut fen = String::with_capacity(90);
+
+        for rank in Rank::ALL.into_iter().rev() {
+            let mut empty = 0;
+            for file in File::ALL {
+                let Some(piece) = self.piece_at(Square::new(file, rank)) else {
+                    empty += 1;
+                    continue;
+                };
+                if empty > 0 {
+                    fen.push((b'0' + empty) as char);
+                    empty = 0;
+                }
+                fen.push(piece.to_char());
+            }
+            if empty > 0 {
+                fen.push((b'0' + empty) as char);
+            }
+            if rank != Rank::FIRST {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(if self.is_white_turn { " w " } else { " b " });
+
+        let castling_len = fen.len();
+        for (allowed, letter) in self.castling_rights().into_iter().zip("KQkq".chars()) {
+            if allowed {
+                fen.push(letter);
+            }
+        }
+        if fen.len() == castling_len {
+            fen.push('-');
+        }
+
+        match self.en_passant_square() {
+            None => fen.push_str(" -"),
+            Some(square) => fen.push_str(&format!(" {}", square)),
+        }
+
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock, self.fullmove_number
+        ));
+        fen
+    }
+
+    /// The side to move.
     #[inline]
     pub fn get_side(&self) -> Color {
         if self.is_white_turn {
@@ -322,15 +521,31 @@ impl Board {
         Color::Black
     }
 
+    /// Zobrist hash of the position.
     #[inline]
     pub fn get_hash(&self) -> u64 {
         self.hash
     }
 
-    pub fn switch_side(&mut self) {
+    /// Halfmoves since the last capture or pawn move.
+    #[inline]
+    pub fn get_halfmove_clock(&self) -> u16 {
+        self.halfmove_clock
+    }
+
+    /// Number of the current move, starting at 1 and counting up after
+    /// each Black move.
+    #[inline]
+    pub fn get_fullmove_number(&self) -> u16 {
+        self.fullmove_number
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn switch_side(&mut self) {
         self.is_white_turn = !self.is_white_turn
     }
 
+    /// Whether `color` castled since the position was set up.
     #[inline(always)]
     pub fn has_castled(&self, color: Color) -> bool {
         match color {
@@ -339,6 +554,8 @@ impl Board {
         }
     }
 
+    /// Squares of the pieces of `color` and `piece_type`, all of them for
+    /// `Type::Any`.
     #[inline]
     pub fn get_pieces(&self, color: Color, piece_type: Type) -> Bitboard {
         match color {
@@ -347,52 +564,45 @@ impl Board {
         }
     }
 
+    /// The piece on `square`, if any.
+    #[inline(always)]
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.mailbox[square.index() as usize]
+    }
+
     #[inline]
-    pub fn get_piece_type_at_square(&self, square: u8) -> Option<Type> {
-        let bb = 1u64 << square;
-        if self.pawn.get_value() & bb != 0 {
-            return Some(Type::Pawn);
-        }
-        if self.knight.get_value() & bb != 0 {
-            return Some(Type::Knight);
-        }
-        if self.bishop.get_value() & bb != 0 {
-            return Some(Type::Bishop);
-        }
-        if self.rook.get_value() & bb != 0 {
-            return Some(Type::Rook);
-        }
-        if self.queen.get_value() & bb != 0 {
-            return Some(Type::Queen);
-        }
-        if self.king.get_value() & bb != 0 {
-            return Some(Type::King);
-        }
-        None
+    pub(crate) fn get_piece_type_at_square(&self, square: Square) -> Option<Type> {
+        self.piece_at(square).map(|piece| piece.piece_type)
     }
 
     #[inline]
-    pub fn get_piece_color_at_square(&self, square: u8) -> Option<Color> {
-        let bb = 1u64 << square;
-        if self.white.get_value() & bb != 0 {
-            return Some(Color::White);
-        }
-        if self.black.get_value() & bb != 0 {
-            return Some(Color::Black);
-        }
-        None
+    #[cfg_attr(not(any(feature = "cli", feature = "ffi")), allow(dead_code))]
+    pub(crate) fn get_piece_color_at_square(&self, square: Square) -> Option<Color> {
+        self.piece_at(square).map(|piece| piece.color)
+    }
+
+    /// Square of the king of `color`, which must be on the board.
+    #[inline]
+    pub fn king_square(&self, color: Color) -> Square {
+        Square::from_lsb(self.get_pieces(color, Type::King).get_value())
+    }
+
+    /// The square a pawn skipped with a double step on the last move.
+    #[inline]
+    pub fn en_passant_square(&self) -> Option<Square> {
+        self.enpassant.first_square()
     }
 
     #[inline]
     fn get_piece_white(&self, piece_type: Type) -> Bitboard {
         match piece_type {
             Type::Any => self.white,
-            Type::Pawn => self.pawn.and(self.white),
-            Type::Bishop => self.bishop.and(self.white),
-            Type::Knight => self.knight.and(self.white),
-            Type::Rook => self.rook.and(self.white),
-            Type::Queen => self.queen.and(self.white),
-            Type::King => self.king.and(self.white),
+            Type::Pawn => self.pawn & self.white,
+            Type::Bishop => self.bishop & self.white,
+            Type::Knight => self.knight & self.white,
+            Type::Rook => self.rook & self.white,
+            Type::Queen => self.queen & self.white,
+            Type::King => self.king & self.white,
         }
     }
 
@@ -400,71 +610,357 @@ impl Board {
     fn get_piece_black(&self, piece_type: Type) -> Bitboard {
         match piece_type {
             Type::Any => self.black,
-            Type::Pawn => self.pawn.and(self.black),
-            Type::Bishop => self.bishop.and(self.black),
-            Type::Knight => self.knight.and(self.black),
-            Type::Rook => self.rook.and(self.black),
-            Type::Queen => self.queen.and(self.black),
-            Type::King => self.king.and(self.black),
+            Type::Pawn => self.pawn & self.black,
+            Type::Bishop => self.bishop & self.black,
+            Type::Knight => self.knight & self.black,
+            Type::Rook => self.rook & self.black,
+            Type::Queen => self.queen & self.black,
+            Type::King => self.king & self.black,
         }
     }
 
     #[inline]
     #[allow(dead_code)]
-    pub fn get_free_pos(&self) -> Bitboard {
-        self.white.or(self.black).not()
+    pub(crate) fn get_free_pos(&self) -> Bitboard {
+        !(self.white | self.black)
     }
 
+    /// Squares holding a piece of either side.
     #[inline]
     pub fn get_occupied_pos(&self) -> Bitboard {
-        self.white.or(self.black)
+        self.white | self.black
     }
 
-    pub fn get_piece_info_from_sq(&self, sq: u8) -> Option<(Color, Type)> {
-        let mut color = Color::White;
-        let mut piece_type = Type::King;
-        let piece = Bitboard::new_from_index(sq);
-        if self.black.and(piece).0 > 0 {
-            color = Color::Black;
-        } else if self.white.and(piece).0 == 0 {
-            return None;
-        }
+    pub(crate) fn get_piece_info_from_sq(&self, sq: Square) -> Option<(Color, Type)> {
+        self.piece_at(sq)
+            .map(|piece| (piece.color, piece.piece_type))
+    }
 
-        if self.pawn.and(piece).0 > 0 {
-            piece_type = Type::Pawn;
-        } else if self.rook.and(piece).0 > 0 {
-            piece_type = Type::Rook;
-        } else if self.bishop.and(piece).0 > 0 {
-            piece_type = Type::Bishop;
-        } else if self.queen.and(piece).0 > 0 {
-            piece_type = Type::Queen;
-        } else if self.knight.and(piece).0 > 0 {
-            piece_type = Type::Knight;
+    /// Flips `squares` on the bitboards of the piece's type and color.
+    #[inline(always)]
+    fn toggle_piece(&mut self, piece: Piece, mask: Bitboard) {
+        match piece.piece_type {
+            Type::Pawn => self.pawn ^= mask,
+            Type::Knight => self.knight ^= mask,
+            Type::Bishop => self.bishop ^= mask,
+            Type::Rook => self.rook ^= mask,
+            Type::Queen => self.queen ^= mask,
+            Type::King => self.king ^= mask,
+            Type::Any => {}
+        }
+        match piece.color {
+            Color::White => self.white ^= mask,
+            Color::Black => self.black ^= mask,
         }
+    }
 
-        Some((color, piece_type))
+    /// Places a piece on an empty square.
+    #[inline(always)]
+    fn put_piece(&mut self, square: Square, piece: Piece) {
+        self.toggle_piece(piece, square.into());
+        self.mailbox[square.index() as usize] = Some(piece);
     }
 
-    pub fn get_legal_moves<'a>(&mut self, color: Color, buffer: &'a mut Vec<Moves>) -> &'a [Moves] {
+    #[inline(always)]
+    fn remove_piece(&mut self, square: Square) -> Option<Piece> {
+        let piece = self.mailbox[square.index() as usize].take()?;
+        self.toggle_piece(piece, square.into());
+        Some(piece)
+    }
+
+    #[inline(always)]
+    fn shift_piece(&mut self, from: Square, to: Square) {
+        if let Some(piece) = self.remove_piece(from) {
+            self.put_piece(to, piece);
+        }
+    }
+
+    /// Generates only legal moves, in the same order as `get_all_moves_bitboard`.
+    /// Checkers, pins and the squares the king may not step on are worked out
+    /// once, so no move has to be made to test it.
+    pub(crate) fn get_legal_moves<'a>(&self, color: Color, buffer: &'a mut Vec<Moves>) -> &'a [Moves] {
         buffer.clear();
-        self.get_all_moves_bitboard(color, buffer);
+        self.generate_legal(color, GenType::All, buffer);
+        &buffer[..]
+    }
+
+    /// Appends the legal moves of the given kind to `buffer`, so the search
+    /// can generate captures first and quiet moves only when it needs them.
+    pub(crate) fn generate_legal(&self, color: Color, gen_type: GenType, buffer: &mut Vec<Moves>) {
+        let mut context = self.create_legal_context(color);
+        context.gen_type = gen_type;
+
+        for piece_type in Type::iter() {
+            if piece_type == Type::Any {
+                continue;
+            }
+            if context.checkers.more_than_one() && piece_type != Type::King {
+                continue;
+            }
+            self.get_moves(color, piece_type, &context, buffer);
+        }
+        if context.checkers.is_empty() && gen_type != GenType::Captures {
+            self.castle(color, buffer);
+        }
+    }
+
+    /// Appends the legal replies to a check: king moves, captures of the
+    /// checking piece and moves that block its line. With two checkers only
+    /// the king can move. Must only be called when `color` is in check.
+    pub(crate) fn generate_evasions(&self, color: Color, buffer: &mut Vec<Moves>) {
+        let context = self.create_legal_context(color);
+        debug_assert!(
+            !context.checkers.is_empty(),
+            "generate_evasions called without check"
+        );
+
+        self.get_moves(color, Type::King, &context, buffer);
+        if context.checkers.more_than_one() {
+            return;
+        }
+        for piece_type in [
+            Type::Pawn,
+            Type::Knight,
+            Type::Bishop,
+            Type::Rook,
+            Type::Queen,
+        ] {
+            self.get_moves(color, piece_type, &context, buffer);
+        }
+    }
 
-        let mut legal_index = 0;
-        for i in 0..buffer.len() {
+    /// Appends the legal quiet moves that give check, either directly or by
+    /// moving a piece out of the line between an own slider and the enemy
+    /// king. Nothing is generated while `color` is in check.
+    pub(crate) fn generate_quiet_checks(&self, color: Color, buffer: &mut Vec<Moves>) {
+        if self.is_king_in_check(color) {
+            return;
+        }
+        let start = buffer.len();
+        self.generate_legal(color, GenType::Quiets, buffer);
+
+        let king_square = self.king_square(color.opposite());
+        let occupied = self.get_occupied_pos();
+        let queens = self.get_pieces(color, Type::Queen);
+        let rooks = self.get_pieces(color, Type::Rook) | queens;
+        let bishops = self.get_pieces(color, Type::Bishop) | queens;
+        let discoverers = self.slider_blockers(king_square, rooks, bishops, occupied)
+            & self.get_pieces(color, Type::Any);
+
+        let rook_checks = lookup::rook(king_square, occupied);
+        let bishop_checks = lookup::bishop(king_square, occupied);
+        // A pawn gives check from the squares an enemy pawn on the king
+        // square would attack.
+        let pawn_checks = lookup::pawn(color.opposite(), king_square);
+
+        let mut kept = start;
+        for i in start..buffer.len() {
             let mv = buffer[i];
-            let undo = self.make_move_with_undo(&mv);
-            if !self.is_king_in_check(color) {
-                buffer[legal_index] = mv;
-                legal_index += 1;
+            let (from, to) = (mv.from(), mv.to());
+            let discovered =
+                discoverers.contains(from) && !lookup::line(king_square, from).contains(to);
+            let direct = match self.get_piece_type_at_square(from) {
+                _ if mv.is_castle() => {
+                    let (rook_from, rook_to) = match to {
+                        Square::C1 => (Square::A1, Square::D1),
+                        Square::G1 => (Square::H1, Square::F1),
+                        Square::C8 => (Square::A8, Square::D8),
+                        _ => (Square::H8, Square::F8),
+                    };
+                    let after = occupied ^ from.into() ^ rook_from.into() | to.into();
+                    lookup::rook(king_square, after).contains(rook_to)
+                }
+                Some(Type::Pawn) => pawn_checks.contains(to),
+                Some(Type::Knight) => lookup::knight(king_square).contains(to),
+                Some(Type::Bishop) => bishop_checks.contains(to),
+                Some(Type::Rook) => rook_checks.contains(to),
+                Some(Type::Queen) => (rook_checks | bishop_checks).contains(to),
+                _ => false,
+            };
+            if direct || discovered {
+                buffer[kept] = mv;
+                kept += 1;
             }
-            self.unmake_move(&mv, undo);
         }
+        buffer.truncate(kept);
+    }
 
-        buffer.truncate(legal_index);
-        &buffer[..]
+    /// Whether the side to move could play `mv` here if its own king did not
+    /// matter. Moves taken from other positions, such as the hash move and
+    /// killers, must pass this before they are made.
+    pub fn is_pseudo_legal(&self, mv: &Moves) -> bool {
+        let color = self.get_side();
+        let from = mv.from();
+        let to = mv.to();
+        let own = self.get_pieces(color, Type::Any);
+        let enemy = self.get_pieces(color.opposite(), Type::Any);
+        let occupied = own | enemy;
+
+        if !own.contains(from) || own.contains(to) {
+            return false;
+        }
+        let Some(piece) = self.get_piece_type_at_square(from) else {
+            return false;
+        };
+
+        if mv.is_castle() {
+            let (king_square, long, short) = color.match_color(
+                (Square::E1, Square::C1, Square::G1),
+                (Square::E8, Square::C8, Square::G8),
+            );
+            return piece == Type::King
+                && from == king_square
+                && !self.is_king_in_check(color)
+                && ((to == long && self.can_castle(color, Side::Long))
+                    || (to == short && self.can_castle(color, Side::Short)));
+        }
+
+        let pawn_attacks = lookup::pawn(color, from);
+        if mv.is_enpassant() {
+            return piece == Type::Pawn
+                && self.en_passant_square() == Some(to)
+                && pawn_attacks.contains(to);
+        }
+        if mv.is_capture() != enemy.contains(to) {
+            return false;
+        }
+
+        if piece == Type::Pawn {
+            let promotion_rank = color.match_color(LASTRANK, FIRSTRANK);
+            let pushes = match color {
+                Color::White => moves_gen::pawn::white_moves(from.into(), !occupied),
+                Color::Black => moves_gen::pawn::black_moves(from.into(), !occupied),
+            };
+            return mv.is_promotion() == promotion_rank.contains(to)
+                && (pushes | pawn_attacks & enemy).contains(to);
+        }
+        if mv.is_promotion() {
+            return false;
+        }
+
+        let attacks = match piece {
+            Type::Knight => lookup::knight(from),
+            Type::Bishop => lookup::bishop(from, occupied),
+            Type::Rook => lookup::rook(from, occupied),
+            Type::Queen => lookup::queen(from, occupied),
+            Type::King => lookup::king(from),
+            _ => Bitboard::empty(),
+        };
+        attacks.contains(to)
+    }
+
+    /// Whether a pseudo-legal move keeps the own king out of check, without
+    /// making it. Castling is fully checked by `is_pseudo_legal` already.
+    pub fn is_legal(&self, mv: &Moves) -> bool {
+        let color = self.get_side();
+        let enemy = self.get_pieces(color.opposite(), Type::Any);
+        let king_square = self.king_square(color);
+        let (from, to) = (mv.from(), mv.to());
+        let occupied = self.get_occupied_pos();
+
+        if mv.is_castle() {
+            return true;
+        }
+        if from == king_square {
+            let without_king = occupied ^ king_square.into();
+            return (self.attackers_to(to, without_king) & enemy).is_empty();
+        }
+        if mv.is_enpassant() {
+            let captured: Bitboard = Square::new(to.file(), from.rank()).into();
+            let after = (occupied ^ from.into() ^ captured) | to.into();
+            return (self.attackers_to(king_square, after) & enemy & !captured).is_empty();
+        }
+
+        let checkers = self.attackers_to(king_square, occupied) & enemy;
+        if checkers.more_than_one() {
+            return false;
+        }
+        if let Some(checker) = checkers.first_square()
+            && !(checkers | lookup::between(king_square, checker)).contains(to)
+        {
+            return false;
+        }
+
+        let queens = self.get_pieces(color.opposite(), Type::Queen);
+        let rooks = self.get_pieces(color.opposite(), Type::Rook) | queens;
+        let bishops = self.get_pieces(color.opposite(), Type::Bishop) | queens;
+        let pinned = self
+            .slider_blockers(king_square, rooks, bishops, occupied)
+            .contains(from);
+        !pinned || lookup::line(king_square, from).contains(to)
+    }
+
+    /// Whether a legal move checks the enemy king, directly or by discovery,
+    /// without making it.
+    pub fn gives_check(&self, mv: &Moves) -> bool {
+        let color = self.get_side();
+        let king_square = self.king_square(color.opposite());
+        let (from, to) = (mv.from(), mv.to());
+        let mut occupied = (self.get_occupied_pos() ^ from.into()) | to.into();
+        let mut moved = Bitboard::from(from);
+
+        // The checking candidate: the moved piece, or the rook when castling
+        let (piece, piece_square) = if mv.is_castle() {
+            let (rook_from, rook_to) = match to {
+                Square::C1 => (Square::A1, Square::D1),
+                Square::G1 => (Square::H1, Square::F1),
+                Square::C8 => (Square::A8, Square::D8),
+                _ => (Square::H8, Square::F8),
+            };
+            occupied = occupied ^ rook_from.into() | rook_to.into();
+            moved |= rook_from.into();
+            (Type::Rook, rook_to)
+        } else if let Some(promotion) = mv.promotion() {
+            (promotion, to)
+        } else {
+            match self.get_piece_type_at_square(from) {
+                Some(piece) => (piece, to),
+                None => return false,
+            }
+        };
+        if mv.is_enpassant() {
+            occupied ^= Square::new(to.file(), from.rank()).into();
+        }
+
+        let direct = match piece {
+            Type::Pawn => lookup::pawn(color, piece_square),
+            Type::Knight => lookup::knight(piece_square),
+            Type::Bishop => lookup::bishop(piece_square, occupied),
+            Type::Rook => lookup::rook(piece_square, occupied),
+            Type::Queen => lookup::queen(piece_square, occupied),
+            _ => Bitboard::empty(),
+        };
+        if direct.contains(king_square) {
+            return true;
+        }
+
+        // Sliders that stayed put and now see the king
+        let queens = self.get_pieces(color, Type::Queen);
+        let rooks = (self.get_pieces(color, Type::Rook) | queens) & !moved;
+        let bishops = (self.get_pieces(color, Type::Bishop) | queens) & !moved;
+        !(lookup::rook(king_square, occupied) & rooks).is_empty()
+            || !(lookup::bishop(king_square, occupied) & bishops).is_empty()
+    }
+
+    /// Legal moves of the side to move.
+    pub fn legal_moves(&self) -> Vec<Moves> {
+        let mut moves = Vec::with_capacity(64);
+        self.get_legal_moves(self.get_side(), &mut moves);
+        moves
+    }
+
+    /// Plays a legal move. Returns an error and leaves the board unchanged
+    /// when the move is not legal.
+    pub fn make_move(&mut self, mv: &Moves) -> Result<(), String> {
+        if !(self.is_pseudo_legal(mv) && self.is_legal(mv)) {
+            return Err(format!("Illegal move: {}", mv.to_string()));
+        }
+        self.perform_move(mv);
+        Ok(())
     }
 
-    pub fn get_all_moves_bitboard<'a>(
+    #[allow(dead_code)]
+    pub(crate) fn get_all_moves_bitboard<'a>(
         &self,
         color: Color,
         buffer: &'a mut Vec<Moves>,
@@ -484,18 +980,111 @@ impl Board {
 
     #[inline]
     fn create_move_context(&self, color: Color) -> MoveContext {
-        match color {
-            Color::White => MoveContext {
-                own_pieces: self.white.get_value(),
-                enemy_pieces: self.black.get_value(),
-                occupied: self.white.or(self.black).get_value(),
-            },
-            Color::Black => MoveContext {
-                own_pieces: self.black.get_value(),
-                enemy_pieces: self.white.get_value(),
-                occupied: self.white.or(self.black).get_value(),
-            },
+        let (own_pieces, enemy_pieces) = match color {
+            Color::White => (self.white, self.black),
+            Color::Black => (self.black, self.white),
+        };
+        MoveContext {
+            own_pieces,
+            enemy_pieces,
+            occupied: own_pieces | enemy_pieces,
+            king_square: Square::A1,
+            checkers: Bitboard::empty(),
+            check_mask: Bitboard::full(),
+            pinned: Bitboard::empty(),
+            king_danger: Bitboard::empty(),
+            legal: false,
+            gen_type: GenType::All,
+        }
+    }
+
+    fn create_legal_context(&self, color: Color) -> MoveContext {
+        let mut context = self.create_move_context(color);
+        let enemy = color.opposite();
+        let king = self.get_pieces(color, Type::King);
+        let king_square = self.king_square(color);
+        let queens = self.get_pieces(enemy, Type::Queen);
+        let rooks = self.get_pieces(enemy, Type::Rook) | queens;
+        let bishops = self.get_pieces(enemy, Type::Bishop) | queens;
+
+        let checkers = self.attackers_to(king_square, context.occupied) & context.enemy_pieces;
+        let pinned = self.slider_blockers(king_square, rooks, bishops, context.occupied)
+            & context.own_pieces;
+
+        context.check_mask = match checkers.first_square() {
+            None => Bitboard::full(),
+            Some(_) if checkers.more_than_one() => Bitboard::empty(),
+            Some(checker) => checkers | lookup::between(king_square, checker),
+        };
+        context.king_square = king_square;
+        context.checkers = checkers;
+        context.pinned = pinned;
+        context.king_danger = self.attacked_squares(enemy, context.occupied & !king);
+        context.legal = true;
+        context
+    }
+
+    /// Pieces of either colour that are the only piece between `square` and
+    /// one of the given sliders on a line the slider moves along.
+    fn slider_blockers(
+        &self,
+        square: Square,
+        rooks: Bitboard,
+        bishops: Bitboard,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        let empty = Bitboard::empty();
+        let snipers = lookup::rook(square, empty) & rooks | lookup::bishop(square, empty) & bishops;
+        let mut blockers = Bitboard::empty();
+        for sniper in snipers {
+            let between = lookup::between(square, sniper) & occupied;
+            if between.count_ones() == 1 {
+                blockers |= between;
+            }
+        }
+        blockers
+    }
+
+    /// Every square `color` attacks with the given occupancy.
+    fn attacked_squares(&self, color: Color, occupied: Bitboard) -> Bitboard {
+        let pawns = self.get_pieces(color, Type::Pawn);
+        let mut attacks = match color {
+            Color::White => moves_gen::pawn::white_attack(pawns, Bitboard::full()),
+            Color::Black => moves_gen::pawn::black_attack(pawns, Bitboard::full()),
+        };
+        attacks |= moves_gen::knight::moves(self.get_pieces(color, Type::Knight));
+        attacks |= moves_gen::king::moves(self.get_pieces(color, Type::King));
+
+        let queens = self.get_pieces(color, Type::Queen);
+        for square in self.get_pieces(color, Type::Rook) | queens {
+            attacks |= lookup::rook(square, occupied);
         }
+        for square in self.get_pieces(color, Type::Bishop) | queens {
+            attacks |= lookup::bishop(square, occupied);
+        }
+        attacks
+    }
+
+    /// Whether taking en passant leaves the king safe. Both pawns leave their
+    /// squares at once, which can open a rank or diagonal to the king.
+    fn is_legal_en_passant(
+        &self,
+        from: Square,
+        to: Square,
+        color: Color,
+        context: &MoveContext,
+    ) -> bool {
+        let captured: Bitboard = Square::new(to.file(), from.rank()).into();
+        let enemy = color.opposite();
+        let occupied = (context.occupied ^ from.into() ^ captured) | to.into();
+        let queens = self.get_pieces(enemy, Type::Queen);
+        let rooks = self.get_pieces(enemy, Type::Rook) | queens;
+        let bishops = self.get_pieces(enemy, Type::Bishop) | queens;
+
+        let sliders = rooks | bishops;
+        (context.checkers & !captured & !sliders).is_empty()
+            && (lookup::rook(context.king_square, occupied) & rooks).is_empty()
+            && (lookup::bishop(context.king_square, occupied) & bishops).is_empty()
     }
 
     fn get_moves(
@@ -525,275 +1114,78 @@ impl Board {
         context: &MoveContext,
         buffer: &mut Vec<Moves>,
     ) {
-        let empty = self.white.or(self.black).not().get_value();
-        let enpassant = self.enpassant.get_value();
-
-        for p in bitboard.iter_bits() {
-            let from_square = p.lsb() as u8;
-            let p_val = p.get_value();
-
-            match color {
-                Color::White => {
-                    let temp_moves = moves_gen::pawn::white_moves(p_val, empty)
-                        | moves_gen::pawn::white_attack(p_val, context.enemy_pieces);
-
-                    let mut temp_bb = temp_moves;
-                    while temp_bb != 0 {
-                        let to_square = temp_bb.trailing_zeros() as u8;
-                        let to_bit = 1u64 << to_square;
-                        temp_bb &= temp_bb - 1;
-
-                        let is_capture = (to_bit & context.enemy_pieces) != 0;
-                        let is_promotion = (to_bit & LASTRANK) != 0;
-
-                        if is_promotion {
-                            let flag = if is_capture {
-                                FLAG_CAPTURE
-                            } else {
-                                FLAG_NORMAL
-                            };
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_QUEEN,
-                                flag,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_ROOK,
-                                flag,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_BISHOP,
-                                flag,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_KNIGHT,
-                                flag,
-                                true,
-                            ));
-                        } else {
-                            let flag = if is_capture {
-                                FLAG_CAPTURE
-                            } else {
-                                FLAG_NORMAL
-                            };
-                            buffer.push(Moves::new(from_square, to_square, 0, flag, false));
-                        }
-                    }
+        let empty = !context.occupied;
+        let promotion_rank = color.match_color(LASTRANK, FIRSTRANK);
 
-                    if enpassant != 0 {
-                        let landing = enpassant;
-                        let left_hit = (p_val << 7) & landing & !FILE_H;
-                        let right_hit = (p_val << 9) & landing & !FILE_A;
-
-                        if left_hit != 0 || right_hit != 0 {
-                            let to_square = landing.trailing_zeros() as u8;
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                0,
-                                FLAG_EN_PASSANT,
-                                false,
-                            ));
-                        }
-                    }
-                }
-                Color::Black => {
-                    let temp_moves = moves_gen::pawn::black_moves(p_val, empty)
-                        | moves_gen::pawn::black_attack(p_val, context.enemy_pieces);
-
-                    let mut temp_bb = temp_moves;
-                    while temp_bb != 0 {
-                        let to_square = temp_bb.trailing_zeros() as u8;
-                        let to_bit = 1u64 << to_square;
-                        temp_bb &= temp_bb - 1;
-
-                        let is_capture = (to_bit & context.enemy_pieces) != 0;
-                        let is_promotion = (to_bit & FIRSTRANK) != 0;
-
-                        if is_promotion {
-                            let flag = if is_capture {
-                                FLAG_CAPTURE
-                            } else {
-                                FLAG_NORMAL
-                            };
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_QUEEN,
-                                flag,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_ROOK,
-                                flag,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_BISHOP,
-                                flag,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_KNIGHT,
-                                flag,
-                                true,
-                            ));
-                        } else {
-                            let flag = if is_capture {
-                                FLAG_CAPTURE
-                            } else {
-                                FLAG_NORMAL
-                            };
-                            buffer.push(Moves::new(from_square, to_square, 0, flag, false));
-                        }
-                    }
+        for from in bitboard {
+            let pushes = match color {
+                Color::White => moves_gen::pawn::white_moves(from.into(), empty),
+                Color::Black => moves_gen::pawn::black_moves(from.into(), empty),
+            };
+            let attacks = lookup::pawn(color, from);
+            let targets = (pushes | attacks & context.enemy_pieces) & context.pawn_targets(from);
 
-                    if enpassant != 0 {
-                        let landing = enpassant;
-                        let left_hit = (p_val >> 9) & landing & !FILE_H;
-                        let right_hit = (p_val >> 7) & landing & !FILE_A;
-
-                        if left_hit != 0 || right_hit != 0 {
-                            let to_square = landing.trailing_zeros() as u8;
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                0,
-                                FLAG_EN_PASSANT,
-                                false,
-                            ));
-                        }
+            for to in targets {
+                let flag = if context.enemy_pieces.contains(to) {
+                    FLAG_CAPTURE
+                } else {
+                    FLAG_NORMAL
+                };
+                if promotion_rank.contains(to) {
+                    for piece in PROMOTION_TYPES {
+                        buffer.push(Moves::new_promotion(from, to, piece, flag));
                     }
+                } else {
+                    buffer.push(Moves::new(from, to, flag));
                 }
             }
+
+            if let Some(to) = (attacks & self.enpassant).first_square()
+                && context.gen_type != GenType::Quiets
+                && (!context.legal || self.is_legal_en_passant(from, to, color, context))
+            {
+                buffer.push(Moves::new(from, to, FLAG_EN_PASSANT));
+            }
         }
     }
 
     fn get_knight_move(&self, bitboard: Bitboard, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        for p in bitboard.iter_bits() {
-            let from_square = p.lsb() as u8;
-            let temp_moves = moves_gen::knight::moves(p.get_value()) & !context.own_pieces;
-
-            let mut temp_bb = temp_moves;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                let to_bit = 1u64 << to_square;
-                temp_bb &= temp_bb - 1;
-
-                let flag = if (to_bit & context.enemy_pieces) != 0 {
-                    FLAG_CAPTURE
-                } else {
-                    FLAG_NORMAL
-                };
-
-                buffer.push(Moves::new(from_square, to_square, 0, flag, false));
-            }
+        for from in bitboard {
+            let targets = lookup::knight(from) & !context.own_pieces & context.targets(from);
+            push_moves(from, targets, context.enemy_pieces, buffer);
         }
     }
 
     fn get_queen_moves(&self, bitboard: Bitboard, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        for p in bitboard.iter_bits() {
-            let from_square = p.lsb() as u8;
-            let temp_moves =
-                moves_gen::queen::moves(p.get_value(), context.occupied) & !context.own_pieces;
-
-            let mut temp_bb = temp_moves;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                let to_bit = 1u64 << to_square;
-                temp_bb &= temp_bb - 1;
-
-                let flag = if (to_bit & context.enemy_pieces) != 0 {
-                    FLAG_CAPTURE
-                } else {
-                    FLAG_NORMAL
-                };
-
-                buffer.push(Moves::new(from_square, to_square, 0, flag, false));
-            }
+        for from in bitboard {
+            let targets =
+                lookup::queen(from, context.occupied) & !context.own_pieces & context.targets(from);
+            push_moves(from, targets, context.enemy_pieces, buffer);
         }
     }
 
     fn get_bishop_move(&self, bitboard: Bitboard, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        for p in bitboard.iter_bits() {
-            let from_square = p.lsb() as u8;
-            let temp_moves =
-                moves_gen::bishop::moves(p.get_value(), context.occupied) & !context.own_pieces;
-
-            let mut temp_bb = temp_moves;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                let to_bit = 1u64 << to_square;
-                temp_bb &= temp_bb - 1;
-
-                let flag = if (to_bit & context.enemy_pieces) != 0 {
-                    FLAG_CAPTURE
-                } else {
-                    FLAG_NORMAL
-                };
-
-                buffer.push(Moves::new(from_square, to_square, 0, flag, false));
-            }
+        for from in bitboard {
+            let targets = lookup::bishop(from, context.occupied)
+                & !context.own_pieces
+                & context.targets(from);
+            push_moves(from, targets, context.enemy_pieces, buffer);
         }
     }
 
     fn get_rook_move(&self, bitboard: Bitboard, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        for p in bitboard.iter_bits() {
-            let from_square = p.lsb() as u8;
-            let temp_moves =
-                moves_gen::rook::moves(p.get_value(), context.occupied) & !context.own_pieces;
-            let mut temp_bb = temp_moves;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                let to_bit = 1u64 << to_square;
-                temp_bb &= temp_bb - 1;
-
-                let flag = if (to_bit & context.enemy_pieces) != 0 {
-                    FLAG_CAPTURE
-                } else {
-                    FLAG_NORMAL
-                };
-
-                buffer.push(Moves::new(from_square, to_square, 0, flag, false));
-            }
+        for from in bitboard {
+            let targets =
+                lookup::rook(from, context.occupied) & !context.own_pieces & context.targets(from);
+            push_moves(from, targets, context.enemy_pieces, buffer);
         }
     }
 
     fn get_king_move(&self, bitboard: Bitboard, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        for p in bitboard.iter_bits() {
-            let from_square = p.lsb() as u8;
-            let temp_moves = moves_gen::king::moves(p.get_value()) & !context.own_pieces;
-
-            let mut temp_bb = temp_moves;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                let to_bit = 1u64 << to_square;
-                temp_bb &= temp_bb - 1;
-
-                let flag = if (to_bit & context.enemy_pieces) != 0 {
-                    FLAG_CAPTURE
-                } else {
-                    FLAG_NORMAL
-                };
-
-                buffer.push(Moves::new(from_square, to_square, 0, flag, false));
-            }
+        for from in bitboard {
+            let targets =
+                lookup::king(from) & !context.own_pieces & !context.king_danger & context.filter();
+            push_moves(from, targets, context.enemy_pieces, buffer);
         }
     }
 
@@ -804,18 +1196,18 @@ impl Board {
         match color {
             Color::White => {
                 if self.can_castle(color, Side::Long) {
-                    buffer.push(Moves::new(4, 2, 0, FLAG_CASTLE, false));
+                    buffer.push(Moves::new(Square::E1, Square::C1, FLAG_CASTLE));
                 }
                 if self.can_castle(color, Side::Short) {
-                    buffer.push(Moves::new(4, 6, 0, FLAG_CASTLE, false));
+                    buffer.push(Moves::new(Square::E1, Square::G1, FLAG_CASTLE));
                 }
             }
             Color::Black => {
                 if self.can_castle(color, Side::Long) {
-                    buffer.push(Moves::new(60, 58, 0, FLAG_CASTLE, false));
+                    buffer.push(Moves::new(Square::E8, Square::C8, FLAG_CASTLE));
                 }
                 if self.can_castle(color, Side::Short) {
-                    buffer.push(Moves::new(60, 62, 0, FLAG_CASTLE, false));
+                    buffer.push(Moves::new(Square::E8, Square::G8, FLAG_CASTLE));
                 }
             }
         }
@@ -839,14 +1231,14 @@ impl Board {
                 if !self.white_king || !self.white_rook_long_side {
                     return false;
                 }
-                if (self.get_occupied_pos().get_value() & 0x0E) != 0 {
+                if !(self.get_occupied_pos() & Bitboard::new(0x0E)).is_empty() {
                     return false;
                 }
                 if self.is_king_in_check(color) {
                     return false;
                 }
-                if self.is_square_attacked_by(3, opponent_color)
-                    || self.is_square_attacked_by(2, opponent_color)
+                if self.is_square_attacked_by(Square::D1, opponent_color)
+                    || self.is_square_attacked_by(Square::C1, opponent_color)
                 {
                     return false;
                 }
@@ -856,11 +1248,11 @@ impl Board {
                 if !self.black_king || !self.black_rook_long_side {
                     return false;
                 }
-                if (self.get_occupied_pos().get_value() & 0x0E00000000000000) != 0 {
+                if !(self.get_occupied_pos() & Bitboard::new(0x0E00000000000000)).is_empty() {
                     return false;
                 }
-                if self.is_square_attacked_by(59, opponent_color)
-                    || self.is_square_attacked_by(58, opponent_color)
+                if self.is_square_attacked_by(Square::D8, opponent_color)
+                    || self.is_square_attacked_by(Square::C8, opponent_color)
                 {
                     return false;
                 }
@@ -880,11 +1272,11 @@ impl Board {
                 if !self.white_king || !self.white_rook_short_side {
                     return false;
                 }
-                if (self.get_occupied_pos().get_value() & 0x60) != 0 {
+                if !(self.get_occupied_pos() & Bitboard::new(0x60)).is_empty() {
                     return false;
                 }
-                if self.is_square_attacked_by(5, opponent_color)
-                    || self.is_square_attacked_by(6, opponent_color)
+                if self.is_square_attacked_by(Square::F1, opponent_color)
+                    || self.is_square_attacked_by(Square::G1, opponent_color)
                 {
                     return false;
                 }
@@ -894,11 +1286,11 @@ impl Board {
                 if !self.black_king || !self.black_rook_short_side {
                     return false;
                 }
-                if (self.get_occupied_pos().get_value() & 0x6000000000000000) != 0 {
+                if !(self.get_occupied_pos() & Bitboard::new(0x6000000000000000)).is_empty() {
                     return false;
                 }
-                if self.is_square_attacked_by(61, opponent_color)
-                    || self.is_square_attacked_by(62, opponent_color)
+                if self.is_square_attacked_by(Square::F8, opponent_color)
+                    || self.is_square_attacked_by(Square::G8, opponent_color)
                 {
                     return false;
                 }
@@ -907,108 +1299,20 @@ impl Board {
         }
     }
 
+    /// Whether the king of `color` is attacked.
     #[inline(always)]
     pub fn is_king_in_check(&self, color: Color) -> bool {
-        let king_bb = self.get_pieces(color, Type::King);
-        let king_square = king_bb.get_value().trailing_zeros() as u8;
-
-        self.is_square_attacked_by(
-            king_square,
-            match color {
-                Color::White => Color::Black,
-                Color::Black => Color::White,
-            },
-        )
+        self.is_square_attacked_by(self.king_square(color), color.opposite())
     }
 
-    fn is_square_attacked_by(&self, square: u8, by_color: Color) -> bool {
-        let square_bb = 1u64 << square;
-        let occupied = self.get_occupied_pos().get_value();
-
-        let enemy_pawns = self.get_pieces(by_color, Type::Pawn).get_value();
-        if self.pawn_attacks_square(square, by_color, enemy_pawns) {
-            return true;
-        }
-
-        let knight_attacks = moves_gen::knight::moves(square_bb);
-        let enemy_knights = self.get_pieces(by_color, Type::Knight).get_value();
-        if knight_attacks & enemy_knights != 0 {
-            return true;
-        }
-
-        let king_attacks = moves_gen::king::moves(square_bb);
-        let enemy_king = self.get_pieces(by_color, Type::King).get_value();
-        if king_attacks & enemy_king != 0 {
-            return true;
-        }
-
-        if self.sliding_attacks_square(square, by_color, occupied) {
-            return true;
-        }
-
-        false
+    fn is_square_attacked_by(&self, square: Square, by_color: Color) -> bool {
+        let occupied = self.get_occupied_pos();
+        let attackers = self.attackers_to(square, occupied) & self.get_pieces(by_color, Type::Any);
+        !attackers.is_empty()
     }
 
     #[inline(always)]
-    fn pawn_attacks_square(&self, square: u8, by_color: Color, enemy_pawns: u64) -> bool {
-        match by_color {
-            Color::White => {
-                let attacks = if square >= 8 {
-                    let mut att = 0u64;
-                    if square % 8 != 0 {
-                        att |= 1u64 << (square - 9);
-                    }
-                    if square % 8 != 7 {
-                        att |= 1u64 << (square - 7);
-                    }
-                    att
-                } else {
-                    0
-                };
-                attacks & enemy_pawns != 0
-            }
-            Color::Black => {
-                let attacks = if square < 56 {
-                    let mut att = 0u64;
-                    if square % 8 != 0 {
-                        att |= 1u64 << (square + 7);
-                    }
-                    if square % 8 != 7 {
-                        att |= 1u64 << (square + 9);
-                    }
-                    att
-                } else {
-                    0
-                };
-                attacks & enemy_pawns != 0
-            }
-        }
-    }
-
-    #[inline(always)]
-    fn sliding_attacks_square(&self, square: u8, by_color: Color, occupied: u64) -> bool {
-        let square_bb = 1u64 << square;
-
-        let rook_attacks = moves_gen::rook::moves(square_bb, occupied);
-        let enemy_rooks = self.get_pieces(by_color, Type::Rook).get_value();
-        let enemy_queens = self.get_pieces(by_color, Type::Queen).get_value();
-
-        if rook_attacks & (enemy_rooks | enemy_queens) != 0 {
-            return true;
-        }
-
-        let bishop_attacks = moves_gen::bishop::moves(square_bb, occupied);
-        let enemy_bishops = self.get_pieces(by_color, Type::Bishop).get_value();
-
-        if bishop_attacks & (enemy_bishops | enemy_queens) != 0 {
-            return true;
-        }
-
-        false
-    }
-
-    #[inline(always)]
-    pub fn gen_all_attacks(&self, moves: &mut Vec<Moves>) {
+    pub(crate) fn gen_all_attacks(&self, moves: &mut Vec<Moves>) {
         moves.clear();
         let color = self.get_side();
         let context = &self.create_move_context(color);
@@ -1022,233 +1326,78 @@ impl Board {
 
     fn gen_pawn_captures(&self, color: Color, context: &MoveContext, buffer: &mut Vec<Moves>) {
         let pawns = self.get_pieces(color, Type::Pawn);
-        let enemy_pieces = context.enemy_pieces;
-        let enpassant = self.enpassant.get_value();
-
-        for p in pawns.iter_bits() {
-            let from_square = p.lsb() as u8;
-            let p_val = p.get_value();
-
-            match color {
-                Color::White => {
-                    let capture_moves = moves_gen::pawn::white_attack(p_val, enemy_pieces);
-
-                    let mut temp_bb = capture_moves;
-                    while temp_bb != 0 {
-                        let to_square = temp_bb.trailing_zeros() as u8;
-                        let to_bit = 1u64 << to_square;
-                        temp_bb &= temp_bb - 1;
-
-                        let is_promotion = (to_bit & LASTRANK) != 0;
-
-                        if is_promotion {
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_QUEEN,
-                                FLAG_CAPTURE,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_ROOK,
-                                FLAG_CAPTURE,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_BISHOP,
-                                FLAG_CAPTURE,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_KNIGHT,
-                                FLAG_CAPTURE,
-                                true,
-                            ));
-                        } else {
-                            buffer.push(Moves::new(from_square, to_square, 0, FLAG_CAPTURE, false));
-                        }
-                    }
+        let promotion_rank = color.match_color(LASTRANK, FIRSTRANK);
 
-                    // En passant
-                    if enpassant != 0 {
-                        let landing = enpassant;
-                        let left_hit = (p_val << 7) & landing & !FILE_H;
-                        let right_hit = (p_val << 9) & landing & !FILE_A;
-
-                        if left_hit != 0 || right_hit != 0 {
-                            let to_square = landing.trailing_zeros() as u8;
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                0,
-                                FLAG_EN_PASSANT,
-                                false,
-                            ));
-                        }
-                    }
-                }
-                Color::Black => {
-                    let capture_moves = moves_gen::pawn::black_attack(p_val, enemy_pieces);
-
-                    let mut temp_bb = capture_moves;
-                    while temp_bb != 0 {
-                        let to_square = temp_bb.trailing_zeros() as u8;
-                        let to_bit = 1u64 << to_square;
-                        temp_bb &= temp_bb - 1;
-
-                        let is_promotion = (to_bit & FIRSTRANK) != 0;
-
-                        if is_promotion {
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_QUEEN,
-                                FLAG_CAPTURE,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_ROOK,
-                                FLAG_CAPTURE,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_BISHOP,
-                                FLAG_CAPTURE,
-                                true,
-                            ));
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                PROMOTE_KNIGHT,
-                                FLAG_CAPTURE,
-                                true,
-                            ));
-                        } else {
-                            buffer.push(Moves::new(from_square, to_square, 0, FLAG_CAPTURE, false));
-                        }
-                    }
+        for from in pawns {
+            let attacks = lookup::pawn(color, from);
 
-                    // En passant
-                    if enpassant != 0 {
-                        let landing = enpassant;
-                        let left_hit = (p_val >> 9) & landing & !FILE_H;
-                        let right_hit = (p_val >> 7) & landing & !FILE_A;
-
-                        if left_hit != 0 || right_hit != 0 {
-                            let to_square = landing.trailing_zeros() as u8;
-                            buffer.push(Moves::new(
-                                from_square,
-                                to_square,
-                                0,
-                                FLAG_EN_PASSANT,
-                                false,
-                            ));
-                        }
+            for to in attacks & context.enemy_pieces {
+                if promotion_rank.contains(to) {
+                    for piece in PROMOTION_TYPES {
+                        buffer.push(Moves::new_promotion(from, to, piece, FLAG_CAPTURE));
                     }
+                } else {
+                    buffer.push(Moves::new(from, to, FLAG_CAPTURE));
                 }
             }
+
+            // En passant
+            if let Some(to) = (attacks & self.enpassant).first_square() {
+                buffer.push(Moves::new(from, to, FLAG_EN_PASSANT));
+            }
         }
     }
     fn gen_knight_captures(&self, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        let knight = self.get_pieces(self.get_side(), Type::Knight);
-
-        for p in knight.iter_bits() {
-            let from_sq = p.lsb() as u8;
-
-            let captures = moves_gen::knight::moves(p.get_value()) & context.enemy_pieces;
-            let mut temp_bb = captures;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                temp_bb &= temp_bb - 1;
-                buffer.push(Moves::new(from_sq, to_square, 0, FLAG_CAPTURE, false));
-            }
+        for from in self.get_pieces(self.get_side(), Type::Knight) {
+            let captures = lookup::knight(from) & context.enemy_pieces;
+            push_moves(from, captures, context.enemy_pieces, buffer);
         }
     }
 
     fn gen_king_captures(&self, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        let king = self.get_pieces(self.get_side(), Type::King);
-        for p in king.iter_bits() {
-            let from_sq = p.lsb() as u8;
-
-            let captures = moves_gen::king::moves(p.get_value()) & context.enemy_pieces;
-            let mut temp_bb = captures;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                temp_bb &= temp_bb - 1;
-                buffer.push(Moves::new(from_sq, to_square, 0, FLAG_CAPTURE, false));
-            }
+        for from in self.get_pieces(self.get_side(), Type::King) {
+            let captures = lookup::king(from) & context.enemy_pieces;
+            push_moves(from, captures, context.enemy_pieces, buffer);
         }
     }
 
     fn gen_queen_captures(&self, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        let queen = self.get_pieces(self.get_side(), Type::Queen);
-        for p in queen.iter_bits() {
-            let from_sq = p.lsb() as u8;
-
-            let captures =
-                moves_gen::queen::moves(p.get_value(), context.occupied) & context.enemy_pieces;
-            let mut temp_bb = captures;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                temp_bb &= temp_bb - 1;
-                buffer.push(Moves::new(from_sq, to_square, 0, FLAG_CAPTURE, false));
-            }
+        for from in self.get_pieces(self.get_side(), Type::Queen) {
+            let captures = lookup::queen(from, context.occupied) & context.enemy_pieces;
+            push_moves(from, captures, context.enemy_pieces, buffer);
         }
     }
 
     fn gen_rook_captures(&self, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        let rook = self.get_pieces(self.get_side(), Type::Rook);
-        for p in rook.iter_bits() {
-            let from_sq = p.lsb() as u8;
-
-            let captures =
-                moves_gen::rook::moves(p.get_value(), context.occupied) & context.enemy_pieces;
-            let mut temp_bb = captures;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                temp_bb &= temp_bb - 1;
-                buffer.push(Moves::new(from_sq, to_square, 0, FLAG_CAPTURE, false));
-            }
+        for from in self.get_pieces(self.get_side(), Type::Rook) {
+            let captures = lookup::rook(from, context.occupied) & context.enemy_pieces;
+            push_moves(from, captures, context.enemy_pieces, buffer);
         }
     }
 
     fn gen_bishop_captures(&self, context: &MoveContext, buffer: &mut Vec<Moves>) {
-        let bishop = self.get_pieces(self.get_side(), Type::Bishop);
-        for p in bishop.iter_bits() {
-            let from_sq = p.lsb() as u8;
-
-            let captures =
-                moves_gen::bishop::moves(p.get_value(), context.occupied) & context.enemy_pieces;
-            let mut temp_bb = captures;
-            while temp_bb != 0 {
-                let to_square = temp_bb.trailing_zeros() as u8;
-                temp_bb &= temp_bb - 1;
-                buffer.push(Moves::new(from_sq, to_square, 0, FLAG_CAPTURE, false));
-            }
+        for from in self.get_pieces(self.get_side(), Type::Bishop) {
+            let captures = lookup::bishop(from, context.occupied) & context.enemy_pieces;
+            push_moves(from, captures, context.enemy_pieces, buffer);
         }
     }
 
-    pub fn perform_move(&mut self, mv: &Moves) -> &Board {
+    pub(crate) fn perform_move(&mut self, mv: &Moves) -> &Board {
         let old_castling_index = self.get_castling_index();
-        let old_enpassant = self.enpassant.get_value();
+        let old_enpassant = self.en_passant_square();
         let moving_color = self.get_side();
 
-        let captured_piece_type = if mv.is_capture() && !mv.is_enpassant() {
-            self.get_piece_type_at_square(mv.to())
+        let captured = self.piece_at(mv.to());
+        let is_pawn_move = self.get_piece_type_at_square(mv.from()) == Some(Type::Pawn);
+
+        if is_pawn_move || mv.is_capture() {
+            self.halfmove_clock = 0;
         } else {
-            None
-        };
-        let captured_on_white = (self.white.get_value() & (1u64 << mv.to())) != 0;
+            self.halfmove_clock = self.halfmove_clock.saturating_add(1);
+        }
+        if !self.is_white_turn {
+            self.fullmove_number = self.fullmove_number.wrapping_add(1);
+        }
 
         self.enpassant.set_empty();
 
@@ -1257,7 +1406,7 @@ impl Board {
                 self.perform_castle_move(mv);
             }
             FLAG_EN_PASSANT => {
-                self.perform_enpassant_move(mv, Bitboard::new(old_enpassant));
+                self.perform_enpassant_move(mv);
             }
             _ => {
                 self.perform_normal_move(mv);
@@ -1271,8 +1420,7 @@ impl Board {
             self,
             mv,
             moving_color,
-            captured_piece_type,
-            captured_on_white,
+            captured,
             old_enpassant,
             old_castling_index,
         );
@@ -1281,18 +1429,30 @@ impl Board {
     }
 
     #[inline]
-    pub fn get_castling_index(&self) -> usize {
+    /// Castling rights in FEN order: K, Q, k, q. A right needs both its king
+    /// and its rook to be unmoved.
+    pub fn castling_rights(&self) -> [bool; 4] {
+        [
+            self.white_king && self.white_rook_short_side,
+            self.white_king && self.white_rook_long_side,
+            self.black_king && self.black_rook_short_side,
+            self.black_king && self.black_rook_long_side,
+        ]
+    }
+
+    pub(crate) fn get_castling_index(&self) -> usize {
+        let [white_short, white_long, black_short, black_long] = self.castling_rights();
         let mut index = 0;
-        if self.white_rook_long_side {
+        if white_long {
             index |= 1;
         }
-        if self.white_rook_short_side {
+        if white_short {
             index |= 2;
         }
-        if self.black_rook_long_side {
+        if black_long {
             index |= 4;
         }
-        if self.black_rook_short_side {
+        if black_short {
             index |= 8;
         }
         index
@@ -1302,41 +1462,33 @@ impl Board {
     fn perform_castle_move(&mut self, mv: &Moves) {
         match (mv.from(), mv.to()) {
             // White short castle (e1-g1)
-            (4, 6) => {
-                self.king = self.king.xor(Bitboard::new(0x50));
-                self.rook = self.rook.xor(Bitboard::new(0xA0));
-                self.white = self.white.xor(Bitboard::new(0x50 | 0xA0));
+            (Square::E1, Square::G1) => {
+                self.shift_piece(Square::E1, Square::G1);
+                self.shift_piece(Square::H1, Square::F1);
                 self.white_king = false;
                 self.white_rook_short_side = false;
                 self.white_has_castled = true;
             }
             // White long castle (e1-c1)
-            (4, 2) => {
-                self.king = self.king.xor(Bitboard::new(0x14));
-                self.rook = self.rook.xor(Bitboard::new(0x09));
-                self.white = self.white.xor(Bitboard::new(0x14 | 0x09));
+            (Square::E1, Square::C1) => {
+                self.shift_piece(Square::E1, Square::C1);
+                self.shift_piece(Square::A1, Square::D1);
                 self.white_king = false;
                 self.white_rook_long_side = false;
                 self.white_has_castled = true;
             }
             // Black short castle (e8-g8)
-            (60, 62) => {
-                self.king = self.king.xor(Bitboard::new(0x5000000000000000));
-                self.rook = self.rook.xor(Bitboard::new(0xA000000000000000));
-                self.black = self
-                    .black
-                    .xor(Bitboard::new(0x5000000000000000 | 0xA000000000000000));
+            (Square::E8, Square::G8) => {
+                self.shift_piece(Square::E8, Square::G8);
+                self.shift_piece(Square::H8, Square::F8);
                 self.black_king = false;
                 self.black_rook_short_side = false;
                 self.black_has_castled = true;
             }
             // Black long castle (e8-c8)
-            (60, 58) => {
-                self.king = self.king.xor(Bitboard::new(0x1400000000000000));
-                self.rook = self.rook.xor(Bitboard::new(0x0900000000000000));
-                self.black = self
-                    .black
-                    .xor(Bitboard::new(0x1400000000000000 | 0x0900000000000000));
+            (Square::E8, Square::C8) => {
+                self.shift_piece(Square::E8, Square::C8);
+                self.shift_piece(Square::A8, Square::D8);
                 self.black_king = false;
                 self.black_rook_long_side = false;
                 self.black_has_castled = true;
@@ -1346,131 +1498,76 @@ impl Board {
     }
 
     #[inline]
-    fn perform_enpassant_move(&mut self, mv: &Moves, old_enpassant: Bitboard) {
-        let from_bb = 1u64 << mv.from();
-        let to_bb = 1u64 << mv.to();
+    fn perform_enpassant_move(&mut self, mv: &Moves) {
+        let captured_pawn_square = Square::new(mv.to().file(), mv.from().rank());
 
-        let captured_pawn_square = if self.is_white_turn {
-            old_enpassant.get_value() >> 8
-        } else {
-            old_enpassant.get_value() << 8
-        };
-
-        self.pawn = self.pawn.xor(Bitboard::new(captured_pawn_square));
-
-        if self.is_white_turn {
-            self.black = self.black.xor(Bitboard::new(captured_pawn_square));
-            self.pawn = self.pawn.xor(Bitboard::new(from_bb | to_bb));
-            self.white = self.white.xor(Bitboard::new(from_bb | to_bb));
-        } else {
-            self.white = self.white.xor(Bitboard::new(captured_pawn_square));
-            self.pawn = self.pawn.xor(Bitboard::new(from_bb | to_bb));
-            self.black = self.black.xor(Bitboard::new(from_bb | to_bb));
-        }
+        self.remove_piece(captured_pawn_square);
+        self.shift_piece(mv.from(), mv.to());
     }
 
     #[inline]
     fn perform_normal_move(&mut self, mv: &Moves) {
-        let from_bb = 1u64 << mv.from();
-        let to_bb = 1u64 << mv.to();
-
-        self.queen = self.queen.and(Bitboard::new(!to_bb));
-        self.rook = self.rook.and(Bitboard::new(!to_bb));
-        self.bishop = self.bishop.and(Bitboard::new(!to_bb));
-        self.knight = self.knight.and(Bitboard::new(!to_bb));
-        self.pawn = self.pawn.and(Bitboard::new(!to_bb));
-        self.king = self.king.and(Bitboard::new(!to_bb));
-
-        match to_bb {
-            0x1 => self.white_rook_long_side = false,
-            0x80 => self.white_rook_short_side = false,
-            0x100000000000000 => self.black_rook_long_side = false,
-            0x8000000000000000 => self.black_rook_short_side = false,
+        let (from, to) = (mv.from(), mv.to());
+
+        self.remove_piece(to);
+
+        match to {
+            Square::A1 => self.white_rook_long_side = false,
+            Square::H1 => self.white_rook_short_side = false,
+            Square::A8 => self.black_rook_long_side = false,
+            Square::H8 => self.black_rook_short_side = false,
             _ => {}
         }
 
-        let old_pos_bb = Bitboard::new(from_bb);
-        let new_pos_bb = Bitboard::new(to_bb);
+        let Some(piece) = self.remove_piece(from) else {
+            return;
+        };
 
-        if self.queen.and(old_pos_bb).get_value() != 0 {
-            self.queen = self.queen.xor(Bitboard::new(from_bb | to_bb));
-        } else if self.rook.and(old_pos_bb).get_value() != 0 {
-            self.rook = self.rook.xor(Bitboard::new(from_bb | to_bb));
-            match from_bb {
-                0x1 => self.white_rook_long_side = false,
-                0x80 => self.white_rook_short_side = false,
-                0x100000000000000 => self.black_rook_long_side = false,
-                0x8000000000000000 => self.black_rook_short_side = false,
+        match piece.piece_type {
+            Type::Rook => match from {
+                Square::A1 => self.white_rook_long_side = false,
+                Square::H1 => self.white_rook_short_side = false,
+                Square::A8 => self.black_rook_long_side = false,
+                Square::H8 => self.black_rook_short_side = false,
                 _ => {}
-            }
-        } else if self.bishop.and(old_pos_bb).get_value() != 0 {
-            self.bishop = self.bishop.xor(Bitboard::new(from_bb | to_bb));
-        } else if self.knight.and(old_pos_bb).get_value() != 0 {
-            self.knight = self.knight.xor(Bitboard::new(from_bb | to_bb));
-        } else if self.pawn.and(old_pos_bb).get_value() != 0 {
-            let white_double_move = (from_bb & 0xFF00) != 0 && (to_bb & 0xFF000000) != 0;
-            let black_double_move =
-                (from_bb & 0xFF000000000000) != 0 && (to_bb & 0xFF00000000) != 0;
-
-            if white_double_move {
-                self.enpassant = Bitboard::new(from_bb << 8);
-            } else if black_double_move {
-                self.enpassant = Bitboard::new(from_bb >> 8);
-            }
-
-            if mv.is_promotion() {
-                self.pawn = self.pawn.and(Bitboard::new(!from_bb));
-                match mv.promotion_piece() {
-                    PROMOTE_QUEEN => self.queen = self.queen.or(new_pos_bb),
-                    PROMOTE_ROOK => self.rook = self.rook.or(new_pos_bb),
-                    PROMOTE_BISHOP => self.bishop = self.bishop.or(new_pos_bb),
-                    PROMOTE_KNIGHT => self.knight = self.knight.or(new_pos_bb),
-                    _ => {}
+            },
+            Type::Pawn => {
+                let white_double_move = from.rank() == Rank::SECOND && to.rank() == Rank::FOURTH;
+                let black_double_move = from.rank() == Rank::SEVENTH && to.rank() == Rank::FIFTH;
+
+                if white_double_move {
+                    self.enpassant = Square::new(from.file(), Rank::THIRD).into();
+                } else if black_double_move {
+                    self.enpassant = Square::new(from.file(), Rank::SIXTH).into();
                 }
-            } else {
-                self.pawn = self.pawn.xor(Bitboard::new(from_bb | to_bb));
-            }
-        } else if self.king.and(old_pos_bb).get_value() != 0 {
-            self.king = self.king.xor(Bitboard::new(from_bb | to_bb));
-            if self.is_white_turn {
-                self.white_king = false;
-            } else {
-                self.black_king = false;
             }
+            Type::King => match piece.color {
+                Color::White => self.white_king = false,
+                Color::Black => self.black_king = false,
+            },
+            _ => {}
         }
 
-        self.white = self.white.and(Bitboard::new(!to_bb));
-        self.black = self.black.and(Bitboard::new(!to_bb));
-
-        if self.is_white_turn {
-            self.white = self.white.xor(Bitboard::new(from_bb | to_bb));
-        } else {
-            self.black = self.black.xor(Bitboard::new(from_bb | to_bb));
-        }
+        let placed = match mv.promotion() {
+            Some(promotion) => Piece::new(piece.color, promotion),
+            None => piece,
+        };
+        self.put_piece(to, placed);
     }
 
     #[inline(always)]
-    pub fn make_move_with_undo(&mut self, mv: &Moves) -> UndoInfo {
-        let from_piece = self
-            .get_piece_type_at_square(mv.from())
-            .map_or(255, |t| t.id());
-
-        let captured_piece = self
-            .get_piece_type_at_square(mv.to())
-            .map_or(255, |t| t.id());
-
+    pub(crate) fn make_move_with_undo(&mut self, mv: &Moves) -> UndoInfo {
         let undo_info = UndoInfo {
-            moving_piece_type: from_piece,
-            captured_piece_type: captured_piece,
-            captured_on_white: (self.white.get_value() & (1u64 << mv.to())) != 0,
+            captured: self.piece_at(mv.to()),
             white_rook_long_side: self.white_rook_long_side,
             white_rook_short_side: self.white_rook_short_side,
             black_rook_long_side: self.black_rook_long_side,
             black_rook_short_side: self.black_rook_short_side,
             white_king: self.white_king,
             black_king: self.black_king,
-            old_enpassant: self.enpassant.get_value(),
+            old_enpassant: self.enpassant,
             was_white_turn: self.is_white_turn,
+            old_halfmove_clock: self.halfmove_clock,
             old_hash: self.hash,
         };
 
@@ -1479,15 +1576,19 @@ impl Board {
     }
 
     #[inline(always)]
-    pub fn unmake_move(&mut self, mv: &Moves, undo_info: UndoInfo) {
+    pub(crate) fn unmake_move(&mut self, mv: &Moves, undo_info: UndoInfo) {
         self.is_white_turn = undo_info.was_white_turn;
-        self.enpassant = Bitboard::new(undo_info.old_enpassant);
+        self.enpassant = undo_info.old_enpassant;
         self.white_rook_long_side = undo_info.white_rook_long_side;
         self.white_rook_short_side = undo_info.white_rook_short_side;
         self.black_rook_long_side = undo_info.black_rook_long_side;
         self.black_rook_short_side = undo_info.black_rook_short_side;
         self.white_king = undo_info.white_king;
         self.black_king = undo_info.black_king;
+        self.halfmove_clock = undo_info.old_halfmove_clock;
+        if !undo_info.was_white_turn {
+            self.fullmove_number = self.fullmove_number.wrapping_sub(1);
+        }
         self.hash = undo_info.old_hash;
 
         match mv.flags() {
@@ -1504,138 +1605,71 @@ impl Board {
         }
     }
     fn unmake_simple_move(&mut self, mv: &Moves, undo_info: &UndoInfo) {
-        let from_bb = 1u64 << mv.from();
-        let to_bb = 1u64 << mv.to();
-        let move_xor_bb = Bitboard::new(from_bb | to_bb);
-
-        if mv.is_promotion() {
-            match mv.promotion_piece() {
-                PROMOTE_QUEEN => self.queen = self.queen.and(Bitboard::new(!to_bb)),
-                PROMOTE_ROOK => self.rook = self.rook.and(Bitboard::new(!to_bb)),
-                PROMOTE_BISHOP => self.bishop = self.bishop.and(Bitboard::new(!to_bb)),
-                PROMOTE_KNIGHT => self.knight = self.knight.and(Bitboard::new(!to_bb)),
-                _ => {}
-            }
-            self.pawn = self.pawn.or(Bitboard::new(from_bb));
-        } else if let Some(p_type) = Type::from_id(undo_info.moving_piece_type) {
-            match p_type {
-                Type::Pawn => self.pawn = self.pawn.xor(move_xor_bb),
-                Type::Knight => self.knight = self.knight.xor(move_xor_bb),
-                Type::Bishop => self.bishop = self.bishop.xor(move_xor_bb),
-                Type::Rook => self.rook = self.rook.xor(move_xor_bb),
-                Type::Queen => self.queen = self.queen.xor(move_xor_bb),
-                Type::King => self.king = self.king.xor(move_xor_bb),
-                Type::Any => {}
-            }
-        }
-
-        self.white = self.white.and(Bitboard::new(!(from_bb | to_bb)));
-        self.black = self.black.and(Bitboard::new(!(from_bb | to_bb)));
-
-        if undo_info.was_white_turn {
-            self.white = self.white.or(Bitboard::new(from_bb));
+        let Some(moved) = self.remove_piece(mv.to()) else {
+            return;
+        };
+        let piece = if mv.is_promotion() {
+            Piece::new(moved.color, Type::Pawn)
         } else {
-            self.black = self.black.or(Bitboard::new(from_bb));
-        }
-
-        if undo_info.captured_piece_type != 255 {
-            if let Some(c_type) = Type::from_id(undo_info.captured_piece_type) {
-                let captured_bb = Bitboard::new(to_bb);
-                match c_type {
-                    Type::Pawn => self.pawn = self.pawn.or(captured_bb),
-                    Type::Knight => self.knight = self.knight.or(captured_bb),
-                    Type::Bishop => self.bishop = self.bishop.or(captured_bb),
-                    Type::Rook => self.rook = self.rook.or(captured_bb),
-                    Type::Queen => self.queen = self.queen.or(captured_bb),
-                    Type::King => self.king = self.king.or(captured_bb),
-                    Type::Any => {}
-                }
-                if undo_info.captured_on_white {
-                    self.white = self.white.or(captured_bb);
-                } else {
-                    self.black = self.black.or(captured_bb);
-                }
-            }
+            moved
+        };
+        self.put_piece(mv.from(), piece);
+
+        if let Some(captured) = undo_info.captured {
+            self.put_piece(mv.to(), captured);
         }
     }
 
     fn unmake_castle(&mut self, mv: &Moves) {
         match (mv.from(), mv.to()) {
             // White short castle
-            (4, 6) => {
-                self.king = self.king.xor(Bitboard::new(0x50));
-                self.rook = self.rook.xor(Bitboard::new(0xA0));
-                self.white = self.white.xor(Bitboard::new(0x50 | 0xA0));
+            (Square::E1, Square::G1) => {
+                self.shift_piece(Square::G1, Square::E1);
+                self.shift_piece(Square::F1, Square::H1);
                 self.white_has_castled = false;
             }
             // White long castle
-            (4, 2) => {
-                self.king = self.king.xor(Bitboard::new(0x14));
-                self.rook = self.rook.xor(Bitboard::new(0x09));
-                self.white = self.white.xor(Bitboard::new(0x14 | 0x09));
+            (Square::E1, Square::C1) => {
+                self.shift_piece(Square::C1, Square::E1);
+                self.shift_piece(Square::D1, Square::A1);
                 self.white_has_castled = false;
             }
             // Black short castle
-            (60, 62) => {
-                self.king = self.king.xor(Bitboard::new(0x5000000000000000));
-                self.rook = self.rook.xor(Bitboard::new(0xA000000000000000));
-                self.black = self
-                    .black
-                    .xor(Bitboard::new(0x5000000000000000 | 0xA000000000000000));
+            (Square::E8, Square::G8) => {
+                self.shift_piece(Square::G8, Square::E8);
+                self.shift_piece(Square::F8, Square::H8);
                 self.black_has_castled = false;
             }
             // Black long castle
-            (60, 58) => {
-                self.king = self.king.xor(Bitboard::new(0x1400000000000000));
-                self.rook = self.rook.xor(Bitboard::new(0x0900000000000000));
-                self.black = self
-                    .black
-                    .xor(Bitboard::new(0x1400000000000000 | 0x0900000000000000));
+            (Square::E8, Square::C8) => {
+                self.shift_piece(Square::C8, Square::E8);
+                self.shift_piece(Square::D8, Square::A8);
                 self.black_has_castled = false;
             }
             _ => {}
         }
     }
     fn unmake_enpassant_move(&mut self, mv: &Moves, undo_info: &UndoInfo) {
-        let from_bb = 1u64 << mv.from();
-        let to_bb = 1u64 << mv.to();
-
-        self.pawn = self
-            .pawn
-            .and(Bitboard::new(!to_bb))
-            .or(Bitboard::new(from_bb));
-
-        if undo_info.was_white_turn {
-            self.white = self
-                .white
-                .and(Bitboard::new(!to_bb))
-                .or(Bitboard::new(from_bb));
-
-            let captured_pawn_pos = to_bb >> 8;
-            self.pawn = self.pawn.or(Bitboard::new(captured_pawn_pos));
-            self.black = self.black.or(Bitboard::new(captured_pawn_pos));
-        } else {
-            self.black = self
-                .black
-                .and(Bitboard::new(!to_bb))
-                .or(Bitboard::new(from_bb));
+        self.shift_piece(mv.to(), mv.from());
 
-            let captured_pawn_pos = to_bb << 8;
-            self.pawn = self.pawn.or(Bitboard::new(captured_pawn_pos));
-            self.white = self.white.or(Bitboard::new(captured_pawn_pos));
-        }
+        let captured_pawn_square = Square::new(mv.to().file(), mv.from().rank());
+        let captured_color = if undo_info.was_white_turn {
+            Color::Black
+        } else {
+            Color::White
+        };
+        self.put_piece(captured_pawn_square, Piece::new(captured_color, Type::Pawn));
     }
 
     #[inline(always)]
-    pub fn make_null_move(&mut self) -> NullMoveUndo {
+    pub(crate) fn make_null_move(&mut self) -> NullMoveUndo {
         let undo = NullMoveUndo {
-            enpassant: self.enpassant.get_value(),
+            enpassant: self.enpassant,
             hash: self.hash,
         };
 
-        if self.enpassant.get_value() != 0 {
-            let file = (self.enpassant.lsb() % 8) as usize;
-            self.hash ^= ZOBRIST.en_passant_file[file];
+        if let Some(square) = self.en_passant_square() {
+            self.hash ^= ZOBRIST.en_passant_file[square.file().index() as usize];
             self.enpassant.set_empty();
         }
 
@@ -1647,24 +1681,73 @@ impl Board {
     }
 
     #[inline(always)]
-    pub fn unmake_null_move(&mut self, undo: NullMoveUndo) {
+    pub(crate) fn unmake_null_move(&mut self, undo: NullMoveUndo) {
         self.is_white_turn = !self.is_white_turn;
-        self.enpassant = crate::bitboard::bitboard::Bitboard::new(undo.enpassant);
+        self.enpassant = undo.enpassant;
         self.hash = undo.hash;
     }
 
     #[inline(always)]
-    pub fn has_non_pawn_material(&self, color: Color) -> bool {
-        let knights = self.get_pieces(color, Type::Knight).get_value();
-        let bishops = self.get_pieces(color, Type::Bishop).get_value();
-        let rooks = self.get_pieces(color, Type::Rook).get_value();
-        let queens = self.get_pieces(color, Type::Queen).get_value();
+    pub(crate) fn has_non_pawn_material(&self, color: Color) -> bool {
+        let knights = self.get_pieces(color, Type::Knight);
+        let bishops = self.get_pieces(color, Type::Bishop);
+        let rooks = self.get_pieces(color, Type::Rook);
+        let queens = self.get_pieces(color, Type::Queen);
+
+        !(knights | bishops | rooks | queens).is_empty()
+    }
+
+    /// Dead positions where neither side can ever deliver mate:
+    /// KK, KBK, KNK and any number of bishops all standing on the same colour.
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy = self.pawn | self.rook | self.queen;
+        if !heavy.is_empty() {
+            return false;
+        }
+
+        let minors = (self.knight | self.bishop).count_ones();
+        if minors <= 1 {
+            return true;
+        }
+        if !self.knight.is_empty() {
+            return false;
+        }
+
+        const LIGHT_SQUARES: Bitboard = Bitboard::new(0x55AA55AA55AA55AA);
+        (self.bishop & LIGHT_SQUARES).is_empty() || (self.bishop & !LIGHT_SQUARES).is_empty()
+    }
 
-        (knights | bishops | rooks | queens) != 0
+    /// `history` holds the hashes of every position reached in the game,
+    /// including the current one.
+    pub fn game_status(&self, history: &[u64]) -> GameStatus {
+        let mut buffer = Vec::with_capacity(256);
+        let turn = self.get_side();
+
+        if self.get_legal_moves(turn, &mut buffer).is_empty() {
+            if self.is_king_in_check(turn) {
+                return GameStatus::Checkmate;
+            }
+            return GameStatus::Stalemate;
+        }
+
+        if self.is_insufficient_material() {
+            return GameStatus::InsufficientMaterial;
+        }
+
+        let repetitions = history.iter().filter(|&&h| h == self.hash).count();
+        if repetitions >= 3 {
+            return GameStatus::ThreefoldRepetition;
+        }
+
+        if self.halfmove_clock >= 100 {
+            return GameStatus::FiftyMove;
+        }
+
+        GameStatus::Ongoing
     }
 
     #[inline(always)]
-    pub fn see(&self, mv: &Moves) -> i32 {
+    pub(crate) fn see(&self, mv: &Moves) -> i32 {
         if mv.flags() == FLAG_CASTLE {
             return 0;
         }
@@ -1680,62 +1763,47 @@ impl Board {
             0
         };
 
-        if mv.is_promotion() {
-            let promo_val = mv.promotion_piece_type().value();
-            victim_val += promo_val - Type::Pawn.value();
+        if let Some(promotion) = mv.promotion() {
+            victim_val += promotion.value() - Type::Pawn.value();
         }
 
         let mut aggressor_type = self.get_piece_type_at_square(from).unwrap();
 
-        let mut occupied = self.get_occupied_pos().get_value();
-        occupied &= !(1u64 << from);
+        let mut occupied = self.get_occupied_pos();
+        occupied &= !Bitboard::from(from);
 
         if mv.flags() == FLAG_EN_PASSANT {
-            let cap_sq = if self.is_white_turn { to - 8 } else { to + 8 };
-            occupied &= !(1u64 << cap_sq);
-        }
-
-        if mv.flags() == FLAG_EN_PASSANT {
-            let cap_sq = if self.is_white_turn { to - 8 } else { to + 8 };
-            occupied &= !(1u64 << cap_sq);
+            let cap_sq = Square::new(to.file(), from.rank());
+            occupied &= !Bitboard::from(cap_sq);
         }
 
         let mut scores = Vec::with_capacity(32);
         scores.push(victim_val);
 
-        let mut attackers = self.get_attackers_to_square(to, occupied);
+        let mut attackers = self.attackers_to(to, occupied);
         let mut side_to_move = self.get_side().match_color(Color::Black, Color::White);
 
         loop {
-            let my_attackers = attackers & self.get_pieces(side_to_move, Type::Any).get_value();
+            let my_attackers = attackers & self.get_pieces(side_to_move, Type::Any);
 
-            if my_attackers == 0 {
+            if my_attackers.is_empty() {
                 break;
             }
 
-            let (lva_val, lva_sq) = self.get_lva(my_attackers, side_to_move);
+            let lva_sq = self.get_lva(my_attackers, side_to_move);
             let prev_score = *scores.last().unwrap();
             let piece_val = aggressor_type.value();
             scores.push(piece_val - prev_score);
 
-            aggressor_type = match lva_val {
-                100 => Type::Pawn,
-                320 => Type::Knight,
-                330 => Type::Bishop,
-                500 => Type::Rook,
-                900 => Type::Queen,
-                _ => Type::King,
-            };
-            occupied &= !(1u64 << lva_sq);
+            aggressor_type = self.get_piece_type_at_square(lva_sq).unwrap();
+            occupied &= !Bitboard::from(lva_sq);
             side_to_move = match side_to_move {
                 Color::White => Color::Black,
                 Color::Black => Color::White,
             };
 
-            let diag_attackers =
-                bishop_moves(1u64 << to, occupied) & (self.bishop.or(self.queen).get_value());
-            let orth_attackers =
-                rook_moves(1u64 << to, occupied) & (self.rook.or(self.queen).get_value());
+            let diag_attackers = lookup::bishop(to, occupied) & (self.bishop | self.queen);
+            let orth_attackers = lookup::rook(to, occupied) & (self.rook | self.queen);
             attackers |= diag_attackers | orth_attackers;
             attackers &= occupied;
         }
@@ -1751,35 +1819,19 @@ impl Board {
         final_score
     }
 
+    /// Pieces of both colours that attack `square` with the given occupancy.
+    /// Sliders see through any square left out of `occupied`.
     #[inline(always)]
-    fn get_attackers_to_square(&self, sq: u8, occupied: u64) -> u64 {
-        let sq_bb = 1u64 << sq;
-        let mut attackers = 0;
-
-        let w_pawns = self.get_piece_white(Type::Pawn).get_value();
-        let attacks_from_white = crate::chess::moves_gen::pawn::black_attack(sq_bb, w_pawns);
-        attackers |= attacks_from_white;
-
-        let b_pawns = self.get_piece_black(Type::Pawn).get_value();
-        let attacks_from_black = crate::chess::moves_gen::pawn::white_attack(sq_bb, b_pawns);
-        attackers |= attacks_from_black;
-
-        let knights = self.knight.get_value();
-        attackers |= crate::chess::moves_gen::knight::moves(sq_bb) & knights;
-
-        let kings = self.king.get_value();
-        attackers |= crate::chess::moves_gen::king::moves(sq_bb) & kings;
-
-        let bishops_queens = self.bishop.or(self.queen).get_value();
-        attackers |= bishop_moves(sq_bb, occupied) & bishops_queens;
-
-        let rooks_queens = self.rook.or(self.queen).get_value();
-        attackers |= rook_moves(sq_bb, occupied) & rooks_queens;
-
-        attackers
+    pub fn attackers_to(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        lookup::pawn(Color::Black, square) & self.get_piece_white(Type::Pawn)
+            | lookup::pawn(Color::White, square) & self.get_piece_black(Type::Pawn)
+            | lookup::knight(square) & self.knight
+            | lookup::king(square) & self.king
+            | lookup::bishop(square, occupied) & (self.bishop | self.queen)
+            | lookup::rook(square, occupied) & (self.rook | self.queen)
     }
 
-    fn get_lva(&self, attackers: u64, side: Color) -> (i32, u8) {
+    fn get_lva(&self, attackers: Bitboard, side: Color) -> Square {
         for &pt in &[
             Type::Pawn,
             Type::Knight,
@@ -1788,130 +1840,168 @@ impl Board {
             Type::Queen,
             Type::King,
         ] {
-            let subset = attackers & self.get_pieces(side, pt).get_value();
-            if subset != 0 {
-                let sq = subset.trailing_zeros() as u8;
-                return (pt.value(), sq);
+            if let Some(square) = (attackers & self.get_pieces(side, pt)).first_square() {
+                return square;
             }
         }
-        (0, 0)
+        Square::A1
     }
 
-    #[allow(dead_code)]
-    pub fn to_string(&self) -> String {
-        let mut string =
-            String::from("■□■□■□■□□■□■□■□■■□■□■□■□□■□■□■□■■□■□■□■□□■□■□■□■■□■□■□■□□■□■□■□■");
-
-        let white_pawns = self.get_pieces(Color::White, Type::Pawn).to_string();
-        let white_rooks = self.get_pieces(Color::White, Type::Rook).to_string();
-        let white_queens = self.get_pieces(Color::White, Type::Queen).to_string();
-        let white_kings = self.get_pieces(Color::White, Type::King).to_string();
-        let white_bishops = self.get_pieces(Color::White, Type::Bishop).to_string();
-        let white_knights = self.get_pieces(Color::White, Type::Knight).to_string();
-
-        let black_pawns = self.get_pieces(Color::Black, Type::Pawn).to_string();
-        let black_kings = self.get_pieces(Color::Black, Type::Rook).to_string();
-        let black_queens = self.get_pieces(Color::Black, Type::Queen).to_string();
-        let black_bishops = self.get_pieces(Color::Black, Type::King).to_string();
-        let black_knights = self.get_pieces(Color::Black, Type::Bishop).to_string();
-        let black_rooks = self.get_pieces(Color::Black, Type::Knight).to_string();
-
-        for i in 0..64 {
-            if white_pawns.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♙')
-            }
-
-            if white_rooks.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♖')
-            }
-
-            if white_kings.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♔')
-            }
-
-            if white_bishops.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♗')
-            }
-
-            if white_queens.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♕')
-            }
-
-            if white_knights.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♘')
-            }
-
-            if black_pawns.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♟')
-            }
-
-            if black_kings.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♚')
-            }
+    fn square_glyph(&self, square: Square) -> char {
+        let Some(Piece { color, piece_type }) = self.piece_at(square) else {
+            return if (square.rank().index() + square.file().index()).is_multiple_of(2) {
+                '■'
+            } else {
+                '□'
+            };
+        };
+        match (color, piece_type) {
+            (Color::White, Type::Pawn) => '♙',
+            (Color::White, Type::Knight) => '♘',
+            (Color::White, Type::Bishop) => '♗',
+            (Color::White, Type::Rook) => '♖',
+            (Color::White, Type::Queen) => '♕',
+            (Color::White, _) => '♔',
+            (Color::Black, Type::Pawn) => '♟',
+            (Color::Black, Type::Knight) => '♞',
+            (Color::Black, Type::Bishop) => '♝',
+            (Color::Black, Type::Rook) => '♜',
+            (Color::Black, Type::Queen) => '♛',
+            (Color::Black, _) => '♚',
+        }
+    }
 
-            if black_queens.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♛')
-            }
+    /// Unicode diagram, rank 8 first and the a-file on the left.
+    pub fn to_string(&self) -> String {
+        Rank::ALL
+            .into_iter()
+            .rev()
+            .map(|rank| {
+                File::ALL
+                    .map(|file| self.square_glyph(Square::new(file, rank)).to_string())
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 
-            if black_bishops.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♝')
-            }
+    /// Unicode diagram with rank and file labels, seen from White's side or,
+    /// when `flipped`, from Black's.
+    pub fn diagram(&self, flipped: bool) -> String {
+        let mut ranks = Rank::ALL;
+        let mut files = File::ALL;
+        if flipped {
+            files.reverse();
+        } else {
+            ranks.reverse();
+        }
+        let mut text = String::new();
 
-            if black_knights.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♞')
+        for rank in ranks {
+            text.push(rank.to_char());
+            text.push(' ');
+            for file in files {
+                text.push(' ');
+                text.push(self.square_glyph(Square::new(file, rank)));
             }
+            text.push('\n');
+        }
 
-            if black_rooks.chars().skip(i).take(1).collect::<Vec<_>>()[0] == '1' {
-                string = change_char_in_string(string, i, '♜')
-            }
+        text.push_str("  ");
+        for file in files {
+            text.push(' ');
+            text.push(file.to_char());
         }
+        text
+    }
+}
 
-        string = string
-            .chars()
-            .enumerate()
-            .flat_map(|(i, c)| {
-                if i != 0 { Some(' ') } else { None }
-                    .into_iter()
-                    .chain(std::iter::once(c))
-            })
-            .collect::<String>();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::notation::parse_move;
 
-        let iter_with_newlines = string
-            .chars()
-            .enumerate()
-            .flat_map(|(i, c)| {
-                if i % 16 == 0 {
-                    Either::Left(['\n', c].into_iter())
-                } else {
-                    Either::Right(std::iter::once(c))
-                }
-            })
-            .skip(1);
+    fn status(fen: &str) -> GameStatus {
+        let board = Board::new_from_fen(fen).unwrap();
+        board.game_status(&[board.get_hash()])
+    }
 
-        let string: String = iter_with_newlines.collect();
-        string
+    #[test]
+    fn detects_insufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2K3 b - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            assert!(
+                Board::new_from_fen(fen).unwrap().is_insufficient_material(),
+                "{fen}"
+            );
+            assert_eq!(status(fen), GameStatus::InsufficientMaterial, "{fen}");
+        }
     }
-}
 
-#[allow(dead_code)]
-fn change_char_in_string(s: String, index: usize, new_char: char) -> String {
-    let mut chars: Vec<char> = s.chars().collect();
-    if index < chars.len() {
-        chars[index] = new_char;
+    #[test]
+    fn mating_material_is_not_a_draw() {
+        for fen in [
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2BNK3 w - - 0 1",
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+        ] {
+            assert!(
+                !Board::new_from_fen(fen).unwrap().is_insufficient_material(),
+                "{fen}"
+            );
+            assert_eq!(status(fen), GameStatus::Ongoing, "{fen}");
+        }
     }
-    String::from_iter(chars)
-}
 
-fn algebraic_to_index(alg: &str) -> Option<u8> {
-    if alg.len() != 2 {
-        return None;
+    #[test]
+    fn detects_checkmate_and_stalemate() {
+        assert_eq!(
+            status("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"),
+            GameStatus::Checkmate
+        );
+        assert_eq!(
+            status("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            GameStatus::Stalemate
+        );
     }
-    let chars: Vec<char> = alg.chars().collect();
-    let file_char = chars[0];
-    let rank_char = chars[1];
 
-    let file = file_char.to_ascii_lowercase() as u8 - 'a' as u8;
-    let rank = rank_char.to_digit(10)? as u8 - 1;
+    #[test]
+    fn detects_threefold_repetition() {
+        let mut board = Board::new();
+        let mut history = vec![board.get_hash()];
+        for (ply, text) in ["g1f3", "g8f6", "f3g1", "f6g8"]
+            .iter()
+            .cycle()
+            .take(8)
+            .enumerate()
+        {
+            assert_eq!(
+                board.game_status(&history),
+                GameStatus::Ongoing,
+                "ply {ply}"
+            );
+            let mv = parse_move(&board, text).unwrap();
+            board.make_move_with_undo(&mv);
+            history.push(board.get_hash());
+        }
+        assert_eq!(board.game_status(&history), GameStatus::ThreefoldRepetition);
+    }
 
-    Some(rank * 8 + file)
+    #[test]
+    fn applies_the_fifty_move_rule_at_100_halfmoves() {
+        assert_eq!(
+            status("4k3/8/8/8/8/8/8/R3K3 w - - 99 80"),
+            GameStatus::Ongoing
+        );
+        assert_eq!(
+            status("4k3/8/8/8/8/8/8/R3K3 w - - 100 80"),
+            GameStatus::FiftyMove
+        );
+    }
 }
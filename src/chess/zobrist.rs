@@ -1,9 +1,11 @@
 use lazy_static::lazy_static;
-use rand::RngCore;
 
 use crate::chess::{
     moves_gen::moves_struct::Moves,
-    table::{Board, Color, Type},
+    polyglot::{CASTLE_OFFSET, EN_PASSANT_OFFSET, POLYGLOT_RANDOM, TURN_OFFSET},
+    prng::Prng,
+    square::Square,
+    table::{Board, Color, Piece, Type},
 };
 
 pub struct ZobristTable {
@@ -13,19 +15,36 @@ pub struct ZobristTable {
     pub black_to_move: u64,
 }
 
+// Fixed so that hashes, and with them search results, are the same on every run
+pub const ZOBRIST_SEED: u64 = 0x5EED_C0FF_EE15_600D;
+
 lazy_static! {
-    pub static ref ZOBRIST: ZobristTable = ZobristTable::new();
+    pub static ref ZOBRIST: ZobristTable = if cfg!(feature = "polyglot-zobrist") {
+        ZobristTable::polyglot()
+    } else {
+        ZobristTable::new()
+    };
+}
+
+impl Default for ZobristTable {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ZobristTable {
     pub fn new() -> Self {
+        ZobristTable::from_seed(ZOBRIST_SEED)
+    }
+
+    pub fn from_seed(seed: u64) -> Self {
         let mut z = ZobristTable {
             pieces: [[0; 64]; 12],
             castling_rights: [0; 16],
             en_passant_file: [0; 8],
             black_to_move: 0,
         };
-        let mut rng = rand::rng();
+        let mut rng = Prng::new(seed);
         for i in 0..12 {
             for j in 0..64 {
                 z.pieces[i][j] = rng.next_u64()
@@ -44,46 +63,60 @@ impl ZobristTable {
         z
     }
 
-    pub fn compute_hash(&self, b: &Board) -> u64 {
-        let mut hash = 0;
+    /// Keys taken from the Polyglot random numbers. Hashes then match
+    /// `polyglot_key` XORed with the Polyglot turn key, except when an en
+    /// passant square is set that no pawn can capture on.
+    pub fn polyglot() -> Self {
+        let mut z = ZobristTable {
+            pieces: [[0; 64]; 12],
+            castling_rights: [0; 16],
+            en_passant_file: [0; 8],
+            black_to_move: POLYGLOT_RANDOM[TURN_OFFSET],
+        };
 
-        for p in b.white.iter_bits() {
-            let sq = p.lsb() as usize;
-            let piece_type = b.get_piece_type_at_square(sq as u8).unwrap();
-            let piece_type_id = piece_type.id() as usize;
-            hash ^= self.pieces[piece_type_id][sq];
+        for piece_type in 0..6 {
+            for sq in 0..64 {
+                // Polyglot orders pieces as black pawn, white pawn, black knight, ...
+                z.pieces[piece_type][sq] = POLYGLOT_RANDOM[64 * (2 * piece_type + 1) + sq];
+                z.pieces[piece_type + 6][sq] = POLYGLOT_RANDOM[64 * (2 * piece_type) + sq];
+            }
         }
 
-        for p in b.black.iter_bits() {
-            let sq = p.lsb() as usize;
-            let piece_type = b.get_piece_type_at_square(sq as u8).unwrap();
-            let piece_type_id = piece_type.id() as usize;
-            hash ^= self.pieces[piece_type_id + 6][sq];
+        // Castling index bits: white long, white short, black long, black short.
+        // Polyglot keys: white short, white long, black short, black long.
+        let polyglot_castle = [1, 0, 3, 2];
+        for index in 0..16 {
+            for (bit, &key) in polyglot_castle.iter().enumerate() {
+                if index & (1 << bit) != 0 {
+                    z.castling_rights[index] ^= POLYGLOT_RANDOM[CASTLE_OFFSET + key];
+                }
+            }
         }
 
-        let mut castle_index = 0;
-        if b.white_rook_long_side {
-            castle_index |= 0b0001
-        }
-        if b.white_rook_short_side {
-            castle_index |= 0b0010
+        for file in 0..8 {
+            z.en_passant_file[file] = POLYGLOT_RANDOM[EN_PASSANT_OFFSET + file];
         }
-        if b.black_rook_long_side {
-            castle_index |= 0b0100
-        }
-        if b.black_rook_short_side {
-            castle_index |= 0b1000
+
+        z
+    }
+
+    pub fn compute_hash(&self, b: &Board) -> u64 {
+        let mut hash = 0;
+
+        for sq in Square::iter() {
+            if let Some(piece) = b.piece_at(sq) {
+                hash ^=
+                    self.pieces[get_piece_index(piece.color, piece.piece_type)][usize::from(sq)];
+            }
         }
 
-        hash ^= self.castling_rights[castle_index];
+        hash ^= self.castling_rights[b.get_castling_index()];
 
-        if b.enpassant.get_value() != 0 {
-            let sq = b.enpassant.lsb();
-            let file = (sq % 8) as usize;
-            hash ^= self.en_passant_file[file]
+        if let Some(sq) = b.en_passant_square() {
+            hash ^= self.en_passant_file[sq.file().index() as usize]
         }
 
-        if !b.is_white_turn {
+        if b.get_side() == Color::Black {
             hash ^= self.black_to_move
         }
 
@@ -96,28 +129,19 @@ impl ZobristTable {
         b: &Board,
         mv: &Moves,
         moving_color: Color,
-        captured_piece_type: Option<Type>,
-        captured_on_white: bool,
-        old_enpassant: u64,
+        captured: Option<Piece>,
+        old_enpassant: Option<Square>,
         old_castling_index: usize,
     ) -> u64 {
         let mut new_hash = old_hash;
 
-        let from_sq = mv.from() as usize;
-        let to_sq = mv.to() as usize;
+        let from_sq = mv.from().index() as usize;
+        let to_sq = mv.to().index() as usize;
 
         // enpassant
-        let old_ep_file = if old_enpassant != 0 {
-            Some((old_enpassant.trailing_zeros() % 8) as usize)
-        } else {
-            None
-        };
+        let old_ep_file = old_enpassant.map(|sq| sq.file().index() as usize);
 
-        let new_ep_file = if b.enpassant.get_value() != 0 {
-            Some((b.enpassant.lsb() % 8) as usize)
-        } else {
-            None
-        };
+        let new_ep_file = b.en_passant_square().map(|sq| sq.file().index() as usize);
 
         if old_ep_file != new_ep_file {
             if let Some(old_file) = old_ep_file {
@@ -143,15 +167,14 @@ impl ZobristTable {
             new_hash ^= self.pieces[king_index][from_sq];
             new_hash ^= self.pieces[king_index][to_sq];
 
-            let (rook_from, rook_to) = get_rook_squares_for_castling(from_sq as u8, to_sq as u8);
-            new_hash ^= self.pieces[rook_index][rook_from];
-            new_hash ^= self.pieces[rook_index][rook_to];
+            let (rook_from, rook_to) = get_rook_squares_for_castling(mv.from(), mv.to());
+            new_hash ^= self.pieces[rook_index][rook_from.index() as usize];
+            new_hash ^= self.pieces[rook_index][rook_to.index() as usize];
         } else {
             let moving_piece_type = if mv.is_promotion() {
                 Type::Pawn
             } else {
-                b.get_piece_type_at_square(to_sq as u8)
-                    .unwrap_or(Type::Pawn)
+                b.get_piece_type_at_square(mv.to()).unwrap_or(Type::Pawn)
             };
 
             let moving_piece_index = get_piece_index(moving_color, moving_piece_type);
@@ -159,13 +182,8 @@ impl ZobristTable {
             new_hash ^= self.pieces[moving_piece_index][from_sq];
 
             if mv.is_capture() && !mv.is_enpassant() {
-                if let Some(captured_type) = captured_piece_type {
-                    let captured_color = if captured_on_white {
-                        Color::White
-                    } else {
-                        Color::Black
-                    };
-                    let captured_index = get_piece_index(captured_color, captured_type);
+                if let Some(captured) = captured {
+                    let captured_index = get_piece_index(captured.color, captured.piece_type);
                     new_hash ^= self.pieces[captured_index][to_sq];
                 }
             }
@@ -185,8 +203,7 @@ impl ZobristTable {
                 new_hash ^= self.pieces[pawn_index][captured_pawn_sq];
             }
 
-            if mv.is_promotion() {
-                let promoted_type = mv.promotion_piece_type();
+            if let Some(promoted_type) = mv.promotion() {
                 let promoted_index = get_piece_index(moving_color, promoted_type);
                 new_hash ^= self.pieces[promoted_index][to_sq];
             } else {
@@ -208,12 +225,56 @@ fn get_piece_index(c: Color, t: Type) -> usize {
     }
 }
 
-fn get_rook_squares_for_castling(king_from: u8, king_to: u8) -> (usize, usize) {
+fn get_rook_squares_for_castling(king_from: Square, king_to: Square) -> (Square, Square) {
     match (king_from, king_to) {
-        (4, 6) => (7, 5),
-        (4, 2) => (0, 3),
-        (60, 62) => (63, 61),
-        (60, 58) => (56, 59),
+        (Square::E1, Square::G1) => (Square::H1, Square::F1),
+        (Square::E1, Square::C1) => (Square::A1, Square::D1),
+        (Square::E8, Square::G8) => (Square::H8, Square::F8),
+        (Square::E8, Square::C8) => (Square::A8, Square::D8),
         _ => panic!("Invalid castling move: from {} to {}", king_from, king_to),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pinned so that a change to the generator or the seed, which would
+    /// silently change every hash, fails here.
+    #[test]
+    fn seeded_keys_are_fixed() {
+        let z = ZobristTable::new();
+        assert_eq!(z.pieces[0][0], 0x0c489495f8554acd);
+        assert_eq!(z.pieces[11][63], 0x2275aa53360fa781);
+        assert_eq!(z.castling_rights[15], 0x4d38f54ab4f1cdcb);
+        assert_eq!(z.en_passant_file[7], 0xf3b728c38c97c4a8);
+        assert_eq!(z.black_to_move, 0x980a38b692b56e48);
+        assert_eq!(z.compute_hash(&Board::new()), 0x0960f190fbff21c8);
+
+        let again = ZobristTable::from_seed(ZOBRIST_SEED);
+        assert_eq!(again.pieces, z.pieces);
+        assert_eq!(again.castling_rights, z.castling_rights);
+        assert_eq!(again.en_passant_file, z.en_passant_file);
+
+        let other = ZobristTable::from_seed(ZOBRIST_SEED + 1);
+        assert_ne!(other.pieces[0][0], z.pieces[0][0]);
+    }
+
+    #[cfg(feature = "polyglot-zobrist")]
+    #[test]
+    fn polyglot_feature_gives_reference_keys() {
+        use crate::chess::notation::parse_move;
+        use crate::chess::polyglot::polyglot_key;
+
+        let turn = POLYGLOT_RANDOM[TURN_OFFSET];
+        let mut board = Board::new();
+        assert_eq!(board.get_hash() ^ turn, 0x463b96181691fc9c);
+
+        // e5 can take on f6, so the en passant key counts in both hashes
+        for text in ["e2e4", "d7d5", "e4e5", "f7f5"] {
+            board.make_move_with_undo(&parse_move(&board, text).unwrap());
+        }
+        assert_eq!(board.get_hash() ^ turn, polyglot_key(&board));
+        assert_eq!(board.get_hash() ^ turn, 0x22a48b5a8e47ff78);
+    }
+}
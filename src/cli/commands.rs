@@ -0,0 +1,287 @@
+use crate::book::builder::{BookBuilder, DEFAULT_MAX_PLY};
+use crate::cecp::engine::CecpEngine;
+use crate::datagen::generator::{parse_datagen_args, run_datagen};
+use crate::engine::bench::{parse_bench_args, run_bench};
+use crate::engine::perft::{start_perft, start_perft_divide, start_perft_fen, start_perft_plus};
+use crate::engine::test_suite::{DEFAULT_TIME_MS, SuiteLimits, start_epd};
+use crate::pgn::reader::parse_games;
+use crate::pgn::writer::write_game;
+use crate::play::repl::{parse_play_args, run_play};
+use crate::tournament::runner::{parse_match_args, run_match};
+use crate::uci::uci::UciEngine;
+use crate::{Board, Engine, MAX_DEPTH};
+use std::io::{self, BufRead};
+use std::vec;
+
+/// Runs the `chessengine` binary with its command-line arguments, program
+/// name included.
+pub fn run(args: &[String]) {
+    if args.len() < 2 {
+        start_uci();
+        return;
+    }
+
+    let command = &args[1];
+    match command.as_str() {
+        "perft" => {
+            if args.len() < 3 {
+                panic!("depth not found")
+            }
+            let mut flag = String::new();
+            let mut depth_index = 2;
+            match args[2].as_str() {
+                "p" | "v" | "f" => {
+                    flag = args[2].to_string();
+                    depth_index = 3
+                }
+                _ => {}
+            }
+            let mut fen = String::new();
+            if args.len() == 5 && flag == "f" {
+                depth_index += 1;
+                fen = args[3].to_string()
+            }
+            let depth: u8 = match args[depth_index].parse() {
+                Ok(n) => n,
+                Err(_) => {
+                    eprintln!("'{}' not a valid depth", args[depth_index]);
+                    return;
+                }
+            };
+            start_perft_analyses(flag, fen, depth);
+        }
+        "search" => start_search(),
+        "book" => start_book(&args[2..]),
+        "pgn" => start_pgn(&args[2..]),
+        "epd" => start_epd_suite(&args[2..]),
+        "bench" => start_bench(&args[2..]),
+        "match" => start_match(&args[2..]),
+        "datagen" => start_datagen(&args[2..]),
+        "play" => start_play(&args[2..]),
+        _ => {
+            panic!("unknow argument {}", command.as_str())
+        }
+    }
+}
+
+fn start_perft_analyses(flag: String, fen: String, depth: u8) {
+    match flag.as_str() {
+        "p" => start_perft_plus(depth),
+        "v" => start_perft_divide(depth),
+        "f" => {
+            if fen.is_empty() {
+                eprintln!("FEN string is empty or missing.");
+                return;
+            }
+            start_perft_fen(fen.as_str(), depth)
+        }
+        _ => start_perft(depth),
+    }
+}
+
+fn start_book(args: &[String]) {
+    const USAGE: &str = "usage: book build [--ply N] <out.bin> <games.pgn>...";
+
+    if args.first().map(String::as_str) != Some("build") {
+        eprintln!("{}", USAGE);
+        return;
+    }
+
+    let mut max_ply = DEFAULT_MAX_PLY;
+    let mut files = Vec::new();
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--ply" {
+            match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => max_ply = n,
+                None => {
+                    eprintln!("--ply expects a number");
+                    return;
+                }
+            }
+        } else {
+            files.push(arg.as_str());
+        }
+    }
+
+    if files.len() < 2 {
+        eprintln!("{}", USAGE);
+        return;
+    }
+
+    let mut builder = BookBuilder::new(max_ply);
+    for pgn in &files[1..] {
+        if let Err(e) = builder.add_pgn_file(pgn) {
+            eprintln!("{}", e);
+            return;
+        }
+    }
+
+    match builder.write(files[0]) {
+        Ok(stats) => println!(
+            "games {} skipped {} positions {} entries {}",
+            stats.games, stats.skipped, stats.positions, stats.entries
+        ),
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn start_pgn(args: &[String]) {
+    let Some(path) = args.first() else {
+        eprintln!("usage: pgn <games.pgn>");
+        return;
+    };
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", path, e);
+            return;
+        }
+    };
+
+    for (index, game) in parse_games(&text).into_iter().enumerate() {
+        match game {
+            Ok(game) => print!("{}", write_game(&game)),
+            Err(e) => eprintln!("game {}: {}", index + 1, e),
+        }
+    }
+}
+
+fn start_epd_suite(args: &[String]) {
+    const USAGE: &str = "usage: epd <suite.epd> [--depth N] [--time MS]";
+
+    let mut path = None;
+    let mut depth = None;
+    let mut time_ms = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--depth" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => depth = Some(n),
+                None => {
+                    eprintln!("--depth expects a number");
+                    return;
+                }
+            },
+            "--time" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => time_ms = Some(n),
+                None => {
+                    eprintln!("--time expects a number of milliseconds");
+                    return;
+                }
+            },
+            _ => path = Some(arg.as_str()),
+        }
+    }
+
+    let Some(path) = path else {
+        eprintln!("{}", USAGE);
+        return;
+    };
+
+    // A depth alone gives a fixed depth search, otherwise the time budget applies
+    let limits = SuiteLimits {
+        depth: depth.unwrap_or(MAX_DEPTH),
+        time_ms: time_ms.or(if depth.is_some() {
+            None
+        } else {
+            Some(DEFAULT_TIME_MS)
+        }),
+    };
+    start_epd(path, limits);
+}
+
+fn start_bench(args: &[String]) {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match parse_bench_args(&args) {
+        Ok((depth, hash_mb)) => {
+            run_bench(depth, hash_mb);
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn start_match(args: &[String]) {
+    let result = parse_match_args(args).and_then(|config| run_match(&config));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+fn start_play(args: &[String]) {
+    let result = parse_play_args(args).and_then(|config| run_play(&config));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+fn start_datagen(args: &[String]) {
+    let result = parse_datagen_args(args).and_then(|config| run_datagen(&config));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+fn start_search() {
+    let test_cases = vec![
+        (
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "1. Initial Position (Opening)",
+        ),
+        ("8/8/8/8/5R2/8/5k2/5K2 w - - 0 1", "2. Mate in 1 (White)"),
+        (
+            "8/8/5K2/8/8/7Q/6kr/8 b - - 0 1",
+            "3. Forced Stalemate (Black)",
+        ),
+        (
+            "rnbqkb1r/pp3ppp/3p1n2/2p5/4P3/5N2/PPPPBPPP/RNBQ1RK1 b kq - 3 6",
+            "4. Tactical Gain (Black)",
+        ),
+        (
+            "8/6pk/8/8/2B5/7P/2PP2P1/5RK1 w - - 0 35",
+            "5. Mate in 2 (White)",
+        ),
+    ];
+
+    let mut engine = Engine::new();
+
+    for (fen, name) in test_cases {
+        match Board::new_from_fen(fen) {
+            Ok(b) => {
+                let start_time = std::time::Instant::now();
+                let game_history: Vec<u64> = Vec::new();
+
+                let best_move = engine.find_best_move(&b, 5, &game_history, Some(10000000));
+                let elapsed = start_time.elapsed();
+
+                let move_str = best_move.to_string();
+
+                println!("--- {} ---", name);
+                println!("FEN: {}", fen);
+                println!("Best Move Found: {}", move_str);
+                println!("Search Time: {} ms", elapsed.as_millis());
+                println!();
+            }
+            Err(e) => {
+                println!("FEN PARSING ERROR for {}: {}", fen, e);
+            }
+        }
+    }
+}
+
+/// Speaks XBoard when the first command is `xboard`, UCI otherwise.
+fn start_uci() {
+    let stdin = io::stdin();
+    let mut lines = stdin
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .skip_while(|line| line.trim().is_empty())
+        .peekable();
+
+    if lines.peek().is_some_and(|line| line.trim() == "xboard") {
+        CecpEngine::new().run(lines);
+    } else {
+        UciEngine::new().run(lines);
+    }
+}
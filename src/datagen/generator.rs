@@ -0,0 +1,434 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::chess::table::{Board, GameStatus};
+use crate::engine::find_best_move::{Engine, MAX_DEPTH};
+use crate::pgn::game::GameResult;
+
+// Openings the engine already scores beyond this are thrown away
+const MAX_OPENING_SCORE: i32 = 400;
+// Scores at or beyond this are mates or tablebase wins and are not recorded
+const MAX_RECORDED_SCORE: i32 = 10000;
+// A game is won once the score stays beyond WIN_SCORE for WIN_PLIES plies
+const WIN_SCORE: i32 = 1500;
+const WIN_PLIES: usize = 6;
+// After DRAW_MIN_PLY, a game is drawn once the score stays within DRAW_SCORE
+// of zero for DRAW_PLIES plies
+const DRAW_SCORE: i32 = 10;
+const DRAW_PLIES: usize = 12;
+const DRAW_MIN_PLY: usize = 80;
+const MAX_PLIES: usize = 400;
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+pub struct DatagenConfig {
+    pub output: String,
+    /// Number of positions the output file should hold when done.
+    pub positions: u64,
+    pub threads: usize,
+    pub nodes: u64,
+    pub random_plies: usize,
+    pub hash_mb: usize,
+    pub seed: u64,
+}
+
+pub const DATAGEN_USAGE: &str = "usage: datagen --output FILE [--positions N] [--threads N] [--nodes N] [--random-plies N] [--hash MB] [--seed N]";
+
+fn next_value<'a>(
+    iter: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<&'a str, String> {
+    iter.next()
+        .map(String::as_str)
+        .ok_or_else(|| format!("{} expects a value", flag))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+pub fn parse_datagen_args(args: &[String]) -> Result<DatagenConfig, String> {
+    let mut output = None;
+    let mut config = DatagenConfig {
+        output: String::new(),
+        positions: 100_000,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        nodes: 5000,
+        random_plies: 8,
+        hash_mb: 16,
+        seed: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64),
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let flag = arg.as_str();
+        match flag {
+            "--output" => output = Some(next_value(&mut iter, flag)?.to_string()),
+            "--positions" => config.positions = parse_number(next_value(&mut iter, flag)?, flag)?,
+            "--threads" => config.threads = parse_number(next_value(&mut iter, flag)?, flag)?,
+            "--nodes" => config.nodes = parse_number(next_value(&mut iter, flag)?, flag)?,
+            "--random-plies" => {
+                config.random_plies = parse_number(next_value(&mut iter, flag)?, flag)?
+            }
+            "--hash" => config.hash_mb = parse_number(next_value(&mut iter, flag)?, flag)?,
+            "--seed" => config.seed = parse_number(next_value(&mut iter, flag)?, flag)?,
+            _ => return Err(format!("Unknown option {}\n{}", flag, DATAGEN_USAGE)),
+        }
+    }
+
+    config.output = output.ok_or_else(|| DATAGEN_USAGE.to_string())?;
+    config.threads = config.threads.max(1);
+    Ok(config)
+}
+
+/// SplitMix64 finalizer, used to derive independent seeds for every thread.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// Counts the complete lines of an existing output file and cuts off a
+/// partial last line left behind by an interrupted run.
+fn count_positions(path: &str) -> Result<u64, String> {
+    let io_error = |e: std::io::Error| format!("Cannot read {}: {}", path, e);
+    let file = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(io_error(e)),
+    };
+
+    let mut reader = BufReader::new(&file);
+    let mut lines = 0;
+    let mut complete_len = 0;
+    let mut offset = 0;
+    loop {
+        let buffer = reader.fill_buf().map_err(io_error)?;
+        if buffer.is_empty() {
+            break;
+        }
+        for (i, &byte) in buffer.iter().enumerate() {
+            if byte == b'\n' {
+                lines += 1;
+                complete_len = offset + i as u64 + 1;
+            }
+        }
+        let len = buffer.len();
+        offset += len as u64;
+        reader.consume(len);
+    }
+
+    if complete_len < offset {
+        file.set_len(complete_len).map_err(io_error)?;
+        eprintln!("Removed an incomplete line at the end of {}", path);
+    }
+    Ok(lines)
+}
+
+/// Plays random moves from the initial position. `None` when the game ends
+/// during the opening.
+fn random_opening(rng: &mut StdRng, plies: usize) -> Option<(Board, Vec<u64>)> {
+    let mut board = Board::new();
+    let mut history = vec![board.get_hash()];
+    let mut buffer = Vec::with_capacity(256);
+
+    for _ in 0..plies {
+        let moves = board.get_legal_moves(board.get_side(), &mut buffer);
+        if moves.is_empty() {
+            return None;
+        }
+        let mv = moves[rng.random_range(0..moves.len())];
+        board.make_move_with_undo(&mv);
+        history.push(board.get_hash());
+    }
+
+    (board.game_status(&history) == GameStatus::Ongoing).then_some((board, history))
+}
+
+/// Plays one self-play game and returns its quiet positions as
+/// `fen | score | result` lines, with the score in centipawns and the result
+/// both from White's point of view. `None` when the opening is rejected.
+fn play_game(engine: &mut Engine, rng: &mut StdRng, random_plies: usize) -> Option<Vec<String>> {
+    let (mut board, mut history) = random_opening(rng, random_plies)?;
+    engine.clear();
+
+    // The search adds the current position to the history itself
+    engine.find_best_move(&board, MAX_DEPTH, &history[..history.len() - 1], None);
+    if engine.score.abs() > MAX_OPENING_SCORE {
+        return None;
+    }
+
+    let mut positions = Vec::new();
+    let mut win_plies = 0;
+    let mut draw_plies = 0;
+    let mut last_score = 0;
+
+    let result = loop {
+        let side = board.get_side();
+        match board.game_status(&history) {
+            GameStatus::Ongoing => {}
+            GameStatus::Checkmate => {
+                break side.match_color(GameResult::BlackWins, GameResult::WhiteWins);
+            }
+            _ => break GameResult::Draw,
+        }
+        let ply = history.len() - 1;
+        if ply >= MAX_PLIES {
+            break GameResult::Draw;
+        }
+
+        let mv = engine.find_best_move(&board, MAX_DEPTH, &history[..history.len() - 1], None);
+        let score = side.match_color(engine.score, -engine.score);
+
+        if score.abs() >= WIN_SCORE && (score > 0) == (last_score > 0) {
+            win_plies += 1;
+        } else {
+            win_plies = 0;
+        }
+        if ply >= DRAW_MIN_PLY && score.abs() <= DRAW_SCORE {
+            draw_plies += 1;
+        } else {
+            draw_plies = 0;
+        }
+        last_score = score;
+
+        if win_plies >= WIN_PLIES {
+            break if score > 0 {
+                GameResult::WhiteWins
+            } else {
+                GameResult::BlackWins
+            };
+        }
+        if draw_plies >= DRAW_PLIES {
+            break GameResult::Draw;
+        }
+
+        if !board.is_king_in_check(side)
+            && !mv.is_capture()
+            && !mv.is_promotion()
+            && score.abs() < MAX_RECORDED_SCORE
+        {
+            positions.push((board.get_fen(), score));
+        }
+
+        board.make_move_with_undo(&mv);
+        history.push(board.get_hash());
+    };
+
+    let white_score = result.white_score().unwrap_or(0.5);
+    Some(
+        positions
+            .into_iter()
+            .map(|(fen, score)| format!("{} | {} | {:.1}\n", fen, score, white_score))
+            .collect(),
+    )
+}
+
+struct Progress {
+    file: File,
+    written: u64,
+    games: u64,
+}
+
+/// Generates positions until the output file holds `config.positions` of
+/// them. An existing file is continued, so an interrupted run can be resumed
+/// by starting it again with the same arguments.
+pub fn run_datagen(config: &DatagenConfig) -> Result<u64, String> {
+    let existing = count_positions(&config.output)?;
+    if existing >= config.positions {
+        println!("{} already holds {} positions", config.output, existing);
+        return Ok(existing);
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&config.output)
+        .map_err(|e| format!("Cannot write {}: {}", config.output, e))?;
+    println!(
+        "Generating {} positions into {} with {} threads at {} nodes per move",
+        config.positions - existing,
+        config.output,
+        config.threads,
+        config.nodes
+    );
+
+    let progress = Mutex::new(Progress {
+        file,
+        written: existing,
+        games: 0,
+    });
+    let rejected = AtomicU64::new(0);
+    let start = Instant::now();
+
+    let worker = |index: usize| -> Result<(), String> {
+        // Continuing a file must not replay the games already in it
+        let mut rng = StdRng::seed_from_u64(mix(config.seed ^ mix(existing ^ mix(index as u64))));
+        let mut engine = Engine::with_hash(config.hash_mb);
+        engine.node_limit = Some(config.nodes);
+
+        loop {
+            if progress.lock().unwrap().written >= config.positions {
+                return Ok(());
+            }
+            let Some(lines) = play_game(&mut engine, &mut rng, config.random_plies) else {
+                rejected.fetch_add(1, Ordering::Relaxed);
+                continue;
+            };
+
+            let mut progress = progress.lock().unwrap();
+            let remaining = config.positions.saturating_sub(progress.written) as usize;
+            let lines = &lines[..lines.len().min(remaining)];
+            // One write per game keeps an interrupted run to one partial line
+            progress
+                .file
+                .write_all(lines.concat().as_bytes())
+                .map_err(|e| format!("Cannot write {}: {}", config.output, e))?;
+
+            let before = progress.written;
+            progress.written += lines.len() as u64;
+            progress.games += 1;
+            if progress.written / PROGRESS_INTERVAL > before / PROGRESS_INTERVAL
+                || progress.written >= config.positions
+            {
+                let generated = progress.written - existing;
+                let seconds = start.elapsed().as_secs_f64().max(0.001);
+                println!(
+                    "Positions {}/{} games {} ({:.0} positions/s)",
+                    progress.written,
+                    config.positions,
+                    progress.games,
+                    generated as f64 / seconds
+                );
+            }
+        }
+    };
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..config.threads)
+            .map(|index| scope.spawn(move || worker(index)))
+            .collect();
+        handles
+            .into_iter()
+            .try_for_each(|handle| handle.join().unwrap_or(Err("Worker panicked".to_string())))
+    })?;
+
+    let progress = progress.into_inner().unwrap();
+    println!(
+        "Done: {} positions from {} games, {} openings rejected",
+        progress.written - existing,
+        progress.games,
+        rejected.load(Ordering::Relaxed)
+    );
+    Ok(progress.written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// A fresh file path in the temporary directory, unique per test.
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "chessengine-datagen-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn config(path: &Path, positions: u64) -> DatagenConfig {
+        DatagenConfig {
+            output: path.to_str().unwrap().to_string(),
+            positions,
+            threads: 1,
+            nodes: 200,
+            random_plies: 8,
+            hash_mb: 1,
+            seed: 7,
+        }
+    }
+
+    #[test]
+    fn counts_nothing_without_a_file() {
+        let path = temp_path("missing");
+        assert_eq!(count_positions(path.to_str().unwrap()), Ok(0));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn counts_complete_lines_and_cuts_a_partial_one() {
+        let path = temp_path("partial");
+        let name = path.to_str().unwrap();
+
+        fs::write(&path, "a | 0 | 0.5\nb | 1 | 1.0\n").unwrap();
+        assert_eq!(count_positions(name), Ok(2));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "a | 0 | 0.5\nb | 1 | 1.0\n"
+        );
+
+        fs::write(&path, "a | 0 | 0.5\nb | 1 | 1.0\nc | 2").unwrap();
+        assert_eq!(count_positions(name), Ok(2));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "a | 0 | 0.5\nb | 1 | 1.0\n"
+        );
+
+        fs::write(&path, "no newline").unwrap();
+        assert_eq!(count_positions(name), Ok(0));
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn counts_across_read_buffers() {
+        let path = temp_path("large");
+        // Lines of 13 bytes never line up with the 8 KiB reader buffer
+        let mut text = "position | 0\n".repeat(5000);
+        text.push_str("position");
+        fs::write(&path, &text).unwrap();
+
+        assert_eq!(count_positions(path.to_str().unwrap()), Ok(5000));
+        assert_eq!(fs::metadata(&path).unwrap().len(), 13 * 5000);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_full_file_is_left_alone() {
+        let path = temp_path("full");
+        fs::write(&path, "a\nb\nc\n").unwrap();
+
+        assert_eq!(run_datagen(&config(&path, 2)), Ok(3));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "a\nb\nc\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn resumes_an_interrupted_file() {
+        let path = temp_path("resume");
+        let kept = "a | 0 | 0.5\nb | 0 | 0.5\n";
+        fs::write(&path, format!("{}c | 0", kept)).unwrap();
+
+        assert_eq!(run_datagen(&config(&path, 6)), Ok(6));
+        let text = fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with(kept));
+        assert!(text.ends_with('\n'));
+        assert_eq!(text.lines().count(), 6);
+        fs::remove_file(&path).unwrap();
+    }
+}
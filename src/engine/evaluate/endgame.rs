@@ -1,3 +1,6 @@
+use crate::bitboard::bitboard::Bitboard;
+use crate::chess::moves_gen::lookup;
+use crate::chess::square::{Rank, Square};
 use crate::chess::table::{Board, Color, Type};
 
 pub fn evaluate_endgame_aggression(b: &Board, color: Color, phase: f32) -> i32 {
@@ -24,11 +27,8 @@ pub fn evaluate_endgame_aggression(b: &Board, color: Color, phase: f32) -> i32 {
 }
 
 pub fn king_mobility(b: &Board, color: Color) -> i32 {
-    let king_sq = b.get_pieces(color, Type::King).lsb() as u8;
-    let king_bb = 1u64 << king_sq;
-
-    let king_moves = crate::chess::moves_gen::king::moves(king_bb);
-    let own_pieces = b.get_pieces(color, Type::Any).get_value();
+    let king_moves = lookup::king(b.king_square(color));
+    let own_pieces = b.get_pieces(color, Type::Any);
 
     let free_squares = (king_moves & !own_pieces).count_ones() as i32;
 
@@ -36,9 +36,7 @@ pub fn king_mobility(b: &Board, color: Color) -> i32 {
 }
 
 pub fn king_proximity_to_pawns(b: &Board, color: Color) -> i32 {
-    let king_sq = b.get_pieces(color, Type::King).lsb() as i32;
-    let king_file = king_sq % 8;
-    let king_rank = king_sq / 8;
+    let king_sq = b.king_square(color);
 
     let own_pawns = b.get_pieces(color, Type::Pawn);
 
@@ -57,12 +55,8 @@ pub fn king_proximity_to_pawns(b: &Board, color: Color) -> i32 {
     }
 
     let mut min_dist = 100;
-    for pawn in target_pawns.iter_bits() {
-        let p_sq = pawn.lsb() as i32;
-        let p_file = p_sq % 8;
-        let p_rank = p_sq / 8;
-
-        let dist = (king_file - p_file).abs() + (king_rank - p_rank).abs(); // Distanza Manhattan
+    for pawn in target_pawns {
+        let dist = king_sq.manhattan_distance(pawn) as i32;
         if dist < min_dist {
             min_dist = dist;
         }
@@ -77,15 +71,9 @@ pub fn king_opposition(b: &Board, color: Color) -> i32 {
         Color::Black => Color::White,
     };
 
-    let own_king_sq = b.get_pieces(color, Type::King).lsb() as i32;
-    let enemy_king_sq = b.get_pieces(enemy_color, Type::King).lsb() as i32;
-
-    let own_file = own_king_sq % 8;
-    let own_rank = own_king_sq / 8;
-    let enemy_file = enemy_king_sq % 8;
-    let enemy_rank = enemy_king_sq / 8;
-
-    let distance = (own_file - enemy_file).abs() + (own_rank - enemy_rank).abs();
+    let distance = b
+        .king_square(color)
+        .manhattan_distance(b.king_square(enemy_color)) as i32;
 
     if distance <= 7 {
         10 * (14 - distance)
@@ -100,9 +88,9 @@ pub fn push_enemy_king_to_edge(b: &Board, color: Color) -> i32 {
         Color::Black => Color::White,
     };
 
-    let enemy_king_sq = b.get_pieces(enemy_color, Type::King).lsb() as i32;
-    let file = enemy_king_sq % 8;
-    let rank = enemy_king_sq / 8;
+    let enemy_king_sq = b.king_square(enemy_color);
+    let file = enemy_king_sq.file().index() as i32;
+    let rank = enemy_king_sq.rank().index() as i32;
 
     let center_file = if file < 4 { 3 } else { 4 };
     let center_rank = if rank < 4 { 3 } else { 4 };
@@ -118,14 +106,8 @@ pub fn advanced_pawns_bonus(b: &Board, color: Color) -> i32 {
     let pawns = b.get_pieces(color, Type::Pawn);
     let mut score = 0;
 
-    for pawn in pawns.iter_bits() {
-        let sq = pawn.lsb() as i32;
-        let rank = sq / 8;
-
-        let advancement = match color {
-            Color::White => rank,
-            Color::Black => 7 - rank,
-        };
+    for sq in pawns {
+        let advancement = sq.rank().relative_to(color).index() as i32;
 
         let mut bonus = advancement * advancement;
 
@@ -133,10 +115,10 @@ pub fn advanced_pawns_bonus(b: &Board, color: Color) -> i32 {
             bonus += 50;
         }
 
-        if is_passed_pawn(b, color, sq as u8) {
+        if is_passed_pawn(b, color, sq) {
             bonus *= 2;
 
-            if is_supported_by_rook(b, color, sq as u8) {
+            if is_supported_by_rook(b, color, sq) {
                 bonus += 20;
             }
         }
@@ -147,60 +129,35 @@ pub fn advanced_pawns_bonus(b: &Board, color: Color) -> i32 {
     score
 }
 
-fn is_supported_by_rook(b: &Board, color: Color, pawn_sq: u8) -> bool {
-    let file = pawn_sq % 8;
+fn is_supported_by_rook(b: &Board, color: Color, pawn_sq: Square) -> bool {
     let rooks = b.get_pieces(color, Type::Rook);
-    let file_mask = 0x0101010101010101u64 << file;
 
-    (rooks.get_value() & file_mask) != 0
+    !(rooks & Bitboard::file(pawn_sq.file())).is_empty()
 }
 
-fn is_passed_pawn(b: &Board, color: Color, square: u8) -> bool {
+fn is_passed_pawn(b: &Board, color: Color, square: Square) -> bool {
     let enemy_color = match color {
         Color::White => Color::Black,
         Color::Black => Color::White,
     };
 
-    let enemy_pawns = b.get_pieces(enemy_color, Type::Pawn).get_value();
-    let file = square % 8;
-    let rank = square / 8;
-
-    let files_to_check = if file == 0 {
-        vec![0, 1]
-    } else if file == 7 {
-        vec![6, 7]
-    } else {
-        vec![file - 1, file, file + 1]
-    };
-
-    match color {
-        Color::White => {
-            for check_rank in (rank + 1)..8 {
-                for &check_file in &files_to_check {
-                    let check_sq = check_rank * 8 + check_file;
-                    if (enemy_pawns & (1u64 << check_sq)) != 0 {
-                        return false;
-                    }
-                }
-            }
-        }
-        Color::Black => {
-            for check_rank in (0..rank).rev() {
-                for &check_file in &files_to_check {
-                    let check_sq = check_rank * 8 + check_file;
-                    if (enemy_pawns & (1u64 << check_sq)) != 0 {
-                        return false;
-                    }
-                }
-            }
-        }
-    }
-
-    true
+    let enemy_pawns = b.get_pieces(enemy_color, Type::Pawn);
+    let ranks_ahead = Rank::ALL
+        .into_iter()
+        .filter(|&rank| match color {
+            Color::White => rank > square.rank(),
+            Color::Black => rank < square.rank(),
+        })
+        .fold(Bitboard::empty(), |ranks, rank| {
+            ranks | Bitboard::rank(rank)
+        });
+    let files = Bitboard::file(square.file()) | Bitboard::adjacent_files(square.file());
+
+    (enemy_pawns & ranks_ahead & files).is_empty()
 }
 
 pub fn control_key_squares(b: &Board, color: Color) -> i32 {
-    let key_squares = [27, 28, 35, 36];
+    let key_squares = [Square::D4, Square::E4, Square::D5, Square::E5];
     let mut score = 0;
 
     for &sq in &key_squares {
@@ -212,66 +169,7 @@ pub fn control_key_squares(b: &Board, color: Color) -> i32 {
     score
 }
 
-fn is_square_controlled(b: &Board, color: Color, square: u8) -> bool {
-    let sq_bb = 1u64 << square;
-    let occupied = b.get_occupied_pos().get_value();
-
-    let pawns = b.get_pieces(color, Type::Pawn).get_value();
-    match color {
-        Color::White => {
-            if square >= 8 {
-                let mut attacks = 0u64;
-                if square % 8 != 0 {
-                    attacks |= 1u64 << (square - 9);
-                }
-                if square % 8 != 7 {
-                    attacks |= 1u64 << (square - 7);
-                }
-                if attacks & pawns != 0 {
-                    return true;
-                }
-            }
-        }
-        Color::Black => {
-            if square < 56 {
-                let mut attacks = 0u64;
-                if square % 8 != 0 {
-                    attacks |= 1u64 << (square + 7);
-                }
-                if square % 8 != 7 {
-                    attacks |= 1u64 << (square + 9);
-                }
-                if attacks & pawns != 0 {
-                    return true;
-                }
-            }
-        }
-    }
-
-    let knights = b.get_pieces(color, Type::Knight).get_value();
-    let knight_attacks = crate::chess::moves_gen::knight::moves(sq_bb);
-    if knight_attacks & knights != 0 {
-        return true;
-    }
-
-    let bishops = b.get_pieces(color, Type::Bishop).get_value();
-    let queens = b.get_pieces(color, Type::Queen).get_value();
-    let bishop_attacks = crate::chess::moves_gen::bishop::moves(sq_bb, occupied);
-    if bishop_attacks & (bishops | queens) != 0 {
-        return true;
-    }
-
-    let rooks = b.get_pieces(color, Type::Rook).get_value();
-    let rook_attacks = crate::chess::moves_gen::rook::moves(sq_bb, occupied);
-    if rook_attacks & (rooks | queens) != 0 {
-        return true;
-    }
-
-    let king = b.get_pieces(color, Type::King).get_value();
-    let king_attacks = crate::chess::moves_gen::king::moves(sq_bb);
-    if king_attacks & king != 0 {
-        return true;
-    }
-
-    false
+fn is_square_controlled(b: &Board, color: Color, square: Square) -> bool {
+    let occupied = b.get_occupied_pos();
+    !(b.attackers_to(square, occupied) & b.get_pieces(color, Type::Any)).is_empty()
 }
@@ -1,10 +1,9 @@
-use strum::IntoEnumIterator;
-
 use crate::chess::table::Board;
 use crate::chess::table::{Color, Type};
 use crate::engine::evaluate::bishop_pair::evaluate_bishop_pair;
 use crate::engine::evaluate::endgame::evaluate_endgame_aggression;
 use crate::engine::evaluate::king_safety::evaluate_king_safety;
+use crate::engine::evaluate::kpk::{self, KpkResult};
 use crate::engine::evaluate::pawn_evaluation::evaluate_pawn;
 use crate::engine::evaluate::pst::get_pst_value;
 
@@ -13,25 +12,23 @@ const QUEEN_PHASE_WEIGHT: i32 = 4;
 const BISHOP_PHASE_WEIGTH: i32 = 1;
 const ROOK_PHASE_WEIGHT: i32 = 2;
 
+const KPK_WIN_BONUS: i32 = 300;
+
 pub fn evaluate(b: &Board, phase: f32) -> i32 {
+    let kpk_result = kpk::probe(b);
+    if kpk_result == Some(KpkResult::Draw) {
+        return 0;
+    }
+
     let mut score = 0;
 
-    for piece_type in Type::iter() {
-        if piece_type == Type::Any {
+    for square in b.get_occupied_pos() {
+        let Some(piece) = b.piece_at(square) else {
             continue;
-        }
-
-        let white_pieces = b.get_pieces(Color::White, piece_type);
-        for piece in white_pieces.iter_bits() {
-            let sq = piece.lsb() as usize;
-            score += piece_type.value() + get_pst_value(piece_type, sq, Color::White, phase);
-        }
-
-        let black_pieces = b.get_pieces(Color::Black, piece_type);
-        for piece in black_pieces.iter_bits() {
-            let sq = piece.lsb() as usize;
-            score -= piece_type.value() + get_pst_value(piece_type, sq, Color::Black, phase);
-        }
+        };
+        let value = piece.piece_type.value()
+            + get_pst_value(piece.piece_type, square.into(), piece.color, phase);
+        score += piece.color.match_color(value, -value);
     }
 
     score += evaluate_pawn(&b.get_pieces(Color::White, Type::Pawn));
@@ -53,7 +50,11 @@ pub fn evaluate(b: &Board, phase: f32) -> i32 {
         score -= evaluate_endgame_aggression(b, Color::Black, phase);
     }
 
-    if b.is_white_turn { score } else { -score }
+    if let Some(KpkResult::Win(strong)) = kpk_result {
+        score += strong.match_color(KPK_WIN_BONUS, -KPK_WIN_BONUS);
+    }
+
+    b.get_side().match_color(score, -score)
 }
 
 pub fn calculate_game_phase(b: &Board) -> f32 {
@@ -72,6 +73,7 @@ pub fn calculate_game_phase(b: &Board) -> f32 {
         / 24.0
 }
 
+#[allow(dead_code)]
 pub fn evaluate_development(b: &Board, color: Color) -> i32 {
     let mut score = 0;
     let back_rank = match color {
@@ -0,0 +1,315 @@
+use lazy_static::lazy_static;
+
+use crate::bitboard::bitboard::Bitboard;
+use crate::chess::moves_gen::{king, pawn};
+use crate::chess::square::{File, Rank, Square};
+use crate::chess::table::{Board, Color, Type};
+
+// Index layout (18 bits), strong side always White with the pawn on files a-d:
+// Bits 0-5:   white king square
+// Bits 6-11:  black king square
+// Bit 12:     side to move (0 = White)
+// Bits 13-14: pawn file (a-d)
+// Bits 15-17: 6 - pawn rank (pawn on ranks 2-7)
+const MAX_INDEX: usize = 2 * 24 * 64 * 64;
+
+const INVALID: u8 = 0;
+const UNKNOWN: u8 = 1;
+const DRAW: u8 = 2;
+const WIN: u8 = 4;
+
+lazy_static! {
+    static ref KPK_BITBASE: Vec<u32> = generate();
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KpkResult {
+    Draw,
+    Win(Color),
+}
+
+/// Exact result of a king and pawn versus king ending, `None` for any other material.
+pub fn probe(b: &Board) -> Option<KpkResult> {
+    let white_pawns = b.get_pieces(Color::White, Type::Pawn);
+    let pawns = white_pawns | b.get_pieces(Color::Black, Type::Pawn);
+    if pawns.count_ones() != 1 || b.get_occupied_pos().count_ones() != 3 {
+        return None;
+    }
+
+    let strong = if !white_pawns.is_empty() {
+        Color::White
+    } else {
+        Color::Black
+    };
+    let weak = strong.opposite();
+
+    let mut strong_king = b.king_square(strong);
+    let mut weak_king = b.king_square(weak);
+    let mut pawn_sq = pawns.first_square()?;
+
+    if strong == Color::Black {
+        strong_king = strong_king.flip_rank();
+        weak_king = weak_king.flip_rank();
+        pawn_sq = pawn_sq.flip_rank();
+    }
+
+    if pawn_sq.file() >= File::E {
+        strong_king = strong_king.flip_file();
+        weak_king = weak_king.flip_file();
+        pawn_sq = pawn_sq.flip_file();
+    }
+
+    let strong_to_move = b.get_side() == strong;
+    let idx = index(strong_to_move, weak_king, strong_king, pawn_sq);
+
+    if KPK_BITBASE[idx / 32] & (1 << (idx % 32)) != 0 {
+        Some(KpkResult::Win(strong))
+    } else {
+        Some(KpkResult::Draw)
+    }
+}
+
+#[inline(always)]
+fn index(white_to_move: bool, black_king: Square, white_king: Square, pawn_sq: Square) -> usize {
+    let stm = if white_to_move { 0 } else { 1 };
+    let file = pawn_sq.file().index() as usize;
+    let rank = pawn_sq.rank().index() as usize;
+
+    white_king.index() as usize
+        | (black_king.index() as usize) << 6
+        | stm << 12
+        | file << 13
+        | (6 - rank) << 15
+}
+
+struct KpkPosition {
+    white_to_move: bool,
+    white_king: Square,
+    black_king: Square,
+    pawn_sq: Square,
+    result: u8,
+}
+
+impl KpkPosition {
+    fn new(idx: usize) -> Self {
+        let white_king = Square::from_index((idx & 0x3F) as u8);
+        let black_king = Square::from_index(((idx >> 6) & 0x3F) as u8);
+        let white_to_move = (idx >> 12) & 1 == 0;
+        let file = File::ALL[(idx >> 13) & 0x3];
+        let rank = Rank::ALL[6 - ((idx >> 15) & 0x7)];
+        let pawn_sq = Square::new(file, rank);
+
+        let pawn_attacks = pawn::white_attack(pawn_sq.into(), !Bitboard::empty());
+        let black_king_attacks = king::moves(black_king.into());
+        let white_king_attacks = king::moves(white_king.into());
+        let promotion_sq = Square::new(file, rank.offset(1).unwrap());
+
+        let result = if white_king.distance(black_king) <= 1
+            || white_king == pawn_sq
+            || black_king == pawn_sq
+            || (white_to_move && pawn_attacks.contains(black_king))
+        {
+            INVALID
+        } else if white_to_move
+            && rank == Rank::SEVENTH
+            && white_king != promotion_sq
+            && (black_king.distance(promotion_sq) > 1 || white_king.distance(promotion_sq) == 1)
+        {
+            WIN
+        } else if !white_to_move
+            && ((black_king_attacks & !(white_king_attacks | pawn_attacks)).is_empty()
+                || (black_king_attacks & !white_king_attacks).contains(pawn_sq))
+        {
+            DRAW
+        } else {
+            UNKNOWN
+        };
+
+        KpkPosition {
+            white_to_move,
+            white_king,
+            black_king,
+            pawn_sq,
+            result,
+        }
+    }
+
+    fn classify(&self, db: &[KpkPosition]) -> u8 {
+        let (good, bad) = if self.white_to_move {
+            (WIN, DRAW)
+        } else {
+            (DRAW, WIN)
+        };
+
+        let mut r = INVALID;
+
+        if self.white_to_move {
+            for to in king::moves(self.white_king.into()) {
+                r |= db[index(false, self.black_king, to, self.pawn_sq)].result;
+            }
+
+            let rank = self.pawn_sq.rank();
+            // The pawn is never on the last rank, so one step ahead is on the board
+            let push = self.pawn_sq.offset(0, 1).unwrap();
+            if rank < Rank::SEVENTH {
+                r |= db[index(false, self.black_king, self.white_king, push)].result;
+            }
+            if rank == Rank::SECOND && push != self.white_king && push != self.black_king {
+                let double_push = push.offset(0, 1).unwrap();
+                r |= db[index(false, self.black_king, self.white_king, double_push)].result;
+            }
+        } else {
+            for to in king::moves(self.black_king.into()) {
+                r |= db[index(true, to, self.white_king, self.pawn_sq)].result;
+            }
+        }
+
+        if r & good != 0 {
+            good
+        } else if r & UNKNOWN != 0 {
+            UNKNOWN
+        } else {
+            bad
+        }
+    }
+}
+
+/// Retrograde analysis over every KPK position: positions are seeded with the
+/// immediately known results and then repeatedly classified from their successors
+/// until nothing changes. Every position still unknown at that point is a draw.
+fn generate() -> Vec<u32> {
+    let mut db: Vec<KpkPosition> = (0..MAX_INDEX).map(KpkPosition::new).collect();
+
+    let mut repeat = true;
+    while repeat {
+        repeat = false;
+        for idx in 0..MAX_INDEX {
+            if db[idx].result == UNKNOWN {
+                let result = db[idx].classify(&db);
+                if result != UNKNOWN {
+                    db[idx].result = result;
+                    repeat = true;
+                }
+            }
+        }
+    }
+
+    let mut bitbase = vec![0u32; MAX_INDEX / 32];
+    for (idx, pos) in db.iter().enumerate() {
+        if pos.result == WIN {
+            bitbase[idx / 32] |= 1 << (idx % 32);
+        }
+    }
+    bitbase
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Swaps the colours of a FEN: ranks are reversed, piece cases swapped and
+    /// the other side is to move.
+    fn colour_flip(fen: &str) -> String {
+        let mut fields = fen.split(' ');
+        let ranks: Vec<String> = fields
+            .next()
+            .unwrap()
+            .split('/')
+            .rev()
+            .map(|rank| {
+                rank.chars()
+                    .map(|c| {
+                        if c.is_ascii_uppercase() {
+                            c.to_ascii_lowercase()
+                        } else {
+                            c.to_ascii_uppercase()
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let side = match fields.next() {
+            Some("w") => "b",
+            _ => "w",
+        };
+        format!("{} {side} - - 0 1", ranks.join("/"))
+    }
+
+    /// Mirrors a FEN across the d/e file boundary.
+    fn file_flip(fen: &str) -> String {
+        let (board, rest) = fen.split_once(' ').unwrap();
+        let ranks: Vec<String> = board
+            .split('/')
+            .map(|r| r.chars().rev().collect())
+            .collect();
+        format!("{} {rest}", ranks.join("/"))
+    }
+
+    fn probe_fen(fen: &str) -> Option<KpkResult> {
+        probe(&Board::new_from_fen(fen).unwrap())
+    }
+
+    /// Checks `fen` with White as the strong side, then its colour and file
+    /// mirrors.
+    fn check(fen: &str, white_wins: bool) {
+        let win = |c| {
+            if white_wins {
+                KpkResult::Win(c)
+            } else {
+                KpkResult::Draw
+            }
+        };
+        assert_eq!(probe_fen(fen), Some(win(Color::White)), "{fen}");
+        assert_eq!(
+            probe_fen(&file_flip(fen)),
+            Some(win(Color::White)),
+            "{fen} mirrored"
+        );
+        let flipped = colour_flip(fen);
+        assert_eq!(probe_fen(&flipped), Some(win(Color::Black)), "{flipped}");
+        let both = file_flip(&flipped);
+        assert_eq!(probe_fen(&both), Some(win(Color::Black)), "{both}");
+    }
+
+    #[test]
+    fn rook_pawn_draws() {
+        check("k7/8/8/8/8/8/P7/K7 w - - 0 1", false);
+        check("k7/8/8/8/8/8/P7/7K w - - 0 1", false);
+        check("8/k7/8/K7/P7/8/8/8 w - - 0 1", false);
+        check("2k5/8/8/8/8/8/P7/K7 b - - 0 1", false);
+    }
+
+    #[test]
+    fn rook_pawn_outside_the_square_wins() {
+        check("7k/8/8/8/8/8/P7/K7 w - - 0 1", true);
+    }
+
+    #[test]
+    fn king_on_a_key_square_wins() {
+        for side in ["w", "b"] {
+            check(&format!("4k3/8/4K3/8/4P3/8/8/8 {side} - - 0 1"), true);
+            check(&format!("8/3k4/8/2K5/8/8/3P4/8 {side} - - 0 1"), true);
+            check(&format!("8/8/1k6/8/3K4/8/3P4/8 {side} - - 0 1"), true);
+        }
+    }
+
+    #[test]
+    fn opposition_decides() {
+        check("8/8/8/4k3/8/4K3/4P3/8 w - - 0 1", false);
+        check("8/8/8/4k3/8/4K3/4P3/8 b - - 0 1", true);
+        check("8/8/8/2k5/8/2K5/2P5/8 w - - 0 1", false);
+        check("8/8/8/2k5/8/2K5/2P5/8 b - - 0 1", true);
+    }
+
+    #[test]
+    fn blocked_pawn_on_the_seventh() {
+        check("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1", false);
+        check("4k3/4P3/4K3/8/8/8/8/8 w - - 0 1", true);
+    }
+
+    #[test]
+    fn other_material_is_not_probed() {
+        assert_eq!(probe_fen("4k3/8/8/8/8/8/4PP2/4K3 w - - 0 1"), None);
+        assert_eq!(probe_fen("4k3/8/8/8/8/8/4P3/3QK3 w - - 0 1"), None);
+    }
+}
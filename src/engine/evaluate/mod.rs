@@ -2,5 +2,6 @@ mod bishop_pair;
 mod endgame;
 pub mod evaluate;
 mod king_safety;
+pub mod kpk;
 mod pawn_evaluation;
 mod pst;
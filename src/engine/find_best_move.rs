@@ -2,22 +2,101 @@ use std::time::{Duration, Instant};
 
 use crate::{
     chess::{moves_gen::moves_struct::Moves, table::Board},
-    engine::{evaluate::evaluate::calculate_game_phase, search::negamax, trasposition_table::TT},
+    engine::{
+        evaluate::evaluate::calculate_game_phase,
+        search::{SearchContext, negamax},
+        syzygy::probe,
+        trasposition_table::TT,
+    },
 };
 
 const MAX_MOVES: usize = 255;
+/// Deepest iteration the search runs.
+pub const MAX_DEPTH: u8 = 64;
 const INFINITY: i32 = 30000;
-const MAX_PLY: usize = 64;
 
+pub const DEFAULT_HASH_MB: usize = 256;
+
+/// A searcher that keeps its transposition table between searches.
 pub struct Engine {
-    pub tt: TT,
+    pub(crate) tt: TT,
+    /// Nodes visited by the last `find_best_move`, quiescence nodes included.
+    pub nodes: u64,
+    /// Score of the last completed iteration, from the side to move's point of view.
+    pub score: i32,
+    /// Depth of the last completed iteration.
+    pub depth: u8,
+    /// Stops the search once this many nodes have been visited. The limit is
+    /// checked at every node of the main search, so only the quiescence
+    /// search of the last node can run past it.
+    pub node_limit: Option<u64>,
+}
+
+/// Limits for `Engine::search`. A limit left at `None` does not restrict the
+/// search, and the depth defaults to `MAX_DEPTH`.
+#[derive(Clone, Copy, Default)]
+pub struct SearchLimits {
+    /// Deepest iteration to run.
+    pub depth: Option<u8>,
+    /// Time budget in milliseconds.
+    pub time_ms: Option<u64>,
+    /// Node budget, see `Engine::node_limit`.
+    pub nodes: Option<u64>,
+}
+
+/// Outcome of `Engine::search`.
+pub struct SearchResult {
+    /// The null move when the position has no legal moves.
+    pub best_move: Moves,
+    /// Centipawns from the side to move's point of view. A mate in `n` plies
+    /// scores `20000 - n` for the winner.
+    pub score: i32,
+    /// Depth of the last completed iteration.
+    pub depth: u8,
+    /// Nodes visited, quiescence nodes included.
+    pub nodes: u64,
+    /// Wall-clock time of the search.
+    pub time_ms: u64,
+}
+
+/// Time to spend on one move given the remaining clock and the increment, in
+/// milliseconds.
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+pub fn allocate_time(time_left: u64, increment: u64) -> u64 {
+    let time_slot = (time_left as f64 / 20.0) + (increment as f64 / 2.0);
+
+    let max_alloc = time_left.saturating_sub(100).max(50);
+
+    (time_slot as u64).clamp(50, max_alloc)
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Engine {
+    /// An engine with a transposition table of `DEFAULT_HASH_MB` megabytes.
     pub fn new() -> Self {
-        Engine { tt: TT::new(256) }
+        Engine::with_hash(DEFAULT_HASH_MB)
+    }
+
+    /// An engine with a transposition table of `size_mb` megabytes.
+    pub fn with_hash(size_mb: usize) -> Self {
+        Engine {
+            tt: TT::new(size_mb),
+            nodes: 0,
+            score: 0,
+            depth: 0,
+            node_limit: None,
+        }
     }
 
+    /// Searches `b` by iterative deepening up to `max_depth` and returns the
+    /// best move, or the null move when there is none. `game_history` holds
+    /// the hashes of the positions before `b`, and `time_limit` is in
+    /// milliseconds. The score and depth are left in `score` and `depth`.
     pub fn find_best_move(
         &mut self,
         b: &Board,
@@ -27,24 +106,21 @@ impl Engine {
     ) -> Moves {
         let mut board_mut = *b;
         let start_time = Instant::now();
-        let mut killer_moves = [[Moves::new(0, 0, 0, 0, false); 2]; MAX_PLY];
-        let mut history = [[0i32; 64]; 64];
+        self.nodes = 0;
+        self.score = 0;
+        self.depth = 0;
 
         let limit_duration = time_limit.map(|ms| Duration::from_millis(ms));
 
-        let mut move_buffers: Vec<Vec<Moves>> = (0..=max_depth + 1)
-            .map(|_| Vec::with_capacity(MAX_MOVES))
-            .collect();
-
-        let (root_move_buffer, _) = move_buffers.split_at_mut(1);
-        let root_move_vec = &mut root_move_buffer[0];
+        let mut root_moves = Vec::with_capacity(MAX_MOVES);
         let turn = board_mut.get_side();
 
-        board_mut.get_legal_moves(turn, root_move_vec);
-        let moves = root_move_vec;
+        board_mut.get_legal_moves(turn, &mut root_moves);
+        probe::filter_root_moves(&mut board_mut, &mut root_moves);
+        let moves = &mut root_moves;
 
         if moves.is_empty() {
-            return Moves::new(0, 0, 0, 0, false);
+            return Moves::NULL;
         }
 
         let mut global_best_move = moves[0];
@@ -52,6 +128,8 @@ impl Engine {
 
         let mut search_history = game_history.to_vec();
         search_history.push(board_mut.get_hash());
+        let mut ctx = SearchContext::new(&mut self.tt, search_history);
+        ctx.node_limit = self.node_limit;
 
         for current_depth in 1..=max_depth {
             // Root Move Ordering
@@ -79,10 +157,9 @@ impl Engine {
 
                 let mut depth_completed = true;
 
-                let mut iter_buffers: Vec<Vec<Moves>> = (0..=current_depth + 1)
-                    .map(|_| Vec::with_capacity(MAX_MOVES))
-                    .collect();
-                let (_, next_buffers) = iter_buffers.split_at_mut(1);
+                // The root moves are kept apart, so the list for ply 0 stays unused
+                ctx.move_buffers
+                    .resize_with(current_depth as usize + 2, || Vec::with_capacity(MAX_MOVES));
 
                 for (i, mv) in moves.iter().enumerate() {
                     if let Some(limit) = limit_duration {
@@ -93,20 +170,17 @@ impl Engine {
                     }
 
                     let undo_info = board_mut.make_move_with_undo(mv);
-                    search_history.push(board_mut.get_hash());
+                    ctx.position_history.push(board_mut.get_hash());
 
                     let mut score;
                     if i == 0 {
                         score = -negamax(
                             &mut board_mut,
+                            &mut ctx,
                             current_depth - 1,
                             -beta,
                             -alpha,
-                            &mut self.tt,
-                            next_buffers,
-                            &mut search_history,
-                            &mut killer_moves,
-                            &mut history,
+                            *mv,
                             1,
                         );
                     } else {
@@ -125,28 +199,22 @@ impl Engine {
 
                         score = -negamax(
                             &mut board_mut,
+                            &mut ctx,
                             current_depth - 1 - reduction,
                             -alpha - 1,
                             -alpha,
-                            &mut self.tt,
-                            next_buffers,
-                            &mut search_history,
-                            &mut killer_moves,
-                            &mut history,
+                            *mv,
                             1,
                         );
 
                         if score > alpha && reduction > 0 {
                             score = -negamax(
                                 &mut board_mut,
+                                &mut ctx,
                                 current_depth - 1,
                                 -alpha - 1,
                                 -alpha,
-                                &mut self.tt,
-                                next_buffers,
-                                &mut search_history,
-                                &mut killer_moves,
-                                &mut history,
+                                *mv,
                                 1,
                             );
                         }
@@ -154,22 +222,24 @@ impl Engine {
                         if score > alpha && score < beta {
                             score = -negamax(
                                 &mut board_mut,
+                                &mut ctx,
                                 current_depth - 1,
                                 -beta,
                                 -alpha,
-                                &mut self.tt,
-                                next_buffers,
-                                &mut search_history,
-                                &mut killer_moves,
-                                &mut history,
+                                *mv,
                                 1,
                             );
                         }
                     }
 
-                    search_history.pop();
+                    ctx.position_history.pop();
                     board_mut.unmake_move(mv, undo_info);
 
+                    if ctx.stopped {
+                        depth_completed = false;
+                        break;
+                    }
+
                     if score > best_score_this_iteration {
                         best_score_this_iteration = score;
                         best_move_this_iteration = *mv;
@@ -203,6 +273,8 @@ impl Engine {
 
                 global_best_move = best_move_this_iteration;
                 previous_score = best_score_this_iteration;
+                self.score = previous_score;
+                self.depth = current_depth;
                 break;
             }
 
@@ -211,12 +283,78 @@ impl Engine {
                     break;
                 }
             }
+            if self.node_limit.is_some_and(|limit| ctx.nodes >= limit) {
+                break;
+            }
         }
+        self.nodes = ctx.nodes;
 
         global_best_move
     }
 
+    /// Searches `b` within `limits`. `game_history` holds the hashes of the
+    /// positions played before, for repetition detection.
+    pub fn search(
+        &mut self,
+        b: &Board,
+        game_history: &[u64],
+        limits: &SearchLimits,
+    ) -> SearchResult {
+        let start_time = Instant::now();
+        let node_limit = self.node_limit;
+        self.node_limit = limits.nodes;
+
+        let best_move = self.find_best_move(
+            b,
+            limits.depth.unwrap_or(MAX_DEPTH),
+            game_history,
+            limits.time_ms,
+        );
+        self.node_limit = node_limit;
+
+        SearchResult {
+            best_move,
+            score: self.score,
+            depth: self.depth,
+            nodes: self.nodes,
+            time_ms: start_time.elapsed().as_millis() as u64,
+        }
+    }
+
+    /// Empties the transposition table, for a new game.
     pub fn clear(&mut self) {
         self.tt.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::table::START_FEN;
+
+    #[test]
+    fn node_limit_holds_inside_the_tree() {
+        for fen in [
+            START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r1bq1rk1/pp2bppp/2n1pn2/2pp4/3P4/2PBPN2/PP1N1PPP/R2QK2R w KQ - 0 8",
+        ] {
+            let board = Board::new_from_fen(fen).unwrap();
+            for limit in [500, 5000, 20000] {
+                let mut engine = Engine::with_hash(1);
+                let limits = SearchLimits {
+                    nodes: Some(limit),
+                    ..SearchLimits::default()
+                };
+                let result = engine.search(&board, &[], &limits);
+                assert!(result.nodes >= limit, "{fen}: {} < {limit}", result.nodes);
+                assert!(
+                    result.nodes < limit + 1000,
+                    "{fen}: {} for {limit}",
+                    result.nodes
+                );
+                assert!(board.legal_moves().contains(&result.best_move), "{fen}");
+            }
+        }
+    }
+}
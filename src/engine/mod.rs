@@ -1,6 +1,13 @@
+#[cfg(feature = "cli")]
+pub mod bench;
 pub mod evaluate;
 pub mod find_best_move;
+#[cfg(feature = "cli")]
 pub mod perft;
+mod move_picker;
 mod quiescence;
-mod search;
+pub mod search;
+pub mod syzygy;
+#[cfg(feature = "cli")]
+pub mod test_suite;
 mod trasposition_table;
@@ -0,0 +1,172 @@
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::chess::table::{Board, GenType};
+
+const MAX_MOVES: usize = 256;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Stage {
+    TtMove,
+    GenerateCaptures,
+    GoodCaptures,
+    FirstKiller,
+    SecondKiller,
+    CounterMove,
+    GenerateQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+/// Hands out the legal moves of a position one at a time, best guess first,
+/// and only generates a group of moves once the earlier ones are used up.
+/// A node that fails high on the hash move or a capture never generates its
+/// quiet moves.
+///
+/// The order is: hash move, captures and promotions that do not lose
+/// material (MVV-LVA), the two killers, the counter move, quiet moves by
+/// history score and finally the captures that lose material.
+pub struct MovePicker<'a> {
+    stage: Stage,
+    moves: &'a mut Vec<Moves>,
+    scores: [i32; MAX_MOVES],
+    current: usize,
+    /// Losing captures are moved to the front of `moves`, up to here.
+    bad_end: usize,
+    tt_move: Option<Moves>,
+    killers: [Moves; 2],
+    counter_move: Moves,
+}
+
+impl<'a> MovePicker<'a> {
+    pub fn new(
+        moves: &'a mut Vec<Moves>,
+        tt_move: Option<Moves>,
+        killers: [Moves; 2],
+        counter_move: Moves,
+    ) -> Self {
+        moves.clear();
+        MovePicker {
+            stage: Stage::TtMove,
+            moves,
+            scores: [0; MAX_MOVES],
+            current: 0,
+            bad_end: 0,
+            tt_move,
+            killers,
+            counter_move,
+        }
+    }
+
+    pub fn next(&mut self, b: &Board, history: &[[i32; 64]; 64]) -> Option<Moves> {
+        loop {
+            match self.stage {
+                Stage::TtMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if let Some(mv) = self.tt_move
+                        && is_playable(b, &mv)
+                    {
+                        return Some(mv);
+                    }
+                    self.tt_move = None;
+                }
+                Stage::GenerateCaptures => {
+                    b.generate_legal(b.get_side(), GenType::Captures, self.moves);
+                    for (i, mv) in self.moves.iter().enumerate() {
+                        self.scores[i] = mv.score(b);
+                    }
+                    self.current = 0;
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => {
+                    let Some(mv) = self.pick_best() else {
+                        self.stage = Stage::FirstKiller;
+                        continue;
+                    };
+                    if Some(mv) == self.tt_move {
+                        continue;
+                    }
+                    if !mv.is_promotion() && b.see(&mv) < 0 {
+                        self.moves[self.bad_end] = mv;
+                        self.bad_end += 1;
+                        continue;
+                    }
+                    return Some(mv);
+                }
+                Stage::FirstKiller | Stage::SecondKiller | Stage::CounterMove => {
+                    let (mv, earlier, next) = match self.stage {
+                        Stage::FirstKiller => (self.killers[0], 0, Stage::SecondKiller),
+                        Stage::SecondKiller => (self.killers[1], 1, Stage::CounterMove),
+                        _ => (self.counter_move, 2, Stage::GenerateQuiets),
+                    };
+                    self.stage = next;
+                    if self.is_new_quiet(&mv, &self.killers[..earlier]) && is_playable(b, &mv) {
+                        return Some(mv);
+                    }
+                }
+                Stage::GenerateQuiets => {
+                    let start = self.moves.len();
+                    b.generate_legal(b.get_side(), GenType::Quiets, self.moves);
+                    for i in start..self.moves.len() {
+                        let mv = self.moves[i];
+                        let (from, to) = (mv.from().index() as usize, mv.to().index() as usize);
+                        self.scores[i] = mv.score(b) + history[from][to].min(700_000);
+                    }
+                    self.current = start;
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    let Some(mv) = self.pick_best() else {
+                        self.current = 0;
+                        self.stage = Stage::BadCaptures;
+                        continue;
+                    };
+                    if Some(mv) == self.tt_move
+                        || mv == self.killers[0]
+                        || mv == self.killers[1]
+                        || mv == self.counter_move
+                    {
+                        continue;
+                    }
+                    return Some(mv);
+                }
+                Stage::BadCaptures => {
+                    if self.current == self.bad_end {
+                        self.stage = Stage::Done;
+                        continue;
+                    }
+                    self.current += 1;
+                    return Some(self.moves[self.current - 1]);
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    /// Swaps the highest scored remaining move to `current` and returns it.
+    fn pick_best(&mut self) -> Option<Moves> {
+        if self.current >= self.moves.len() {
+            return None;
+        }
+        let mut best = self.current;
+        for i in (self.current + 1)..self.moves.len() {
+            if self.scores[i] > self.scores[best] {
+                best = i;
+            }
+        }
+        self.moves.swap(self.current, best);
+        self.scores.swap(self.current, best);
+        self.current += 1;
+        Some(self.moves[self.current - 1])
+    }
+
+    /// Killers and the counter move are only tried as quiet moves that were
+    /// not handed out before.
+    fn is_new_quiet(&self, mv: &Moves, earlier: &[Moves]) -> bool {
+        !mv.is_capture() && !mv.is_promotion() && Some(*mv) != self.tt_move && !earlier.contains(mv)
+    }
+}
+
+/// Whether a move from another position is legal here.
+fn is_playable(b: &Board, mv: &Moves) -> bool {
+    b.is_pseudo_legal(mv) && b.is_legal(mv)
+}
@@ -1,6 +1,5 @@
 use crate::chess::moves_gen::moves_struct::{FLAG_CAPTURE, FLAG_CASTLE, FLAG_EN_PASSANT, Moves};
 use crate::chess::table::Board;
-use crate::chess::table::Color;
 use std::time::Instant;
 
 struct PerftResult {
@@ -44,32 +43,17 @@ fn perft_inner(b: &mut Board, depth: u8, move_buffers: &mut [Vec<Moves>]) -> u64
         return 1;
     }
 
-    let turn = if b.is_white_turn {
-        Color::White
-    } else {
-        Color::Black
-    };
+    let turn = b.get_side();
 
     let buffer_index = depth as usize;
     let move_buffer = &mut move_buffers[buffer_index];
 
     move_buffer.clear();
-    let moves = b.get_all_moves_bitboard(turn, move_buffer);
+    let moves = b.get_legal_moves(turn, move_buffer);
     let move_count = moves.len();
 
     if depth == 1 {
-        let mut legal_count = 0;
-        for i in 0..move_count {
-            let mv = move_buffers[buffer_index][i];
-            let undo = b.make_move_with_undo(&mv);
-
-            if !b.is_king_in_check(turn) {
-                legal_count += 1;
-            }
-
-            b.unmake_move(&mv, undo);
-        }
-        return legal_count;
+        return move_count as u64;
     }
 
     let mut total_moves = 0;
@@ -77,11 +61,7 @@ fn perft_inner(b: &mut Board, depth: u8, move_buffers: &mut [Vec<Moves>]) -> u64
     for i in 0..move_count {
         let mv = move_buffers[buffer_index][i];
         let undo = b.make_move_with_undo(&mv);
-
-        if !b.is_king_in_check(turn) {
-            total_moves += perft_inner(b, depth - 1, move_buffers);
-        }
-
+        total_moves += perft_inner(b, depth - 1, move_buffers);
         b.unmake_move(&mv, undo);
     }
 
@@ -96,17 +76,13 @@ pub fn perft(b: &mut Board, depth: u8) -> u64 {
 }
 
 fn perft_divide_inner(b: &mut Board, depth: u8, move_buffers: &mut [Vec<Moves>]) -> u64 {
-    let turn = if b.is_white_turn {
-        Color::White
-    } else {
-        Color::Black
-    };
+    let turn = b.get_side();
 
     let buffer_index = depth as usize;
     let move_buffer = &mut move_buffers[buffer_index];
 
     move_buffer.clear();
-    let moves = b.get_all_moves_bitboard(turn, move_buffer);
+    let moves = b.get_legal_moves(turn, move_buffer);
     let move_count = moves.len();
 
     let mut total = 0;
@@ -114,17 +90,13 @@ fn perft_divide_inner(b: &mut Board, depth: u8, move_buffers: &mut [Vec<Moves>])
     for i in 0..move_count {
         let mv = move_buffers[buffer_index][i];
         let undo = b.make_move_with_undo(&mv);
-
-        if !b.is_king_in_check(turn) {
-            let count = if depth <= 1 {
-                1
-            } else {
-                perft_inner(b, depth - 1, move_buffers)
-            };
-            println!("{} -> {}: {}", mv.from(), mv.to(), count);
-            total += count;
-        }
-
+        let count = if depth <= 1 {
+            1
+        } else {
+            perft_inner(b, depth - 1, move_buffers)
+        };
+        println!("{} -> {}: {}", mv.from(), mv.to(), count);
+        total += count;
         b.unmake_move(&mv, undo);
     }
 
@@ -151,17 +123,13 @@ fn perft_plus_inner(b: &mut Board, depth: u8, move_buffers: &mut [Vec<Moves>]) -
         };
     }
 
-    let turn = if b.is_white_turn {
-        Color::White
-    } else {
-        Color::Black
-    };
+    let turn = b.get_side();
 
     let buffer_index = depth as usize;
     let move_buffer = &mut move_buffers[buffer_index];
 
     move_buffer.clear();
-    let moves = b.get_all_moves_bitboard(turn, move_buffer);
+    let moves = b.get_legal_moves(turn, move_buffer);
     let move_count = moves.len();
 
     let mut result = PerftResult::new();
@@ -174,34 +142,25 @@ fn perft_plus_inner(b: &mut Board, depth: u8, move_buffers: &mut [Vec<Moves>]) -
         for i in 0..move_count {
             let mv = move_buffers[buffer_index][i];
             let undo = b.make_move_with_undo(&mv);
+            result.nodes += 1;
+
+            let flags = mv.flags();
+
+            if flags == FLAG_CAPTURE {
+                result.captures += 1;
+            } else if flags == FLAG_EN_PASSANT {
+                result.en_passant += 1;
+                result.captures += 1;
+            } else if flags == FLAG_CASTLE {
+                result.castles += 1;
+            }
 
-            if !b.is_king_in_check(turn) {
-                result.nodes += 1;
-
-                let flags = mv.flags();
-
-                if flags == FLAG_CAPTURE {
-                    result.captures += 1;
-                } else if flags == FLAG_EN_PASSANT {
-                    result.en_passant += 1;
-                    result.captures += 1;
-                } else if flags == FLAG_CASTLE {
-                    result.castles += 1;
-                }
-
-                if mv.is_promotion() {
-                    result.promotion += 1;
-                }
-
-                let opponent_in_check = if b.is_white_turn {
-                    Color::White
-                } else {
-                    Color::Black
-                };
-
-                if b.is_king_in_check(opponent_in_check) {
-                    result.checks += 1;
-                }
+            if mv.is_promotion() {
+                result.promotion += 1;
+            }
+
+            if b.is_king_in_check(b.get_side()) {
+                result.checks += 1;
             }
 
             b.unmake_move(&mv, undo);
@@ -212,12 +171,8 @@ fn perft_plus_inner(b: &mut Board, depth: u8, move_buffers: &mut [Vec<Moves>]) -
     for i in 0..move_count {
         let mv = move_buffers[buffer_index][i];
         let undo = b.make_move_with_undo(&mv);
-
-        if !b.is_king_in_check(turn) {
-            let sub_result = perft_plus_inner(b, depth - 1, move_buffers);
-            result.add(&sub_result);
-        }
-
+        let sub_result = perft_plus_inner(b, depth - 1, move_buffers);
+        result.add(&sub_result);
         b.unmake_move(&mv, undo);
     }
 
@@ -1,16 +1,30 @@
 use crate::{
-    chess::table::{Board, Color, Type},
+    chess::table::{Board, Type},
     engine::evaluate::evaluate::{calculate_game_phase, evaluate},
+    engine::search::MATE_SCORE,
 };
 
 const MAX_DEPTH: i32 = -5;
 
-pub fn quiescence(b: &mut Board, mut alpha: i32, beta: i32, depth: i32) -> i32 {
+pub fn quiescence(
+    b: &mut Board,
+    mut alpha: i32,
+    beta: i32,
+    depth: i32,
+    ply: i32,
+    nodes: &mut u64,
+) -> i32 {
+    *nodes += 1;
     if depth <= MAX_DEPTH {
         let phase = calculate_game_phase(b);
         return evaluate(b, phase);
     }
 
+    // Standing pat is no option in check, every evasion has to be searched
+    if b.is_king_in_check(b.get_side()) {
+        return search_evasions(b, alpha, beta, depth, ply, nodes);
+    }
+
     let phase = calculate_game_phase(b);
     let stand_pat = evaluate(b, phase);
 
@@ -29,10 +43,6 @@ pub fn quiescence(b: &mut Board, mut alpha: i32, beta: i32, depth: i32) -> i32 {
     let mut captures = Vec::new();
     b.gen_all_attacks(&mut captures);
 
-    if captures.is_empty() {
-        return stand_pat;
-    }
-
     let mut best_score = stand_pat;
 
     for capture_move in captures {
@@ -48,18 +58,12 @@ pub fn quiescence(b: &mut Board, mut alpha: i32, beta: i32, depth: i32) -> i32 {
 
         let undo = b.make_move_with_undo(&capture_move);
 
-        let opponent = if b.is_white_turn {
-            Color::Black
-        } else {
-            Color::White
-        };
-
-        if b.is_king_in_check(opponent) {
+        if b.is_king_in_check(b.get_side().opposite()) {
             b.unmake_move(&capture_move, undo);
             continue;
         }
 
-        let score = -quiescence(b, -beta, -alpha, depth - 1);
+        let score = -quiescence(b, -beta, -alpha, depth - 1, ply + 1, nodes);
 
         b.unmake_move(&capture_move, undo);
 
@@ -76,5 +80,73 @@ pub fn quiescence(b: &mut Board, mut alpha: i32, beta: i32, depth: i32) -> i32 {
         }
     }
 
+    // Quiet checks only at the first ply, deeper they would not terminate
+    if depth == 0 {
+        let mut checks = Vec::new();
+        b.generate_quiet_checks(b.get_side(), &mut checks);
+
+        for check_move in checks {
+            if b.see(&check_move) < 0 {
+                continue;
+            }
+
+            let undo = b.make_move_with_undo(&check_move);
+            let score = -quiescence(b, -beta, -alpha, depth - 1, ply + 1, nodes);
+            b.unmake_move(&check_move, undo);
+
+            if score > best_score {
+                best_score = score;
+            }
+
+            if best_score > alpha {
+                alpha = best_score;
+            }
+
+            if alpha >= beta {
+                return beta;
+            }
+        }
+    }
+
+    best_score
+}
+
+fn search_evasions(
+    b: &mut Board,
+    mut alpha: i32,
+    beta: i32,
+    depth: i32,
+    ply: i32,
+    nodes: &mut u64,
+) -> i32 {
+    let mut evasions = Vec::new();
+    b.generate_evasions(b.get_side(), &mut evasions);
+
+    if evasions.is_empty() {
+        return -MATE_SCORE + ply;
+    }
+
+    evasions.sort_by_cached_key(|mv| -mv.score(b));
+
+    let mut best_score = -MATE_SCORE + ply;
+
+    for evasion in evasions {
+        let undo = b.make_move_with_undo(&evasion);
+        let score = -quiescence(b, -beta, -alpha, depth - 1, ply + 1, nodes);
+        b.unmake_move(&evasion, undo);
+
+        if score > best_score {
+            best_score = score;
+        }
+
+        if best_score > alpha {
+            alpha = best_score;
+        }
+
+        if alpha >= beta {
+            return beta;
+        }
+    }
+
     best_score
 }
@@ -1,10 +1,16 @@
 use crate::chess::moves_gen::moves_struct::Moves;
 use crate::chess::table::Board;
+use crate::engine::move_picker::MovePicker;
 use crate::engine::quiescence::quiescence;
+use crate::engine::syzygy::probe::{Wdl, probe_wdl};
+use crate::engine::syzygy::tablebase;
 use crate::engine::trasposition_table::{BoundType, TT, TTEntry};
 
-const MATE_SCORE: i32 = 20000;
+pub const MATE_SCORE: i32 = 20000;
 const CONTEMPT: i32 = 0;
+const MAX_PLY: usize = 64;
+// Tablebase wins rank below any mate found by the search but above every evaluation
+const TB_WIN_SCORE: i32 = MATE_SCORE - 1000;
 
 #[inline(always)]
 fn is_repetition(history: &[u64], current_hash: u64) -> bool {
@@ -20,34 +26,70 @@ fn is_repetition(history: &[u64], current_hash: u64) -> bool {
     false
 }
 
-#[inline(always)]
-fn pick_move(moves: &mut [(Moves, i32)], start_index: usize) {
-    let mut best_index = start_index;
-    for i in (start_index + 1)..moves.len() {
-        if moves[i].1 > moves[best_index].1 {
-            best_index = i
+/// State shared by every node of one search.
+pub struct SearchContext<'a> {
+    pub tt: &'a mut TT,
+    /// One move list per ply. Nodes beyond the last one only run quiescence.
+    pub move_buffers: Vec<Vec<Moves>>,
+    /// Hashes of the positions from the start of the game to the current node.
+    pub position_history: Vec<u64>,
+    pub killer_moves: [[Moves; 2]; MAX_PLY],
+    pub history: [[i32; 64]; 64],
+    pub counter_moves: [[Moves; 64]; 64],
+    pub nodes: u64,
+    /// Nodes after which the search unwinds.
+    pub node_limit: Option<u64>,
+    /// Set once `node_limit` is reached. Every score returned from then on
+    /// is meaningless and must be neither used nor stored.
+    pub stopped: bool,
+}
+
+impl<'a> SearchContext<'a> {
+    pub fn new(tt: &'a mut TT, position_history: Vec<u64>) -> Self {
+        SearchContext {
+            tt,
+            move_buffers: Vec::new(),
+            position_history,
+            killer_moves: [[Moves::NULL; 2]; MAX_PLY],
+            history: [[0; 64]; 64],
+            counter_moves: [[Moves::NULL; 64]; 64],
+            nodes: 0,
+            node_limit: None,
+            stopped: false,
         }
     }
-    moves.swap(start_index, best_index);
 }
 
 pub fn negamax(
     b: &mut Board,
+    ctx: &mut SearchContext,
     mut depth: u8,
     mut alpha: i32,
     mut beta: i32,
-    tt: &mut TT,
-    move_buffers: &mut [Vec<Moves>],
-    position_history: &mut Vec<u64>,
-    killer_moves: &mut [[Moves; 2]; 64],
-    history: &mut [[i32; 64]; 64],
+    prev_move: Moves,
     ply: i32,
 ) -> i32 {
+    ctx.nodes += 1;
+    if ctx.node_limit.is_some_and(|limit| ctx.nodes >= limit) {
+        ctx.stopped = true;
+    }
+    if ctx.stopped {
+        return 0;
+    }
     let alpha_orig = alpha;
     // let beta_orig = beta;
     let current_hash = b.get_hash();
 
-    if is_repetition(position_history, current_hash) {
+    if is_repetition(&ctx.position_history, current_hash) {
+        return -CONTEMPT;
+    }
+
+    if b.is_insufficient_material() {
+        return -CONTEMPT;
+    }
+
+    // A mate delivered on the 100th ply still stands, so positions in check are searched on
+    if b.get_halfmove_clock() >= 100 && !b.is_king_in_check(b.get_side()) {
         return -CONTEMPT;
     }
 
@@ -58,7 +100,7 @@ pub fn negamax(
     }
 
     let mut tt_move = None;
-    if let Some(entry) = tt.probe(current_hash) {
+    if let Some(entry) = ctx.tt.probe(current_hash) {
         tt_move = Some(entry.best_move);
         if entry.depth >= depth {
             match entry.bound {
@@ -76,20 +118,44 @@ pub fn negamax(
         }
     }
 
+    // Right after a zeroing move the tablebase result is exact, so a win or
+    // loss that is already outside the window needs no further search
+    if ply > 0 && b.get_halfmove_clock() == 0 {
+        let max_pieces = tablebase::max_pieces();
+        if max_pieces > 0
+            && tablebase::can_probe(b, max_pieces)
+            && let Some(wdl) = probe_wdl(b)
+        {
+            let (score, bound) = match wdl {
+                Wdl::Win => (TB_WIN_SCORE - ply, BoundType::Lower),
+                Wdl::Loss => (-TB_WIN_SCORE + ply, BoundType::Upper),
+                _ => (-CONTEMPT, BoundType::Exact),
+            };
+
+            if bound == BoundType::Exact
+                || (bound == BoundType::Lower && score >= beta)
+                || (bound == BoundType::Upper && score <= alpha)
+            {
+                let entry =
+                    TTEntry::new(current_hash, score, Moves::NULL, depth, bound, ctx.tt.age);
+                ctx.tt.store(current_hash, entry);
+                return score;
+            }
+        }
+    }
+
     if depth == 0 {
         if b.is_king_in_check(b.get_side()) {
             depth = 1;
         } else {
-            return quiescence(b, alpha, beta, 0);
+            return quiescence(b, alpha, beta, 0, ply, &mut ctx.nodes);
         }
     }
 
-    if move_buffers.is_empty() {
-        return quiescence(b, alpha, beta, 0);
+    if ply as usize >= ctx.move_buffers.len() {
+        return quiescence(b, alpha, beta, 0, ply, &mut ctx.nodes);
     }
 
-    let (current_buffer, next_buffers) = move_buffers.split_at_mut(1);
-    current_buffer[0].clear();
     let turn = b.get_side();
 
     // Null Move Pruning
@@ -104,77 +170,46 @@ pub fn negamax(
 
         let score = -negamax(
             b,
+            ctx,
             depth - 1 - r,
             -beta,
             -beta + 1,
-            tt,
-            next_buffers,
-            position_history,
-            killer_moves,
-            history,
+            Moves::NULL,
             ply + 1,
         );
 
         b.unmake_null_move(undo_null);
 
+        if ctx.stopped {
+            return 0;
+        }
         if score >= beta {
             return beta;
         }
     }
 
-    let moves = b.get_legal_moves(turn, &mut current_buffer[0]);
-
-    if moves.is_empty() {
-        if b.is_king_in_check(turn) {
-            return -MATE_SCORE + ply;
-        } else {
-            return 0;
-        }
-    }
-
-    let mut scored_moves: Vec<(Moves, i32)> = moves
-        .iter()
-        .map(|&mv| {
-            let mut score = 0;
-            if let Some(tm) = tt_move {
-                if mv == tm {
-                    return (mv, 2_000_000_000);
-                }
-            }
-
-            if mv.is_capture() {
-                score = mv.score(b);
-            } else {
-                let mut is_killer = false;
-                if (ply as usize) < 64 {
-                    if mv == killer_moves[ply as usize][0] {
-                        score = 900_000;
-                        is_killer = true;
-                    } else if mv == killer_moves[ply as usize][1] {
-                        score = 800_000;
-                        is_killer = true;
-                    }
-                }
-                if !is_killer {
-                    let from = mv.from() as usize;
-                    let to = mv.to() as usize;
-                    score = history[from][to].min(700_000);
-                }
-            }
-            (mv, score)
-        })
-        .collect();
-
-    // scored_moves.sort_unstable_by_key(|(_, score)| -score);
+    let killers = if (ply as usize) < MAX_PLY {
+        ctx.killer_moves[ply as usize]
+    } else {
+        [Moves::NULL; 2]
+    };
+    let counter_move =
+        ctx.counter_moves[prev_move.from().index() as usize][prev_move.to().index() as usize];
+    // The list is taken out of the context while the children search with it
+    let mut moves = std::mem::take(&mut ctx.move_buffers[ply as usize]);
+    moves.clear();
+    let mut picker = MovePicker::new(&mut moves, tt_move, killers, counter_move);
 
     let mut best_score = -MATE_SCORE;
-    let mut best_move = scored_moves[0].0;
+    let mut best_move = None;
+    let mut i = 0;
 
-    for i in 0..scored_moves.len() {
-        pick_move(&mut scored_moves, i);
-        let mv = &scored_moves[i].0;
-        let undo_info = b.make_move_with_undo(mv);
-        position_history.push(b.get_hash());
+    while let Some(mv) = picker.next(b, &ctx.history) {
+        if best_move.is_none() {
+            best_move = Some(mv);
+        }
+        let undo_info = b.make_move_with_undo(&mv);
+        ctx.position_history.push(b.get_hash());
 
         let in_check = b.is_king_in_check(turn);
         let extension = if in_check { 1 } else { 0 };
@@ -184,18 +219,7 @@ pub fn negamax(
         let mut score;
 
         if i == 0 {
-            score = -negamax(
-                b,
-                new_depth,
-                -beta,
-                -alpha,
-                tt,
-                next_buffers,
-                position_history,
-                killer_moves,
-                history,
-                ply + 1,
-            );
+            score = -negamax(b, ctx, new_depth, -beta, -alpha, mv, ply + 1);
         } else {
             let mut reduction = 0;
             if depth >= 3 && i >= 4 && !mv.is_capture() && !mv.is_promotion() && !in_check {
@@ -207,80 +231,67 @@ pub fn negamax(
 
             let research_depth = new_depth.saturating_sub(reduction);
 
-            score = -negamax(
-                b,
-                research_depth,
-                -alpha - 1,
-                -alpha,
-                tt,
-                next_buffers,
-                position_history,
-                killer_moves,
-                history,
-                ply + 1,
-            );
+            score = -negamax(b, ctx, research_depth, -alpha - 1, -alpha, mv, ply + 1);
 
             if score > alpha && reduction > 0 {
-                score = -negamax(
-                    b,
-                    new_depth,
-                    -alpha - 1,
-                    -alpha,
-                    tt,
-                    next_buffers,
-                    position_history,
-                    killer_moves,
-                    history,
-                    ply + 1,
-                );
+                score = -negamax(b, ctx, new_depth, -alpha - 1, -alpha, mv, ply + 1);
             }
 
             if score > alpha && score < beta {
-                score = -negamax(
-                    b,
-                    new_depth,
-                    -beta,
-                    -alpha,
-                    tt,
-                    next_buffers,
-                    position_history,
-                    killer_moves,
-                    history,
-                    ply + 1,
-                );
+                score = -negamax(b, ctx, new_depth, -beta, -alpha, mv, ply + 1);
             }
         }
 
-        position_history.pop();
-        b.unmake_move(mv, undo_info);
+        ctx.position_history.pop();
+        b.unmake_move(&mv, undo_info);
 
+        if ctx.stopped {
+            break;
+        }
         if score > best_score {
             best_score = score;
             if score > alpha {
                 alpha = score;
-                best_move = *mv;
+                best_move = Some(mv);
             }
         }
 
         // Beta Cutoff
         if alpha >= beta {
             if !mv.is_capture() {
-                if (ply as usize) < 64 {
-                    if killer_moves[ply as usize][0] != *mv {
-                        killer_moves[ply as usize][1] = killer_moves[ply as usize][0];
-                        killer_moves[ply as usize][0] = *mv;
+                if (ply as usize) < MAX_PLY {
+                    let killers = &mut ctx.killer_moves[ply as usize];
+                    if killers[0] != mv {
+                        killers[1] = killers[0];
+                        killers[0] = mv;
                     }
                 }
+                ctx.counter_moves[prev_move.from().index() as usize]
+                    [prev_move.to().index() as usize] = mv;
                 let bonus = (depth as i32) * (depth as i32);
-                let from = mv.from() as usize;
-                let to = mv.to() as usize;
-                if history[from][to] < 1_000_000 {
-                    history[from][to] += bonus;
+                let from = mv.from().index() as usize;
+                let to = mv.to().index() as usize;
+                if ctx.history[from][to] < 1_000_000 {
+                    ctx.history[from][to] += bonus;
                 }
             }
             break;
         }
+        i += 1;
     }
+    ctx.move_buffers[ply as usize] = moves;
+
+    if ctx.stopped {
+        return 0;
+    }
+
+    let Some(best_move) = best_move else {
+        return if b.is_king_in_check(turn) {
+            -MATE_SCORE + ply
+        } else {
+            0
+        };
+    };
 
     let bound = if best_score <= alpha_orig {
         BoundType::Upper
@@ -290,8 +301,15 @@ pub fn negamax(
         BoundType::Exact
     };
 
-    let entry = TTEntry::new(current_hash, best_score, best_move, depth, bound, tt.age);
-    tt.store(current_hash, entry);
+    let entry = TTEntry::new(
+        current_hash,
+        best_score,
+        best_move,
+        depth,
+        bound,
+        ctx.tt.age,
+    );
+    ctx.tt.store(current_hash, entry);
 
     best_score
 }
@@ -0,0 +1,9 @@
+pub mod probe;
+// Tables are only loaded through the UCI `SyzygyPath` option
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+mod table;
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+pub mod tablebase;
+
+#[cfg(test)]
+mod tests;
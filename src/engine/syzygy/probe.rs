@@ -0,0 +1,257 @@
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::chess::table::{Board, Type};
+use crate::engine::syzygy::table::{TableProbe, TbType};
+use crate::engine::syzygy::tablebase::{self, probe_table};
+
+/// Win/draw/loss from the side to move's point of view. Cursed wins and
+/// blessed losses are decided only by the fifty-move rule.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Wdl {
+    Loss = -2,
+    BlessedLoss = -1,
+    Draw = 0,
+    CursedWin = 1,
+    Win = 2,
+}
+
+impl Wdl {
+    fn from_value(value: i32) -> Wdl {
+        match value {
+            v if v <= -2 => Wdl::Loss,
+            -1 => Wdl::BlessedLoss,
+            0 => Wdl::Draw,
+            1 => Wdl::CursedWin,
+            _ => Wdl::Win,
+        }
+    }
+
+    fn negate(self) -> Wdl {
+        Wdl::from_value(-(self as i32))
+    }
+
+    fn sign(self) -> i32 {
+        (self as i32).signum()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ProbeState {
+    Ok,
+    Fail,
+    ChangeStm,
+    // The best move zeroes the fifty-move counter, so the stored DTZ can not be used
+    ZeroingBestMove,
+}
+
+fn is_zeroing(b: &Board, mv: &Moves) -> bool {
+    mv.is_capture() || b.get_piece_type_at_square(mv.from()) == Some(Type::Pawn)
+}
+
+/// DTZ of a position whose best move zeroes the fifty-move counter.
+fn dtz_before_zeroing(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => 1,
+        Wdl::CursedWin => 101,
+        Wdl::BlessedLoss => -101,
+        Wdl::Loss => -1,
+        Wdl::Draw => 0,
+    }
+}
+
+/// Resolves captures (and pawn moves when `check_zeroing`) before trusting the
+/// table: tables do not encode en passant rights, and the best move may be a
+/// capture into a table with a different result.
+fn search(b: &mut Board, check_zeroing: bool, state: &mut ProbeState) -> Wdl {
+    let mut buffer = Vec::with_capacity(64);
+    let turn = b.get_side();
+    let moves = b.get_legal_moves(turn, &mut buffer);
+
+    let mut best = Wdl::Loss;
+    let mut move_count = 0;
+
+    for mv in moves {
+        if !(mv.is_capture() || check_zeroing && is_zeroing(b, mv)) {
+            continue;
+        }
+        move_count += 1;
+
+        let undo = b.make_move_with_undo(mv);
+        let value = search(b, false, state).negate();
+        b.unmake_move(mv, undo);
+
+        if *state == ProbeState::Fail {
+            return Wdl::Draw;
+        }
+
+        if value > best {
+            best = value;
+            if value == Wdl::Win {
+                *state = ProbeState::ZeroingBestMove;
+                return value;
+            }
+        }
+    }
+
+    let no_more_moves = move_count > 0 && move_count == moves.len();
+    let value = if no_more_moves {
+        best
+    } else {
+        match probe_table(b, TbType::Wdl, 0) {
+            Some(TableProbe::Value(v)) => Wdl::from_value(v),
+            _ => {
+                *state = ProbeState::Fail;
+                return Wdl::Draw;
+            }
+        }
+    };
+
+    if best >= value {
+        *state = if best > Wdl::Draw || no_more_moves {
+            ProbeState::ZeroingBestMove
+        } else {
+            ProbeState::Ok
+        };
+        return best;
+    }
+
+    *state = ProbeState::Ok;
+    value
+}
+
+/// Win/draw/loss for the side to move, `None` if the tables can not tell.
+pub fn probe_wdl(b: &mut Board) -> Option<Wdl> {
+    let mut state = ProbeState::Ok;
+    let wdl = search(b, false, &mut state);
+    (state != ProbeState::Fail).then_some(wdl)
+}
+
+fn probe_dtz_inner(b: &mut Board, state: &mut ProbeState) -> i32 {
+    *state = ProbeState::Ok;
+    let wdl = search(b, true, state);
+
+    if *state == ProbeState::Fail || wdl == Wdl::Draw {
+        return 0;
+    }
+    if *state == ProbeState::ZeroingBestMove {
+        return dtz_before_zeroing(wdl);
+    }
+
+    match probe_table(b, TbType::Dtz, wdl as i32) {
+        None => {
+            *state = ProbeState::Fail;
+            return 0;
+        }
+        Some(TableProbe::Value(dtz)) => {
+            let cursed = matches!(wdl, Wdl::BlessedLoss | Wdl::CursedWin);
+            return (dtz + if cursed { 100 } else { 0 }) * wdl.sign();
+        }
+        Some(TableProbe::ChangeStm) => *state = ProbeState::ChangeStm,
+    }
+
+    // The table only stores the other side to move: take the best DTZ
+    // over a one ply search instead
+    let mut buffer = Vec::with_capacity(64);
+    let turn = b.get_side();
+    let moves = b.get_legal_moves(turn, &mut buffer);
+    let mut min_dtz = 0xFFFF;
+
+    for mv in moves {
+        let zeroing = is_zeroing(b, mv);
+        let undo = b.make_move_with_undo(mv);
+
+        let mut dtz = if zeroing {
+            -dtz_before_zeroing(search(b, false, state))
+        } else {
+            -probe_dtz_inner(b, state)
+        };
+
+        if dtz == 1 && b.is_king_in_check(b.get_side()) && !has_legal_moves(b) {
+            min_dtz = 1;
+        }
+
+        if !zeroing {
+            dtz += dtz.signum();
+        }
+
+        if dtz < min_dtz && dtz.signum() == wdl.sign() {
+            min_dtz = dtz;
+        }
+
+        b.unmake_move(mv, undo);
+
+        if *state == ProbeState::Fail {
+            return 0;
+        }
+    }
+
+    if min_dtz == 0xFFFF { -1 } else { min_dtz }
+}
+
+/// Distance to the next zeroing move in plies, positive when winning and
+/// negative when losing. Values beyond 100 are cursed wins or blessed losses.
+pub fn probe_dtz(b: &mut Board) -> Option<i32> {
+    let mut state = ProbeState::Ok;
+    let dtz = probe_dtz_inner(b, &mut state);
+    (state != ProbeState::Fail).then_some(dtz)
+}
+
+fn has_legal_moves(b: &Board) -> bool {
+    let mut buffer = Vec::with_capacity(64);
+    let turn = b.get_side();
+    !b.get_legal_moves(turn, &mut buffer).is_empty()
+}
+
+/// Keeps only the root moves that preserve the best tablebase result. Wins
+/// are narrowed to the moves with the shortest DTZ so the search always makes
+/// progress towards zeroing the fifty-move counter, losses to the ones that
+/// resist the longest. Returns false and leaves `moves` untouched when the
+/// position can not be probed.
+pub fn filter_root_moves(b: &mut Board, moves: &mut Vec<Moves>) -> bool {
+    let max_pieces = tablebase::max_pieces();
+    if moves.is_empty() || !tablebase::can_probe(b, max_pieces) {
+        return false;
+    }
+
+    let mut ranked = Vec::with_capacity(moves.len());
+    for mv in moves.iter() {
+        let undo = b.make_move_with_undo(mv);
+
+        let dtz = if b.get_halfmove_clock() == 0 {
+            probe_wdl(b).map(|wdl| dtz_before_zeroing(wdl.negate()))
+        } else {
+            probe_dtz(b).map(|dtz| -dtz - dtz.signum())
+        };
+
+        // A mating move ends the game right away
+        let dtz = dtz.map(|dtz| {
+            if dtz == 2 && b.is_king_in_check(b.get_side()) && !has_legal_moves(b) {
+                1
+            } else {
+                dtz
+            }
+        });
+
+        b.unmake_move(mv, undo);
+
+        match dtz {
+            Some(dtz) => ranked.push((*mv, dtz)),
+            None => return false,
+        }
+    }
+
+    let rank = |dtz: i32| match dtz {
+        d if d > 0 => 1000 - d,
+        d if d < 0 => -1000 - d,
+        _ => 0,
+    };
+    let best = ranked.iter().map(|&(_, dtz)| rank(dtz)).max().unwrap_or(0);
+
+    moves.clear();
+    moves.extend(
+        ranked
+            .iter()
+            .filter(|&&(_, dtz)| rank(dtz) == best)
+            .map(|&(mv, _)| mv),
+    );
+    true
+}
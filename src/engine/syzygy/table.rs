@@ -0,0 +1,1096 @@
+use std::path::Path;
+
+use lazy_static::lazy_static;
+
+pub const TB_PIECES: usize = 7;
+
+const WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+// Per-table flags stored in front of the size information
+const FLAG_STM: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TbType {
+    Wdl,
+    Dtz,
+}
+
+/// Square and piece encoding tables shared by every table file.
+struct Encoding {
+    map_b1h1h7: [u64; 64],
+    map_a1d1d4: [u64; 64],
+    map_kk: [[u64; 64]; 10],
+    binomial: [[u64; 64]; 7],
+    map_pawns: [u64; 64],
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+lazy_static! {
+    static ref ENCODING: Encoding = Encoding::new();
+}
+
+#[inline(always)]
+fn off_a1h8(sq: u8) -> i32 {
+    (sq / 8) as i32 - (sq % 8) as i32
+}
+
+impl Encoding {
+    fn new() -> Self {
+        let mut e = Encoding {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 7],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        // Squares below the a1-h8 diagonal map to 0..27
+        let mut code = 0;
+        for sq in 0..64u8 {
+            if off_a1h8(sq) < 0 {
+                e.map_b1h1h7[sq as usize] = code;
+                code += 1;
+            }
+        }
+
+        // The a1-d1-d4 triangle maps to 0..9, diagonal squares last
+        let mut diagonal = Vec::new();
+        code = 0;
+        for sq in 0..28u8 {
+            if sq % 8 > 3 {
+                continue;
+            }
+            if off_a1h8(sq) < 0 {
+                e.map_a1d1d4[sq as usize] = code;
+                code += 1;
+            } else if off_a1h8(sq) == 0 {
+                diagonal.push(sq);
+            }
+        }
+        for sq in diagonal {
+            e.map_a1d1d4[sq as usize] = code;
+            code += 1;
+        }
+
+        // The 462 legal placements of two kings with the first one in the
+        // a1-d1-d4 triangle, placements with both kings on the diagonal last
+        let mut both_on_diagonal = Vec::new();
+        code = 0;
+        for idx in 0..10u64 {
+            for s1 in 0..28u8 {
+                if s1 % 8 > 3 || e.map_a1d1d4[s1 as usize] != idx || (idx == 0 && s1 != 1) {
+                    continue;
+                }
+                for s2 in 0..64u8 {
+                    let rank_dist = (s1 / 8).abs_diff(s2 / 8);
+                    let file_dist = (s1 % 8).abs_diff(s2 % 8);
+                    if rank_dist <= 1 && file_dist <= 1 {
+                        continue;
+                    }
+                    if off_a1h8(s1) == 0 && off_a1h8(s2) > 0 {
+                        continue;
+                    }
+                    if off_a1h8(s1) == 0 && off_a1h8(s2) == 0 {
+                        both_on_diagonal.push((idx as usize, s2 as usize));
+                    } else {
+                        e.map_kk[idx as usize][s2 as usize] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, sq) in both_on_diagonal {
+            e.map_kk[idx][sq] = code;
+            code += 1;
+        }
+
+        e.binomial[0][0] = 1;
+        for n in 1..64 {
+            for k in 0..7.min(n + 1) {
+                e.binomial[k][n] = if k > 0 { e.binomial[k - 1][n - 1] } else { 0 }
+                    + if k < n { e.binomial[k][n - 1] } else { 0 };
+            }
+        }
+
+        // Pawns on a2-h7 map to 0..47, the leading pawn is the one with the
+        // highest value: nearest to the edge and, on the same file, lowest rank
+        let mut available = 47;
+        for lead_pawns_cnt in 1..6 {
+            for file in 0..4u8 {
+                let mut idx = 0;
+                for rank in 1..7u8 {
+                    let sq = (rank * 8 + file) as usize;
+                    if lead_pawns_cnt == 1 {
+                        e.map_pawns[sq] = available;
+                        e.map_pawns[sq ^ 7] = available - 1;
+                        available = available.saturating_sub(2);
+                    }
+                    e.lead_pawn_idx[lead_pawns_cnt][sq] = idx;
+                    idx += e.binomial[lead_pawns_cnt - 1][e.map_pawns[sq] as usize];
+                }
+                e.lead_pawns_size[lead_pawns_cnt][file as usize] = idx;
+            }
+        }
+
+        e
+    }
+}
+
+pub fn init() {
+    lazy_static::initialize(&ENCODING);
+}
+
+#[inline(always)]
+fn u16_le(bytes: &[u8], off: usize) -> u16 {
+    bytes
+        .get(off..off + 2)
+        .map_or(0, |b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+#[inline(always)]
+fn u32_le(bytes: &[u8], off: usize) -> u32 {
+    bytes
+        .get(off..off + 4)
+        .map_or(0, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[inline(always)]
+fn u32_be(bytes: &[u8], off: usize) -> u32 {
+    bytes
+        .get(off..off + 4)
+        .map_or(0, |b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[inline(always)]
+fn byte(bytes: &[u8], off: usize) -> u8 {
+    bytes.get(off).copied().unwrap_or(0)
+}
+
+/// Decoding information for one compressed sub-table (one side to move and,
+/// for pawn endings, one file of the leading pawn).
+#[derive(Clone, Default)]
+struct PairsData {
+    flags: u8,
+    max_sym_len: u8,
+    min_sym_len: u8,
+    num_blocks: usize,
+    block_size: usize,
+    span: usize,
+    lowest_sym: usize,
+    btree: usize,
+    block_length: usize,
+    block_length_size: usize,
+    sparse_index: usize,
+    sparse_index_size: usize,
+    data: usize,
+    base64: Vec<u64>,
+    sym_len: Vec<u8>,
+    pieces: [u8; TB_PIECES],
+    group_idx: [u64; TB_PIECES + 1],
+    group_len: [usize; TB_PIECES + 1],
+    map_idx: [u16; 4],
+}
+
+impl PairsData {
+    // Each btree node packs the left symbol in the first 12 bits and the right one in the next 12
+    #[inline(always)]
+    fn left(&self, bytes: &[u8], sym: usize) -> usize {
+        let off = self.btree + 3 * sym;
+        ((byte(bytes, off + 1) as usize & 0xF) << 8) | byte(bytes, off) as usize
+    }
+
+    #[inline(always)]
+    fn right(&self, bytes: &[u8], sym: usize) -> usize {
+        let off = self.btree + 3 * sym;
+        ((byte(bytes, off + 2) as usize) << 4) | (byte(bytes, off + 1) as usize >> 4)
+    }
+
+    fn set_sym_len(&mut self, bytes: &[u8], sym: usize, visited: &mut [bool]) -> u8 {
+        visited[sym] = true;
+        let sr = self.right(bytes, sym);
+        if sr == 0xFFF {
+            return 0;
+        }
+        let sl = self.left(bytes, sym);
+        if sl >= visited.len() || sr >= visited.len() {
+            return 0;
+        }
+
+        if !visited[sl] {
+            self.sym_len[sl] = self.set_sym_len(bytes, sl, visited);
+        }
+        if !visited[sr] {
+            self.sym_len[sr] = self.set_sym_len(bytes, sr, visited);
+        }
+
+        self.sym_len[sl]
+            .wrapping_add(self.sym_len[sr])
+            .wrapping_add(1)
+    }
+
+    /// Reads the block layout and the canonical Huffman code of this sub-table,
+    /// returning the offset just past it.
+    fn set_sizes(&mut self, bytes: &[u8], mut off: usize) -> usize {
+        self.flags = byte(bytes, off);
+        off += 1;
+
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            self.min_sym_len = byte(bytes, off);
+            return off + 1;
+        }
+
+        let groups = self.group_len[..TB_PIECES]
+            .iter()
+            .position(|&l| l == 0)
+            .unwrap_or(TB_PIECES);
+        let tb_size = self.group_idx[groups];
+
+        self.block_size = 1 << byte(bytes, off);
+        self.span = 1 << byte(bytes, off + 1);
+        self.sparse_index_size = tb_size.div_ceil(self.span as u64) as usize;
+        let padding = byte(bytes, off + 2) as usize;
+        self.num_blocks = u32_le(bytes, off + 3) as usize;
+        self.block_length_size = self.num_blocks + padding;
+        self.max_sym_len = byte(bytes, off + 7);
+        self.min_sym_len = byte(bytes, off + 8);
+        off += 9;
+        self.lowest_sym = off;
+
+        let len = (self.max_sym_len as usize + 1).saturating_sub(self.min_sym_len as usize);
+        self.base64 = vec![0; len];
+        for i in (0..len.saturating_sub(1)).rev() {
+            let lowest = u16_le(bytes, self.lowest_sym + 2 * i) as u64;
+            let next = u16_le(bytes, self.lowest_sym + 2 * (i + 1)) as u64;
+            self.base64[i] = self.base64[i + 1].wrapping_add(lowest).wrapping_sub(next) / 2;
+        }
+        for i in 0..len {
+            let shift = 64 - i as u32 - self.min_sym_len as u32;
+            self.base64[i] = self.base64[i].checked_shl(shift).unwrap_or(0);
+        }
+        off += len * 2;
+
+        let symbols = u16_le(bytes, off) as usize;
+        off += 2;
+        self.btree = off;
+        self.sym_len = vec![0; symbols];
+
+        let mut visited = vec![false; symbols];
+        for sym in 0..symbols {
+            if !visited[sym] {
+                self.sym_len[sym] = self.set_sym_len(bytes, sym, &mut visited);
+            }
+        }
+
+        off + symbols * 3 + (symbols & 1)
+    }
+
+    /// Decompresses the value stored at `idx`. Blocks are located through the
+    /// sparse index, then symbols are Huffman-decoded until the one covering
+    /// `idx` is found and finally expanded through the pairing tree.
+    fn decompress(&self, bytes: &[u8], idx: u64) -> i32 {
+        if self.flags & FLAG_SINGLE_VALUE != 0 {
+            return self.min_sym_len as i32;
+        }
+
+        let span = self.span as u64;
+        let k = (idx / span) as usize;
+        let entry = self.sparse_index + 6 * k;
+        let mut block = u32_le(bytes, entry) as usize;
+        let mut offset = u16_le(bytes, entry + 4) as i64;
+        offset += (idx % span) as i64 - (span / 2) as i64;
+
+        let block_length = |block: usize| u16_le(bytes, self.block_length + 2 * block) as i64;
+
+        while offset < 0 && block > 0 {
+            block -= 1;
+            offset += block_length(block) + 1;
+        }
+        while offset > block_length(block) && block + 1 < self.block_length_size {
+            offset -= block_length(block) + 1;
+            block += 1;
+        }
+
+        let mut ptr = self.data + block * self.block_size;
+        let mut buf64 = ((u32_be(bytes, ptr) as u64) << 32) | u32_be(bytes, ptr + 4) as u64;
+        ptr += 8;
+        let mut buf64_size = 64;
+        let min_sym_len = self.min_sym_len as usize;
+
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while len + 1 < self.base64.len() && buf64 < self.base64[len] {
+                len += 1;
+            }
+
+            sym = ((buf64 - self.base64[len]) >> (64 - len - min_sym_len)) as usize;
+            sym += u16_le(bytes, self.lowest_sym + 2 * len) as usize;
+            if sym >= self.sym_len.len() {
+                return 0;
+            }
+
+            if offset < self.sym_len[sym] as i64 + 1 {
+                break;
+            }
+
+            offset -= self.sym_len[sym] as i64 + 1;
+            len += min_sym_len;
+            buf64 <<= len;
+            buf64_size -= len;
+
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= (u32_be(bytes, ptr) as u64) << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        while self.sym_len[sym] != 0 {
+            let left = self.left(bytes, sym);
+            if offset < self.sym_len[left] as i64 + 1 {
+                sym = left;
+            } else {
+                offset -= self.sym_len[left] as i64 + 1;
+                sym = self.right(bytes, sym);
+            }
+        }
+
+        self.left(bytes, sym) as i32
+    }
+}
+
+/// Material description of a table, derived from its file name (e.g. `KRPvKR`).
+#[derive(Clone)]
+pub struct TableInfo {
+    pub key: u64,
+    pub key2: u64,
+    pub piece_count: usize,
+    pub has_pawns: bool,
+    pub has_unique_pieces: bool,
+    pub pawn_count: [usize; 2],
+}
+
+const PIECE_LETTERS: &str = "PNBRQK";
+
+/// Material key from piece counts indexed by `[color][piece id]`, 4 bits per count.
+pub fn material_key(counts: &[[usize; 6]; 2]) -> u64 {
+    let mut key = 0;
+    for (color, pieces) in counts.iter().enumerate() {
+        for (id, &count) in pieces.iter().enumerate() {
+            key |= (count as u64) << (4 * (color * 6 + id));
+        }
+    }
+    key
+}
+
+impl TableInfo {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (white, black) = name.split_once('v')?;
+        let mut counts = [[0usize; 6]; 2];
+        for (color, side) in [white, black].iter().enumerate() {
+            for c in side.chars() {
+                counts[color][PIECE_LETTERS.find(c)?] += 1;
+            }
+            if counts[color][5] != 1 {
+                return None;
+            }
+        }
+
+        let piece_count: usize = counts.iter().flatten().sum();
+        if piece_count > TB_PIECES {
+            return None;
+        }
+
+        let has_unique_pieces = counts.iter().any(|pieces| pieces[..5].contains(&1));
+
+        // The side with fewer pawns leads, as it compresses better
+        let (white_pawns, black_pawns) = (counts[0][0], counts[1][0]);
+        let white_leads = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        let pawn_count = if white_leads {
+            [white_pawns, black_pawns]
+        } else {
+            [black_pawns, white_pawns]
+        };
+
+        let swapped = [counts[1], counts[0]];
+        Some(TableInfo {
+            key: material_key(&counts),
+            key2: material_key(&swapped),
+            piece_count,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces,
+            pawn_count,
+        })
+    }
+}
+
+/// A loaded `.rtbw` or `.rtbz` file together with its decoding tables.
+pub struct Table {
+    tb_type: TbType,
+    bytes: Vec<u8>,
+    info: TableInfo,
+    items: [[PairsData; 4]; 2],
+    map: usize,
+}
+
+/// Everything `Table::probe` needs to know about the position, already
+/// expressed in table terms: piece codes are 1-6 for White pawn to king and
+/// 9-14 for Black.
+pub struct TbPosition {
+    pub pieces: Vec<(u8, u8)>,
+    pub material_key: u64,
+    pub white_to_move: bool,
+}
+
+pub enum TableProbe {
+    Value(i32),
+    ChangeStm,
+}
+
+impl Table {
+    pub fn load(path: &Path, tb_type: TbType, info: &TableInfo) -> Option<Self> {
+        Self::from_bytes(std::fs::read(path).ok()?, tb_type, info)
+    }
+
+    fn from_bytes(bytes: Vec<u8>, tb_type: TbType, info: &TableInfo) -> Option<Self> {
+        let magic = match tb_type {
+            TbType::Wdl => WDL_MAGIC,
+            TbType::Dtz => DTZ_MAGIC,
+        };
+        if bytes.len() % 64 != 16 || bytes[..4] != magic {
+            return None;
+        }
+
+        let mut table = Table {
+            tb_type,
+            bytes,
+            info: info.clone(),
+            items: Default::default(),
+            map: 0,
+        };
+        table.parse();
+        Some(table)
+    }
+
+    #[inline(always)]
+    fn sides(&self) -> usize {
+        if self.tb_type == TbType::Wdl && self.info.key != self.info.key2 {
+            2
+        } else {
+            1
+        }
+    }
+
+    #[inline(always)]
+    fn pairs(&self, stm: usize, file: usize) -> &PairsData {
+        let side = if self.tb_type == TbType::Wdl {
+            stm % 2
+        } else {
+            0
+        };
+        &self.items[side][if self.info.has_pawns { file } else { 0 }]
+    }
+
+    fn parse(&mut self) {
+        let bytes = std::mem::take(&mut self.bytes);
+        let sides = self.sides();
+        let max_file = if self.info.has_pawns { 3 } else { 0 };
+        let pp = self.info.has_pawns && self.info.pawn_count[1] > 0;
+
+        // Skip the magic and the split/pawns flag byte
+        let mut off = 5;
+
+        for file in 0..=max_file {
+            let first = byte(&bytes, off);
+            let second = if pp { byte(&bytes, off + 1) } else { 0xFF };
+            let order = [
+                [(first & 0xF) as usize, (second & 0xF) as usize],
+                [(first >> 4) as usize, (second >> 4) as usize],
+            ];
+            off += 1 + pp as usize;
+
+            for k in 0..self.info.piece_count {
+                let b = byte(&bytes, off);
+                for side in 0..sides {
+                    self.items[side][file].pieces[k] = if side == 1 { b >> 4 } else { b & 0xF };
+                }
+                off += 1;
+            }
+
+            for (side, &order) in order.iter().enumerate().take(sides) {
+                self.set_groups(side, file, order);
+            }
+        }
+
+        off += off & 1;
+
+        for file in 0..=max_file {
+            for side in 0..sides {
+                off = self.items[side][file].set_sizes(&bytes, off);
+            }
+        }
+
+        if self.tb_type == TbType::Dtz {
+            off = self.set_dtz_map(&bytes, off, max_file);
+        }
+
+        for file in 0..=max_file {
+            for side in 0..sides {
+                let d = &mut self.items[side][file];
+                d.sparse_index = off;
+                off += d.sparse_index_size * 6;
+            }
+        }
+
+        for file in 0..=max_file {
+            for side in 0..sides {
+                let d = &mut self.items[side][file];
+                d.block_length = off;
+                off += d.block_length_size * 2;
+            }
+        }
+
+        for file in 0..=max_file {
+            for side in 0..sides {
+                off = (off + 0x3F) & !0x3F;
+                let d = &mut self.items[side][file];
+                d.data = off;
+                off += d.num_blocks * d.block_size;
+            }
+        }
+
+        self.bytes = bytes;
+    }
+
+    /// Splits the piece sequence into groups of identical pieces and computes
+    /// the index multiplier of each group in the table's encoding order.
+    fn set_groups(&mut self, side: usize, file: usize, order: [usize; 2]) {
+        let e = &*ENCODING;
+        let info = &self.info;
+        let d = &mut self.items[side][file];
+
+        let mut n = 0;
+        let mut first_len: i32 = if info.has_pawns {
+            0
+        } else if info.has_unique_pieces {
+            3
+        } else {
+            2
+        };
+        d.group_len[0] = 1;
+
+        for i in 1..info.piece_count {
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            } else {
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+
+        let pp = info.has_pawns && info.pawn_count[1] > 0;
+        let mut next = if pp { 2 } else { 1 };
+        let mut free_squares = 64 - d.group_len[0] - if pp { d.group_len[1] } else { 0 };
+        let mut idx: u64 = 1;
+
+        let mut k = 0;
+        while next < n || k == order[0] || k == order[1] {
+            if k == order[0] {
+                d.group_idx[0] = idx;
+                idx *= if info.has_pawns {
+                    e.lead_pawns_size[d.group_len[0]][file]
+                } else if info.has_unique_pieces {
+                    31332
+                } else {
+                    462
+                };
+            } else if k == order[1] {
+                d.group_idx[1] = idx;
+                idx *= e.binomial[d.group_len[1]][48 - d.group_len[0]];
+            } else {
+                d.group_idx[next] = idx;
+                idx *= e.binomial[d.group_len[next]][free_squares];
+                free_squares -= d.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+
+        d.group_idx[n] = idx;
+    }
+
+    fn set_dtz_map(&mut self, bytes: &[u8], mut off: usize, max_file: usize) -> usize {
+        self.map = off;
+
+        for file in 0..=max_file {
+            let flags = self.items[0][file].flags;
+            if flags & FLAG_MAPPED == 0 {
+                continue;
+            }
+            if flags & FLAG_WIDE != 0 {
+                off += off & 1;
+                for i in 0..4 {
+                    self.items[0][file].map_idx[i] = ((off - self.map) / 2 + 1) as u16;
+                    off += 2 * u16_le(bytes, off) as usize + 2;
+                }
+            } else {
+                for i in 0..4 {
+                    self.items[0][file].map_idx[i] = (off - self.map + 1) as u16;
+                    off += byte(bytes, off) as usize + 1;
+                }
+            }
+        }
+
+        off + (off & 1)
+    }
+
+    /// Converts a raw DTZ value to plies, following the per-table flags.
+    fn map_dtz(&self, file: usize, mut value: i32, wdl: i32) -> i32 {
+        const WDL_MAP: [usize; 5] = [1, 3, 0, 2, 0];
+
+        let d = self.pairs(0, file);
+        let map_idx = d.map_idx[WDL_MAP[(wdl + 2) as usize]] as usize;
+
+        if d.flags & FLAG_MAPPED != 0 {
+            value = if d.flags & FLAG_WIDE != 0 {
+                u16_le(&self.bytes, self.map + 2 * (map_idx + value as usize)) as i32
+            } else {
+                byte(&self.bytes, self.map + map_idx + value as usize) as i32
+            };
+        }
+
+        if (wdl == 2 && d.flags & FLAG_WIN_PLIES == 0)
+            || (wdl == -2 && d.flags & FLAG_LOSS_PLIES == 0)
+            || wdl == 1
+            || wdl == -1
+        {
+            value *= 2;
+        }
+
+        value + 1
+    }
+
+    /// Looks up the position: a WDL value in -2..=2 for WDL tables, or the
+    /// DTZ in plies for DTZ tables, which need the position's WDL value.
+    pub fn probe(&self, pos: &TbPosition, wdl: i32) -> TableProbe {
+        let (stm, file, idx) = self.encode(pos);
+
+        let d = self.pairs(stm, file);
+        if self.tb_type == TbType::Dtz
+            && (d.flags & FLAG_STM) as usize != stm
+            && (self.info.key != self.info.key2 || self.info.has_pawns)
+        {
+            return TableProbe::ChangeStm;
+        }
+
+        let value = d.decompress(&self.bytes, idx);
+        match self.tb_type {
+            TbType::Wdl => TableProbe::Value(value - 2),
+            TbType::Dtz => TableProbe::Value(self.map_dtz(file, value, wdl)),
+        }
+    }
+
+    /// The side to move and leading pawn file selecting the sub-table, and
+    /// the position's index within it.
+    fn encode(&self, pos: &TbPosition) -> (usize, usize, u64) {
+        let e = &*ENCODING;
+        let info = &self.info;
+
+        let mut squares = [0u8; TB_PIECES];
+        let mut pieces = [0u8; TB_PIECES];
+        let mut size = 0;
+        let mut lead_pawns_cnt = 0;
+        let mut file = 0;
+
+        // Tables are stored with White as the stronger side and symmetric
+        // tables only for White to move, anything else is color flipped
+        let black_symmetric = !pos.white_to_move && info.key == info.key2;
+        let black_stronger = pos.material_key != info.key;
+        let flip = black_symmetric || black_stronger;
+        let flip_color = if flip { 8 } else { 0 };
+        let flip_squares = if flip { 56 } else { 0 };
+        let stm = (flip ^ !pos.white_to_move) as usize;
+
+        let mut lead_color = 0;
+        if info.has_pawns {
+            let pc = self.pairs(0, 0).pieces[0] ^ flip_color;
+            lead_color = pc & 8;
+            for &(sq, piece) in &pos.pieces {
+                if piece == 1 | lead_color {
+                    squares[size] = sq ^ flip_squares;
+                    size += 1;
+                }
+            }
+            lead_pawns_cnt = size;
+
+            let mut lead = 0;
+            for i in 1..lead_pawns_cnt {
+                if e.map_pawns[squares[i] as usize] > e.map_pawns[squares[lead] as usize] {
+                    lead = i;
+                }
+            }
+            squares.swap(0, lead);
+
+            let f = squares[0] % 8;
+            file = f.min(7 - f) as usize;
+        }
+
+        for &(sq, piece) in &pos.pieces {
+            if info.has_pawns && piece == 1 | lead_color {
+                continue;
+            }
+            squares[size] = sq ^ flip_squares;
+            pieces[size] = piece ^ flip_color;
+            size += 1;
+        }
+
+        let d = self.pairs(stm, file);
+
+        // Reorder the pieces to follow the sequence stored in the table
+        for i in lead_pawns_cnt..size.saturating_sub(1) {
+            for j in i + 1..size {
+                if d.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        // The leading piece always ends up on files a-d
+        if squares[0] % 8 > 3 {
+            for sq in squares.iter_mut().take(size) {
+                *sq ^= 7;
+            }
+        }
+
+        let mut idx: u64;
+        if info.has_pawns {
+            idx = e.lead_pawn_idx[lead_pawns_cnt][squares[0] as usize];
+            squares[1..lead_pawns_cnt].sort_by_key(|&sq| e.map_pawns[sq as usize]);
+            for (i, &sq) in squares.iter().enumerate().take(lead_pawns_cnt).skip(1) {
+                idx += e.binomial[i][e.map_pawns[sq as usize] as usize];
+            }
+        } else {
+            // Without pawns the leading piece is also mapped below rank 5
+            // and below the a1-h8 diagonal
+            if squares[0] / 8 > 3 {
+                for sq in squares.iter_mut().take(size) {
+                    *sq ^= 56;
+                }
+            }
+
+            for i in 0..d.group_len[0] {
+                let off = off_a1h8(squares[i]);
+                if off == 0 {
+                    continue;
+                }
+                if off > 0 {
+                    for sq in squares.iter_mut().take(size).skip(i) {
+                        *sq = ((*sq >> 3) | (*sq << 3)) & 63;
+                    }
+                }
+                break;
+            }
+
+            let s = [squares[0] as u64, squares[1] as u64, squares[2] as u64];
+            let rank = |sq: u64| sq / 8;
+
+            idx = if info.has_unique_pieces {
+                let adjust1 = (s[1] > s[0]) as u64;
+                let adjust2 = (s[2] > s[0]) as u64 + (s[2] > s[1]) as u64;
+
+                if off_a1h8(squares[0]) != 0 {
+                    (e.map_a1d1d4[s[0] as usize] * 63 + (s[1] - adjust1)) * 62 + s[2] - adjust2
+                } else if off_a1h8(squares[1]) != 0 {
+                    (6 * 63 + rank(s[0]) * 28 + e.map_b1h1h7[s[1] as usize]) * 62 + s[2] - adjust2
+                } else if off_a1h8(squares[2]) != 0 {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + rank(s[0]) * 7 * 28
+                        + (rank(s[1]) - adjust1) * 28
+                        + e.map_b1h1h7[s[2] as usize]
+                } else {
+                    6 * 63 * 62
+                        + 4 * 28 * 62
+                        + 4 * 7 * 28
+                        + rank(s[0]) * 7 * 6
+                        + (rank(s[1]) - adjust1) * 6
+                        + (rank(s[2]) - adjust2)
+                }
+            } else {
+                e.map_kk[e.map_a1d1d4[s[0] as usize] as usize][s[1] as usize]
+            };
+        }
+
+        // Remaining groups are encoded as ascending square combinations,
+        // skipping the squares already taken by previous groups
+        idx *= d.group_idx[0];
+        let mut group_start = d.group_len[0];
+        let mut remaining_pawns = info.has_pawns && info.pawn_count[1] > 0;
+        let mut next = 1;
+
+        while d.group_len[next] != 0 {
+            let len = d.group_len[next];
+            squares[group_start..group_start + len].sort();
+
+            let mut n = 0;
+            for i in 0..len {
+                let sq = squares[group_start + i];
+                let adjust = squares[..group_start].iter().filter(|&&s| sq > s).count();
+                let pos = sq as usize - adjust - if remaining_pawns { 8 } else { 0 };
+                n += e.binomial[i + 1][pos];
+            }
+
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            group_start += len;
+            next += 1;
+        }
+
+        (stm, file, idx)
+    }
+}
+
+/// Writes tables in the on-disk format from a value per position, so tests
+/// can build small tablebases of their own. Values are stored as fixed-length
+/// codes without symbol pairing, which `decompress` reads like any other
+/// canonical Huffman code.
+#[cfg(test)]
+pub(super) mod writer {
+    use super::*;
+
+    const LOG_BLOCK_SIZE: u8 = 10;
+    const LOG_SPAN: u8 = 10;
+
+    /// Builds the table named `name` (e.g. `KRvK`) of up to three pieces.
+    /// `value` gets whether White is to move and the pieces as `(square,
+    /// code)` pairs, and returns the WDL value or the DTZ in plies, `None`
+    /// for an illegal position. DTZ tables store White to move only.
+    pub fn write(
+        name: &str,
+        tb_type: TbType,
+        value: impl Fn(bool, &[(u8, u8)]) -> Option<i32>,
+    ) -> Vec<u8> {
+        let info = TableInfo::from_name(name).unwrap();
+        let (white, black) = name.split_once('v').unwrap();
+        let mut codes: Vec<u8> = white
+            .chars()
+            .map(|c| PIECE_LETTERS.find(c).unwrap() as u8 + 1)
+            .chain(
+                black
+                    .chars()
+                    .map(|c| PIECE_LETTERS.find(c).unwrap() as u8 + 9),
+            )
+            .collect();
+        // A pawn leads; only tables where just White has pawns are supported
+        codes.sort_by_key(|&code| code != 1);
+        assert!(codes.len() <= 3 && !codes.contains(&9));
+
+        let header = header(&info, tb_type, &codes);
+        let mut placeholder = header.clone();
+        for _ in 0..sub_tables(&info, tb_type) {
+            placeholder.extend([FLAG_SINGLE_VALUE, 0]);
+        }
+        let table = Table::from_bytes(finish(placeholder), tb_type, &info).unwrap();
+
+        let files = if info.has_pawns { 4 } else { 1 };
+        let sides = table.sides();
+        let mut values: Vec<Vec<Vec<u16>>> = (0..sides)
+            .map(|side| {
+                (0..files)
+                    .map(|file| vec![u16::MAX; table.items[side][file].size() as usize])
+                    .collect()
+            })
+            .collect();
+
+        let stms: &[bool] = match tb_type {
+            TbType::Wdl => &[true, false],
+            TbType::Dtz => &[true],
+        };
+        // Every index is reached with the leading piece in the a1-d1-d4
+        // triangle, or the leading pawn on files a-d
+        let leading: Vec<u8> = if info.has_pawns {
+            (8..56).filter(|sq| sq % 8 < 4).collect()
+        } else {
+            (0..64)
+                .filter(|sq| sq % 8 < 4 && sq / 8 <= sq % 8)
+                .collect()
+        };
+        let squares = |code: u8| if code == 1 { 8..56 } else { 0..64 };
+        for &white_to_move in stms {
+            for &s0 in &leading {
+                for s1 in squares(codes[1]).filter(|&s| s != s0) {
+                    for s2 in squares(codes[2]).filter(|&s| s != s0 && s != s1) {
+                        let pieces = [(s0, codes[0]), (s1, codes[1]), (s2, codes[2])];
+                        let pos = TbPosition {
+                            pieces: pieces.to_vec(),
+                            material_key: info.key,
+                            white_to_move,
+                        };
+                        let (stm, file, idx) = table.encode(&pos);
+                        let slot = &mut values[stm % sides][file][idx as usize];
+                        if *slot == u16::MAX {
+                            *slot = match (tb_type, value(white_to_move, &pieces)) {
+                                (_, None) => 0,
+                                (TbType::Wdl, Some(wdl)) => (wdl + 2) as u16,
+                                (TbType::Dtz, Some(dtz)) => dtz.saturating_sub(1) as u16,
+                            };
+                        }
+                    }
+                }
+            }
+        }
+
+        let flags = match tb_type {
+            TbType::Wdl => 0,
+            TbType::Dtz => FLAG_WIN_PLIES | FLAG_LOSS_PLIES,
+        };
+        let mut bytes = header;
+        let mut sparse = Vec::new();
+        let mut lengths = Vec::new();
+        let mut data = Vec::new();
+        for file in 0..files {
+            for side in values.iter_mut() {
+                let side_values = &mut side[file];
+                for v in side_values.iter_mut() {
+                    if *v == u16::MAX {
+                        *v = 0;
+                    }
+                }
+                let compressed = compress(side_values, flags);
+                bytes.extend(compressed.sizes);
+                sparse.push(compressed.sparse);
+                lengths.push(compressed.lengths);
+                data.push(compressed.data);
+            }
+        }
+        if tb_type == TbType::Dtz {
+            bytes.resize(bytes.len() + (bytes.len() & 1), 0);
+        }
+        bytes.extend(sparse.concat());
+        bytes.extend(lengths.concat());
+        for block in data {
+            bytes.resize(bytes.len().div_ceil(64) * 64, 0);
+            bytes.extend(block);
+        }
+        finish(bytes)
+    }
+
+    impl PairsData {
+        fn size(&self) -> u64 {
+            let groups = self.group_len.iter().position(|&l| l == 0).unwrap();
+            self.group_idx[groups]
+        }
+    }
+
+    fn sub_tables(info: &TableInfo, tb_type: TbType) -> usize {
+        let files = if info.has_pawns { 4 } else { 1 };
+        let split = tb_type == TbType::Wdl && info.key != info.key2;
+        files * if split { 2 } else { 1 }
+    }
+
+    /// Magic, flags and the piece order of every file, padded to even length.
+    fn header(info: &TableInfo, tb_type: TbType, codes: &[u8]) -> Vec<u8> {
+        let mut bytes = match tb_type {
+            TbType::Wdl => WDL_MAGIC.to_vec(),
+            TbType::Dtz => DTZ_MAGIC.to_vec(),
+        };
+        let split = tb_type == TbType::Wdl && info.key != info.key2;
+        bytes.push(split as u8 | (info.has_pawns as u8) << 1);
+        for _ in 0..if info.has_pawns { 4 } else { 1 } {
+            bytes.push(0);
+            bytes.extend(codes.iter().map(|&code| code | code << 4));
+        }
+        bytes.resize(bytes.len() + (bytes.len() & 1), 0);
+        bytes
+    }
+
+    /// Pads to the length `Table::load` expects.
+    fn finish(mut bytes: Vec<u8>) -> Vec<u8> {
+        while bytes.len() % 64 != 16 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    struct Compressed {
+        sizes: Vec<u8>,
+        sparse: Vec<u8>,
+        lengths: Vec<u8>,
+        data: Vec<u8>,
+    }
+
+    fn compress(values: &[u16], flags: u8) -> Compressed {
+        let mut symbols = values.to_vec();
+        symbols.sort();
+        symbols.dedup();
+        if symbols.len() == 1 {
+            return Compressed {
+                sizes: vec![flags | FLAG_SINGLE_VALUE, symbols[0] as u8],
+                sparse: Vec::new(),
+                lengths: Vec::new(),
+                data: Vec::new(),
+            };
+        }
+
+        let bits = (usize::BITS - (symbols.len() - 1).leading_zeros()) as usize;
+        let block_size = 1usize << LOG_BLOCK_SIZE;
+        let per_block = block_size * 8 / bits;
+        let span = 1usize << LOG_SPAN;
+        let num_blocks = values.len().div_ceil(per_block);
+
+        let mut sizes = vec![flags, LOG_BLOCK_SIZE, LOG_SPAN, 0];
+        sizes.extend((num_blocks as u32).to_le_bytes());
+        sizes.extend([bits as u8, bits as u8, 0, 0]);
+        sizes.extend((symbols.len() as u16).to_le_bytes());
+        for &symbol in &symbols {
+            sizes.extend([symbol as u8, (symbol >> 8) as u8 | 0xF0, 0xFF]);
+        }
+        sizes.resize(sizes.len() + (symbols.len() & 1), 0);
+
+        let mut sparse = Vec::new();
+        for k in 0..values.len().div_ceil(span) {
+            let i = k * span + span / 2;
+            let block = (i / per_block).min(num_blocks - 1);
+            sparse.extend((block as u32).to_le_bytes());
+            sparse.extend(((i - block * per_block) as u16).to_le_bytes());
+        }
+
+        let mut lengths = Vec::new();
+        let mut data = Vec::new();
+        for chunk in values.chunks(per_block) {
+            lengths.extend(((chunk.len() - 1) as u16).to_le_bytes());
+            let mut block = vec![0u8; block_size];
+            for (i, v) in chunk.iter().enumerate() {
+                let symbol = symbols.binary_search(v).unwrap();
+                for bit in 0..bits {
+                    if symbol >> (bits - 1 - bit) & 1 != 0 {
+                        let pos = i * bits + bit;
+                        block[pos / 8] |= 0x80 >> (pos % 8);
+                    }
+                }
+            }
+            data.extend(block);
+        }
+
+        Compressed {
+            sizes,
+            sparse,
+            lengths,
+            data,
+        }
+    }
+}
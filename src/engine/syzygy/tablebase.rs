@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use lazy_static::lazy_static;
+
+use crate::chess::square::Square;
+use crate::chess::table::{Board, Color, Type};
+use crate::engine::syzygy::table::{
+    self, TB_PIECES, Table, TableInfo, TableProbe, TbPosition, TbType, material_key,
+};
+
+#[cfg(windows)]
+const PATH_SEPARATOR: char = ';';
+#[cfg(not(windows))]
+const PATH_SEPARATOR: char = ':';
+
+/// One material combination found on disk. The files are only read the
+/// first time a position with this material is probed.
+struct TableEntry {
+    info: TableInfo,
+    wdl_path: PathBuf,
+    dtz_path: Option<PathBuf>,
+    wdl: OnceLock<Option<Table>>,
+    dtz: OnceLock<Option<Table>>,
+}
+
+#[derive(Default)]
+struct Tablebases {
+    entries: HashMap<u64, Arc<TableEntry>>,
+    max_pieces: usize,
+}
+
+lazy_static! {
+    static ref TABLEBASES: RwLock<Tablebases> = RwLock::new(Tablebases::default());
+}
+
+static PROBE_LIMIT: AtomicUsize = AtomicUsize::new(TB_PIECES);
+
+/// Registers every table found in `paths` (separated like the `PATH`
+/// variable), replacing the previous set. Returns the number of WDL tables.
+pub fn init(paths: &str) -> usize {
+    table::init();
+
+    let dirs: Vec<PathBuf> = paths
+        .split(PATH_SEPARATOR)
+        .map(str::trim)
+        .filter(|p| !p.is_empty() && *p != "<empty>")
+        .map(PathBuf::from)
+        .collect();
+
+    let mut tablebases = Tablebases::default();
+    let mut count = 0;
+
+    for dir in &dirs {
+        let Ok(read_dir) = std::fs::read_dir(dir) else {
+            continue;
+        };
+
+        for file in read_dir.flatten() {
+            let wdl_path = file.path();
+            if wdl_path.extension().is_none_or(|ext| ext != "rtbw") {
+                continue;
+            }
+            let Some(name) = wdl_path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            let Some(info) = TableInfo::from_name(name) else {
+                continue;
+            };
+            if tablebases.entries.contains_key(&info.key) {
+                continue;
+            }
+
+            let dtz_file = format!("{}.rtbz", name);
+            let dtz_path = dirs.iter().map(|d| d.join(&dtz_file)).find(|p| p.is_file());
+
+            tablebases.max_pieces = tablebases.max_pieces.max(info.piece_count);
+            let entry = Arc::new(TableEntry {
+                info: info.clone(),
+                wdl_path,
+                dtz_path,
+                wdl: OnceLock::new(),
+                dtz: OnceLock::new(),
+            });
+            tablebases.entries.insert(info.key, entry.clone());
+            tablebases.entries.insert(info.key2, entry);
+            count += 1;
+        }
+    }
+
+    *TABLEBASES.write().unwrap() = tablebases;
+    count
+}
+
+pub fn set_probe_limit(limit: usize) {
+    PROBE_LIMIT.store(limit.min(TB_PIECES), Ordering::Relaxed);
+}
+
+/// Largest piece count that is both available on disk and allowed by the probe limit.
+pub fn max_pieces() -> usize {
+    let available = TABLEBASES.read().unwrap().max_pieces;
+    available.min(PROBE_LIMIT.load(Ordering::Relaxed))
+}
+
+/// Tables hold neither castling rights nor more pieces than were found on disk.
+pub fn can_probe(b: &Board, max_pieces: usize) -> bool {
+    b.get_castling_index() == 0 && b.get_occupied_pos().count_ones() as usize <= max_pieces
+}
+
+fn tb_position(b: &Board) -> TbPosition {
+    let mut counts = [[0usize; 6]; 2];
+    let mut pieces = Vec::with_capacity(TB_PIECES);
+    let mut occupied = b.get_occupied_pos().get_value();
+
+    while occupied != 0 {
+        let sq = occupied.trailing_zeros() as u8;
+        occupied &= occupied - 1;
+        if let Some((color, piece_type)) = b.get_piece_info_from_sq(Square::from_index(sq)) {
+            let color_idx = color.match_color(0, 1);
+            counts[color_idx][piece_type.id() as usize] += 1;
+            pieces.push((sq, piece_type.id() + 1 + 8 * color_idx as u8));
+        }
+    }
+
+    TbPosition {
+        pieces,
+        material_key: material_key(&counts),
+        white_to_move: b.get_side() == Color::White,
+    }
+}
+
+/// Raw table lookup. `None` when the table is missing or unreadable.
+pub fn probe_table(b: &Board, tb_type: TbType, wdl: i32) -> Option<TableProbe> {
+    if b.get_occupied_pos().count_ones() == 2 {
+        return Some(TableProbe::Value(0));
+    }
+    if b.get_pieces(Color::White, Type::King).count_ones() != 1
+        || b.get_pieces(Color::Black, Type::King).count_ones() != 1
+    {
+        return None;
+    }
+
+    let pos = tb_position(b);
+    let entry = TABLEBASES
+        .read()
+        .unwrap()
+        .entries
+        .get(&pos.material_key)
+        .cloned()?;
+
+    let table = match tb_type {
+        TbType::Wdl => entry
+            .wdl
+            .get_or_init(|| Table::load(&entry.wdl_path, TbType::Wdl, &entry.info)),
+        TbType::Dtz => entry.dtz.get_or_init(|| {
+            entry
+                .dtz_path
+                .as_ref()
+                .and_then(|path| Table::load(path, TbType::Dtz, &entry.info))
+        }),
+    };
+
+    table.as_ref().map(|t| t.probe(&pos, wdl))
+}
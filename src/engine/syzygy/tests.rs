@@ -0,0 +1,415 @@
+//! Probes tables written by `table::writer` into a temporary directory:
+//! KQvK and KRvK solved by retrograde analysis, KPvK from the KPK bitbase
+//! and the drawn KBvK and KNvK needed after underpromotions.
+
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use crate::chess::moves_gen::lookup;
+use crate::chess::square::Square;
+use crate::chess::table::{Board, Color};
+use crate::engine::evaluate::kpk::{self, KpkResult};
+use crate::engine::find_best_move::{Engine, SearchLimits};
+use crate::engine::syzygy::probe::{Wdl, filter_root_moves, probe_dtz, probe_wdl};
+use crate::engine::syzygy::table::{TB_PIECES, TbType, writer};
+use crate::engine::syzygy::tablebase;
+
+const UNKNOWN: i16 = i16::MAX;
+const ILLEGAL: i16 = -1;
+const DRAW: i16 = -2;
+
+/// Distance to mate in plies of every king and piece versus king position,
+/// indexed by white king, piece and black king square.
+struct Solution {
+    white_to_move: Vec<i16>,
+    black_to_move: Vec<i16>,
+}
+
+fn index(white_king: u8, piece: u8, black_king: u8) -> usize {
+    (white_king as usize) << 12 | (piece as usize) << 6 | black_king as usize
+}
+
+fn squares(mut bb: u64) -> impl Iterator<Item = u8> {
+    std::iter::from_fn(move || {
+        let sq = (bb != 0).then(|| bb.trailing_zeros() as u8);
+        bb &= bb.wrapping_sub(1);
+        sq
+    })
+}
+
+fn king(sq: u8) -> u64 {
+    lookup::king(Square::from_index(sq)).into()
+}
+
+/// Black king moves that neither capture nor walk into check.
+fn black_moves(attacks: fn(Square, u64) -> u64, wk: u8, piece: u8, bk: u8) -> Vec<u8> {
+    squares(king(bk) & !king(wk) & !(1 << piece))
+        .filter(|&to| {
+            let occupied = 1u64 << wk | 1 << piece | 1 << to;
+            attacks(Square::from_index(piece), occupied) & 1 << to == 0
+        })
+        .collect()
+}
+
+/// Retrograde analysis: mates first, then every position one ply further
+/// away, until no more positions are reached.
+fn solve(attacks: fn(Square, u64) -> u64) -> Solution {
+    let size = 64 * 64 * 64;
+    let mut white_to_move = vec![ILLEGAL; size];
+    let mut black_to_move = vec![ILLEGAL; size];
+    let mut moves_left = vec![0u8; size];
+    let mut frontier = Vec::new();
+
+    for wk in 0..64 {
+        for piece in (0..64).filter(|&sq| sq != wk) {
+            for bk in (0..64).filter(|&sq| sq != wk && sq != piece) {
+                if king(wk) & 1 << bk != 0 {
+                    continue;
+                }
+                let i = index(wk, piece, bk);
+                let occupied = 1u64 << wk | 1 << piece | 1 << bk;
+                let check = attacks(Square::from_index(piece), occupied) & 1 << bk != 0;
+                if !check {
+                    white_to_move[i] = UNKNOWN;
+                }
+
+                let can_capture = king(bk) & 1 << piece != 0 && king(wk) & 1 << piece == 0;
+                let moves = black_moves(attacks, wk, piece, bk).len();
+                black_to_move[i] = if can_capture || moves == 0 && !check {
+                    DRAW
+                } else if moves == 0 {
+                    frontier.push(i);
+                    0
+                } else {
+                    moves_left[i] = moves as u8;
+                    UNKNOWN
+                };
+            }
+        }
+    }
+
+    let mut ply = 0;
+    while !frontier.is_empty() {
+        let mut white_frontier = Vec::new();
+        for &i in &frontier {
+            let (wk, piece, bk) = ((i >> 12) as u8, (i >> 6 & 63) as u8, (i & 63) as u8);
+            let occupied = 1u64 << wk | 1 << piece | 1 << bk;
+            let king_from = king(wk) & !king(bk) & !(1 << piece);
+            let piece_from = attacks(Square::from_index(piece), occupied) & !occupied;
+            let predecessors = squares(king_from)
+                .map(|from| index(from, piece, bk))
+                .chain(squares(piece_from).map(|from| index(wk, from, bk)));
+            for p in predecessors {
+                if white_to_move[p] == UNKNOWN {
+                    white_to_move[p] = ply + 1;
+                    white_frontier.push(p);
+                }
+            }
+        }
+
+        frontier.clear();
+        for &i in &white_frontier {
+            let (wk, piece, bk) = ((i >> 12) as u8, (i >> 6 & 63) as u8, (i & 63) as u8);
+            for from in squares(king(bk) & !king(wk) & !(1 << piece)) {
+                let p = index(wk, piece, from);
+                if black_to_move[p] == UNKNOWN {
+                    moves_left[p] -= 1;
+                    if moves_left[p] == 0 {
+                        black_to_move[p] = ply + 2;
+                        frontier.push(p);
+                    }
+                }
+            }
+        }
+        ply += 2;
+    }
+
+    Solution {
+        white_to_move,
+        black_to_move,
+    }
+}
+
+struct Tablebases {
+    kqk: Solution,
+    krk: Solution,
+}
+
+fn fen(white_to_move: bool, pieces: &[(u8, u8)]) -> String {
+    let mut board = [None; 64];
+    for &(sq, code) in pieces {
+        board[sq as usize] = Some(b" PNBRQK  pnbrqk"[code as usize] as char);
+    }
+    let ranks: Vec<String> = board
+        .chunks(8)
+        .rev()
+        .map(|rank| {
+            let mut text = String::new();
+            let mut empty = 0;
+            for square in rank {
+                match square {
+                    Some(c) => {
+                        if empty > 0 {
+                            text.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        text.push(*c);
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                text.push_str(&empty.to_string());
+            }
+            text
+        })
+        .collect();
+    let side = if white_to_move { "w" } else { "b" };
+    format!("{} {} - - 0 1", ranks.join("/"), side)
+}
+
+/// Looks up a KXvK position given as `(square, code)` pairs in `solution`.
+fn solved(solution: &Solution, white_to_move: bool, pieces: &[(u8, u8)]) -> i16 {
+    let find = |code: u8| pieces.iter().find(|p| p.1 == code).unwrap().0;
+    let piece = pieces.iter().find(|p| p.1 != 6 && p.1 != 14).unwrap().0;
+    let i = index(find(6), piece, find(14));
+    if white_to_move {
+        solution.white_to_move[i]
+    } else {
+        solution.black_to_move[i]
+    }
+}
+
+fn wdl_of(solution: &Solution, white_to_move: bool, pieces: &[(u8, u8)]) -> Option<i32> {
+    match solved(solution, white_to_move, pieces) {
+        ILLEGAL => None,
+        DRAW => Some(0),
+        _ if white_to_move => Some(2),
+        _ => Some(-2),
+    }
+}
+
+fn dtz_of(solution: &Solution, white_to_move: bool, pieces: &[(u8, u8)]) -> Option<i32> {
+    match solved(solution, white_to_move, pieces) {
+        ILLEGAL | DRAW => None,
+        plies => Some(plies as i32),
+    }
+}
+
+/// Writes the tables once, into a directory whose name has a run of spaces
+/// like the paths `SyzygyPath` has to keep intact.
+fn tablebases() -> &'static Tablebases {
+    static TABLEBASES: OnceLock<Tablebases> = OnceLock::new();
+    TABLEBASES.get_or_init(|| {
+        let tables = Tablebases {
+            kqk: solve(|square, occupied| lookup::queen(square, occupied.into()).into()),
+            krk: solve(|square, occupied| lookup::rook(square, occupied.into()).into()),
+        };
+
+        let dir: PathBuf =
+            std::env::temp_dir().join(format!("chessengine  syzygy {}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, tb_type: TbType, bytes: Vec<u8>| {
+            let extension = if tb_type == TbType::Wdl {
+                "rtbw"
+            } else {
+                "rtbz"
+            };
+            std::fs::write(dir.join(format!("{}.{}", name, extension)), bytes).unwrap();
+        };
+
+        for (name, solution) in [("KQvK", &tables.kqk), ("KRvK", &tables.krk)] {
+            let wdl = writer::write(name, TbType::Wdl, |w, p| wdl_of(solution, w, p));
+            write(name, TbType::Wdl, wdl);
+            let dtz = writer::write(name, TbType::Dtz, |w, p| dtz_of(solution, w, p));
+            write(name, TbType::Dtz, dtz);
+        }
+        let kpk = writer::write("KPvK", TbType::Wdl, |white_to_move, pieces| {
+            let board = Board::new_from_fen(&fen(white_to_move, pieces)).ok()?;
+            match kpk::probe(&board)? {
+                KpkResult::Draw => Some(0),
+                KpkResult::Win(_) if white_to_move => Some(2),
+                KpkResult::Win(_) => Some(-2),
+            }
+        });
+        write("KPvK", TbType::Wdl, kpk);
+        for name in ["KBvK", "KNvK"] {
+            write(
+                name,
+                TbType::Wdl,
+                writer::write(name, TbType::Wdl, |_, _| Some(0)),
+            );
+        }
+
+        assert_eq!(tablebase::init(dir.to_str().unwrap()), 5);
+        // Tables are read on first use, so load each before removing the files
+        for fen in [
+            "k7/8/2K5/8/8/8/8/1Q6 w - - 0 1",
+            "k7/8/2K5/8/8/8/8/1R6 w - - 0 1",
+            "k7/8/2K5/8/8/8/8/1B6 w - - 0 1",
+            "k7/8/2K5/8/8/8/8/1N6 w - - 0 1",
+            "k7/8/2K5/8/8/8/1P6/8 w - - 0 1",
+        ] {
+            let mut b = board(fen);
+            probe_wdl(&mut b).unwrap();
+            probe_dtz(&mut b);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+        tables
+    })
+}
+
+/// Held by tests that change or depend on the global probe limit.
+fn probe_limit_lock() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn board(fen: &str) -> Board {
+    Board::new_from_fen(fen).unwrap()
+}
+
+/// King, piece and king on a spread of distinct squares, legal or not.
+fn sample(code: u8) -> impl Iterator<Item = (bool, [(u8, u8); 3])> {
+    (0..64u8).step_by(21).flat_map(move |wk| {
+        (0..64u8)
+            .step_by(13)
+            .filter(move |&piece| piece != wk)
+            .flat_map(move |piece| {
+                (0..64u8)
+                    .filter(move |&bk| bk != wk && bk != piece)
+                    .flat_map(move |bk| {
+                        [true, false]
+                            .into_iter()
+                            .map(move |w| (w, [(wk, 6), (piece, code), (bk, 14)]))
+                    })
+            })
+    })
+}
+
+#[test]
+fn solutions_have_the_known_longest_mates() {
+    let tables = tablebases();
+    let longest = |s: &Solution| {
+        s.white_to_move
+            .iter()
+            .filter(|&&v| v != UNKNOWN)
+            .max()
+            .copied()
+    };
+    // Mate in 10 with the queen, in 16 with the rook
+    assert_eq!(longest(&tables.kqk), Some(19));
+    assert_eq!(longest(&tables.krk), Some(31));
+    assert!(!tables.kqk.white_to_move.contains(&UNKNOWN));
+    assert!(!tables.krk.black_to_move.contains(&UNKNOWN));
+}
+
+#[test]
+fn wdl_and_dtz_match_the_solutions() {
+    let tables = tablebases();
+    for (code, solution) in [(5, &tables.kqk), (4, &tables.krk)] {
+        for (white_to_move, pieces) in sample(code) {
+            let Ok(mut b) = Board::new_from_fen(&fen(white_to_move, &pieces)) else {
+                continue;
+            };
+            let expected = match solved(solution, white_to_move, &pieces) {
+                DRAW => (Wdl::Draw, 0),
+                // Syzygy reports a side that is already mated at -1
+                0 => (Wdl::Loss, -1),
+                plies if white_to_move => (Wdl::Win, plies as i32),
+                plies => (Wdl::Loss, -plies as i32),
+            };
+            let probed = (probe_wdl(&mut b).unwrap(), probe_dtz(&mut b).unwrap());
+            assert_eq!(probed, expected, "{}", b.get_fen());
+        }
+    }
+}
+
+#[test]
+fn known_kqk_and_krk_positions() {
+    tablebases();
+    let probe = |fen: &str| {
+        let mut b = board(fen);
+        (probe_wdl(&mut b).unwrap(), probe_dtz(&mut b).unwrap())
+    };
+    // Qb7 mates
+    assert_eq!(probe("k7/8/2K5/8/8/8/8/1Q6 w - - 0 1"), (Wdl::Win, 1));
+    assert_eq!(probe("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1"), (Wdl::Loss, -1));
+    assert_eq!(probe("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), (Wdl::Draw, 0));
+    // The undefended rook falls
+    assert_eq!(probe("8/8/8/8/8/3k4/3R4/7K b - - 0 1"), (Wdl::Draw, 0));
+    // Colours swapped: Black is the stronger side
+    assert_eq!(probe("1q6/8/8/8/8/8/2k5/K7 b - - 0 1"), (Wdl::Win, 1));
+    assert_eq!(probe("1q6/8/8/8/8/8/2k5/K7 w - - 0 1").0, Wdl::Loss);
+}
+
+#[test]
+fn kpk_matches_the_bitbase() {
+    tablebases();
+    for (white_to_move, pieces) in sample(1).filter(|(_, p)| (8..56).contains(&p[1].0)) {
+        let Ok(mut b) = Board::new_from_fen(&fen(white_to_move, &pieces)) else {
+            continue;
+        };
+        let expected = match kpk::probe(&b).unwrap() {
+            KpkResult::Draw => Wdl::Draw,
+            KpkResult::Win(_) if white_to_move => Wdl::Win,
+            KpkResult::Win(_) => Wdl::Loss,
+        };
+        assert_eq!(probe_wdl(&mut b), Some(expected), "{}", b.get_fen());
+    }
+}
+
+#[test]
+fn known_kpk_positions() {
+    tablebases();
+    // The defending king in front of a rook pawn holds the draw
+    let mut b = board("k7/8/8/8/8/8/P7/K7 w - - 0 1");
+    assert_eq!(probe_wdl(&mut b), Some(Wdl::Draw));
+    assert_eq!(probe_dtz(&mut b), Some(0));
+
+    // Promoting zeroes the counter right away, underpromotions are probed too
+    let mut b = board("8/4P3/8/8/8/8/k7/4K3 w - - 0 1");
+    assert_eq!(probe_wdl(&mut b), Some(Wdl::Win));
+    assert_eq!(probe_dtz(&mut b), Some(1));
+
+    let mut b = board("4k3/8/8/8/8/8/4p3/K7 b - - 0 1");
+    assert_eq!(probe_dtz(&mut b), Some(1));
+    let mut b = board("4k3/8/8/8/8/8/4p3/K7 w - - 0 1");
+    assert_eq!(probe_wdl(&mut b), Some(Wdl::Loss));
+}
+
+#[test]
+fn root_moves_keep_the_fastest_win() {
+    tablebases();
+    let _lock = probe_limit_lock();
+    let mut b = board("k7/8/2K5/8/8/8/8/1Q6 w - - 0 1");
+    let mut moves = b.legal_moves();
+    assert!(filter_root_moves(&mut b, &mut moves));
+    assert!(!moves.is_empty());
+    for mv in moves {
+        let mut after = b;
+        after.make_move_with_undo(&mv);
+        assert!(after.legal_moves().is_empty() && after.is_king_in_check(Color::Black));
+    }
+}
+
+#[test]
+fn search_probes_after_captures() {
+    tablebases();
+    let _lock = probe_limit_lock();
+    // Qxc7 wins the knight but stalemates, which quiescence can not see
+    let b = board("k7/2n5/8/8/8/8/8/2Q1K3 w - - 0 1");
+    let limits = SearchLimits {
+        depth: Some(1),
+        ..Default::default()
+    };
+    let capture = |engine: &mut Engine| engine.search(&b, &[], &limits).best_move.to_string();
+
+    tablebase::set_probe_limit(0);
+    let without_tables = capture(&mut Engine::with_hash(1));
+    tablebase::set_probe_limit(TB_PIECES);
+    let with_tables = capture(&mut Engine::with_hash(1));
+
+    assert_eq!(without_tables, "c1c7");
+    assert_ne!(with_tables, "c1c7");
+}
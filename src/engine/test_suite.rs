@@ -0,0 +1,177 @@
+use std::fs;
+use std::time::Instant;
+
+use crate::chess::epd::{EpdRecord, parse_epd_line};
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::engine::find_best_move::Engine;
+
+pub const DEFAULT_TIME_MS: u64 = 1000;
+
+/// Per-position search budget. With a time limit the search still stops at
+/// `depth`; without one it always completes `depth` plies.
+pub struct SuiteLimits {
+    pub depth: u8,
+    pub time_ms: Option<u64>,
+}
+
+enum Outcome {
+    Solved,
+    Failed,
+    Unscored,
+}
+
+/// A position is solved when the engine move is one of the `bm` moves and
+/// none of the `am` moves.
+fn score_position(record: &EpdRecord, best: Moves) -> Result<Outcome, String> {
+    let parse_list = |opcode: &str| -> Result<Option<Vec<Moves>>, String> {
+        record
+            .operands(opcode)
+            .map(|list| list.iter().map(|san| record.board.parse_san(san)).collect())
+            .transpose()
+    };
+    let best_moves = parse_list("bm")?;
+    let avoid_moves = parse_list("am")?;
+
+    if best_moves.is_none() && avoid_moves.is_none() {
+        return Ok(Outcome::Unscored);
+    }
+
+    let bm_ok = best_moves.is_none_or(|list| list.contains(&best));
+    let am_ok = avoid_moves.is_none_or(|list| !list.contains(&best));
+    if bm_ok && am_ok {
+        Ok(Outcome::Solved)
+    } else {
+        Ok(Outcome::Failed)
+    }
+}
+
+pub fn start_epd(path: &str, limits: SuiteLimits) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", path, e);
+            return;
+        }
+    };
+
+    let mut engine = Engine::new();
+    let mut solved = 0;
+    let mut scored = 0;
+    let mut errors = 0;
+    let suite_start = Instant::now();
+
+    for (line_number, line) in text.lines().enumerate() {
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let record = match parse_epd_line(line) {
+            Ok(record) => record,
+            Err(e) => {
+                eprintln!("line {}: {}", line_number + 1, e);
+                errors += 1;
+                continue;
+            }
+        };
+        let id = record
+            .operand("id")
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("line {}", line_number + 1));
+
+        engine.clear();
+        let start = Instant::now();
+        let best = engine.find_best_move(&record.board, limits.depth, &[], limits.time_ms);
+        let elapsed = start.elapsed().as_millis();
+
+        let found = if best == Moves::NULL {
+            "none".to_string()
+        } else {
+            record.board.move_to_san(&best)
+        };
+
+        let expected = ["bm", "am"]
+            .iter()
+            .filter_map(|op| {
+                record
+                    .operands(op)
+                    .map(|list| format!("{} {}", op, list.join(" ")))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let verdict = match score_position(&record, best) {
+            Ok(Outcome::Solved) => {
+                solved += 1;
+                scored += 1;
+                "ok"
+            }
+            Ok(Outcome::Failed) => {
+                scored += 1;
+                "FAIL"
+            }
+            Ok(Outcome::Unscored) => "-",
+            Err(e) => {
+                eprintln!("{}: {}", id, e);
+                errors += 1;
+                "error"
+            }
+        };
+
+        print!(
+            "{:<16} {:<5} found {:<8} {:<20} {:>6} ms",
+            id, verdict, found, expected, elapsed
+        );
+        if let Some(comment) = record.operand("c0") {
+            print!("  {}", comment);
+        }
+        println!();
+    }
+
+    let total = suite_start.elapsed().as_millis();
+    println!();
+    println!("Solved: {} / {}", solved, scored);
+    if errors > 0 {
+        println!("Errors: {}", errors);
+    }
+    println!("Total time: {} ms", total);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chess::notation::parse_move;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+
+    fn score(line: &str, best: &str) -> Result<Outcome, String> {
+        let record = parse_epd_line(line).unwrap();
+        let best = parse_move(&record.board, best).unwrap();
+        score_position(&record, best)
+    }
+
+    #[test]
+    fn scores_best_and_avoid_moves() {
+        let bm = format!("{} bm e4 d4;", START);
+        assert!(matches!(score(&bm, "d2d4"), Ok(Outcome::Solved)));
+        assert!(matches!(score(&bm, "g1f3"), Ok(Outcome::Failed)));
+
+        let am = format!("{} am f3;", START);
+        assert!(matches!(score(&am, "e2e4"), Ok(Outcome::Solved)));
+        assert!(matches!(score(&am, "f2f3"), Ok(Outcome::Failed)));
+
+        let both = format!("{} bm e4 d4; am d4;", START);
+        assert!(matches!(score(&both, "d2d4"), Ok(Outcome::Failed)));
+    }
+
+    #[test]
+    fn needs_a_bm_or_am_operation() {
+        let line = format!("{} id \"start\";", START);
+        assert!(matches!(score(&line, "e2e4"), Ok(Outcome::Unscored)));
+    }
+
+    #[test]
+    fn reports_an_illegal_expected_move() {
+        let line = format!("{} bm Ke2;", START);
+        assert!(score(&line, "e2e4").is_err());
+    }
+}
@@ -46,12 +46,13 @@ pub struct TT {
 impl TT {
     pub fn new(size_mb: usize) -> Self {
         let entry_size = std::mem::size_of::<TTEntry>();
-        let num_entries = (size_mb * 1024 * 1024) / entry_size;
+        // Indexing takes the hash modulo the size, which must not be zero
+        let num_entries = ((size_mb * 1024 * 1024) / entry_size).max(1);
 
         let empty_entry = TTEntry {
             hash: 0,
             score: 0,
-            best_move: Moves::new(0, 0, 0, 0, false),
+            best_move: Moves::NULL,
             depth: 0,
             bound: BoundType::Exact,
             age: 0,
@@ -64,6 +65,7 @@ impl TT {
         }
     }
 
+    #[allow(dead_code)]
     pub fn new_search(&mut self) {
         self.age = self.age.wrapping_add(1)
     }
@@ -96,4 +98,3 @@ impl TT {
         }
     }
 }
-
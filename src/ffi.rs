@@ -0,0 +1,370 @@
+//! C interface, enabled with the `ffi` feature. `include/chessengine.h`
+//! is generated from this file by cbindgen, and `tests/ffi.rs` fails when
+//! the committed header no longer matches.
+//!
+//! Boards and engines are opaque heap objects owned by the caller, who must
+//! release them with `chess_board_free` and `chess_engine_free`. Moves cross
+//! the interface in their packed `u32` encoding, where 0 never is a legal
+//! move. Functions returning text follow `snprintf`: they write at most
+//! `size - 1` bytes plus a terminating NUL and return the full length.
+//!
+//! A panic must not unwind into C, so every entry point that runs engine
+//! code catches it and returns its error value instead.
+
+use std::ffi::{CStr, c_char};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::Moves;
+use crate::chess::notation::parse_move;
+use crate::chess::table::{Board, GameStatus};
+use crate::engine::find_best_move::{Engine, MAX_DEPTH, SearchLimits};
+
+/// A position together with the hashes of every position reached so far,
+/// the current one included, for repetition detection.
+pub struct ChessBoard {
+    board: Board,
+    history: Vec<u64>,
+}
+
+impl ChessBoard {
+    fn new(board: Board) -> Self {
+        ChessBoard {
+            history: vec![board.get_hash()],
+            board,
+        }
+    }
+}
+
+/// An engine and its transposition table.
+pub struct ChessEngine {
+    engine: Engine,
+}
+
+/// A limit of 0 does not restrict the search. At least one must be set.
+#[repr(C)]
+pub struct ChessSearchLimits {
+    /// Deepest iteration to run, capped at 64.
+    pub depth: u32,
+    /// Time budget in milliseconds.
+    pub time_ms: u64,
+    /// Node budget.
+    pub nodes: u64,
+}
+
+/// Outcome of `chess_engine_search`.
+#[repr(C)]
+pub struct ChessSearchResult {
+    /// The best move in its packed encoding.
+    pub best_move: u32,
+    /// Centipawns from the side to move's point of view.
+    pub score: i32,
+    /// Nodes visited.
+    pub nodes: u64,
+    /// Wall-clock time of the search.
+    pub time_ms: u64,
+}
+
+/// Returned on success.
+pub const CHESS_OK: i32 = 0;
+/// Returned on failure.
+pub const CHESS_ERROR: i32 = -1;
+
+/// # Safety
+///
+/// `buffer` must be null or valid for `size` bytes.
+unsafe fn write_string(text: &str, buffer: *mut c_char, size: usize) -> usize {
+    if !buffer.is_null() && size > 0 {
+        let len = text.len().min(size - 1);
+        unsafe {
+            ptr::copy_nonoverlapping(text.as_ptr(), buffer.cast::<u8>(), len);
+            *buffer.add(len) = 0;
+        }
+    }
+    text.len()
+}
+
+/// Runs `f`, turning a panic into `error`.
+fn guard<T>(error: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(error)
+}
+
+/// # Safety
+///
+/// `text` must be null or a NUL-terminated string.
+unsafe fn read_string<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(text) }.to_str().ok()
+}
+
+/// The initial position.
+#[unsafe(no_mangle)]
+pub extern "C" fn chess_board_new() -> *mut ChessBoard {
+    Box::into_raw(Box::new(ChessBoard::new(Board::new())))
+}
+
+/// Parses a FEN string. Returns null when it is invalid, including positions
+/// without exactly one king per side or with the side not to move in check.
+///
+/// # Safety
+///
+/// `fen` must be null or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_from_fen(fen: *const c_char) -> *mut ChessBoard {
+    let Some(fen) = (unsafe { read_string(fen) }) else {
+        return ptr::null_mut();
+    };
+    guard(ptr::null_mut(), || match Board::new_from_fen(fen) {
+        Ok(board) => Box::into_raw(Box::new(ChessBoard::new(board))),
+        Err(_) => ptr::null_mut(),
+    })
+}
+
+/// # Safety
+///
+/// `board` must be null or a board from this library that was not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_free(board: *mut ChessBoard) {
+    if !board.is_null() {
+        drop(unsafe { Box::from_raw(board) });
+    }
+}
+
+/// Writes the FEN of the position.
+///
+/// # Safety
+///
+/// `board` must be a live board and `buffer` null or valid for `size` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_fen(
+    board: *const ChessBoard,
+    buffer: *mut c_char,
+    size: usize,
+) -> usize {
+    let Some(board) = (unsafe { board.as_ref() }) else {
+        return 0;
+    };
+    unsafe { write_string(&board.board.get_fen(), buffer, size) }
+}
+
+/// 0 when White is to move, 1 when Black is.
+///
+/// # Safety
+///
+/// `board` must be a live board.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_side_to_move(board: *const ChessBoard) -> i32 {
+    match unsafe { board.as_ref() } {
+        Some(board) => board.board.get_side().match_color(0, 1),
+        None => 0,
+    }
+}
+
+/// Writes up to `capacity` legal moves into `moves` and returns how many
+/// there are in total.
+///
+/// # Safety
+///
+/// `board` must be a live board and `moves` null or valid for `capacity`
+/// elements.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_legal_moves(
+    board: *const ChessBoard,
+    moves: *mut u32,
+    capacity: usize,
+) -> usize {
+    let Some(board) = (unsafe { board.as_ref() }) else {
+        return 0;
+    };
+    let legal = guard(Vec::new(), || board.board.legal_moves());
+    if !moves.is_null() {
+        for (i, &mv) in legal.iter().take(capacity).enumerate() {
+            unsafe { *moves.add(i) = mv.into() };
+        }
+    }
+    legal.len()
+}
+
+/// Resolves a move in coordinate notation (`e2e4`, `e7e8q`) or SAN (`Nf3`).
+/// Returns 0 when it is not a legal move in the position.
+///
+/// # Safety
+///
+/// `board` must be a live board and `text` null or a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_parse_move(
+    board: *const ChessBoard,
+    text: *const c_char,
+) -> u32 {
+    let (Some(board), Some(text)) = (unsafe { board.as_ref() }, unsafe { read_string(text) })
+    else {
+        return 0;
+    };
+    guard(0, || {
+        let legal = board.board.legal_moves();
+        let mv = parse_move(&board.board, text)
+            .filter(|mv| legal.contains(mv))
+            .or_else(|| board.board.parse_san(text).ok());
+        mv.map_or(0, u32::from)
+    })
+}
+
+/// Plays a legal move. Returns `CHESS_ERROR` and leaves the board unchanged
+/// when the move is not legal.
+///
+/// # Safety
+///
+/// `board` must be a live board.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_make_move(board: *mut ChessBoard, mv: u32) -> i32 {
+    let Some(board) = (unsafe { board.as_mut() }) else {
+        return CHESS_ERROR;
+    };
+    let mv: Moves = mv.into();
+    guard(CHESS_ERROR, || {
+        if !board.board.legal_moves().contains(&mv) {
+            return CHESS_ERROR;
+        }
+        board.board.make_move_with_undo(&mv);
+        board.history.push(board.board.get_hash());
+        CHESS_OK
+    })
+}
+
+/// 0 ongoing, 1 checkmate, 2 stalemate, 3 threefold repetition, 4 fifty-move
+/// rule, 5 insufficient material, `CHESS_ERROR` for a null board.
+///
+/// # Safety
+///
+/// `board` must be a live board.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_status(board: *const ChessBoard) -> i32 {
+    let Some(board) = (unsafe { board.as_ref() }) else {
+        return CHESS_ERROR;
+    };
+    guard(CHESS_ERROR, || {
+        match board.board.game_status(&board.history) {
+            GameStatus::Ongoing => 0,
+            GameStatus::Checkmate => 1,
+            GameStatus::Stalemate => 2,
+            GameStatus::ThreefoldRepetition => 3,
+            GameStatus::FiftyMove => 4,
+            GameStatus::InsufficientMaterial => 5,
+        }
+    })
+}
+
+/// Writes a move in coordinate notation.
+///
+/// # Safety
+///
+/// `buffer` must be null or valid for `size` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_move_to_uci(mv: u32, buffer: *mut c_char, size: usize) -> usize {
+    let mv: Moves = mv.into();
+    unsafe { write_string(&mv.to_string(), buffer, size) }
+}
+
+/// Writes a legal move of the position in SAN. Writes an empty string and
+/// returns 0 when the move is not legal.
+///
+/// # Safety
+///
+/// `board` must be a live board and `buffer` null or valid for `size` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_board_move_to_san(
+    board: *const ChessBoard,
+    mv: u32,
+    buffer: *mut c_char,
+    size: usize,
+) -> usize {
+    let san = match unsafe { board.as_ref() } {
+        Some(board) => guard(String::new(), || {
+            let mv: Moves = mv.into();
+            if board.board.legal_moves().contains(&mv) {
+                board.board.move_to_san(&mv)
+            } else {
+                String::new()
+            }
+        }),
+        None => String::new(),
+    };
+    unsafe { write_string(&san, buffer, size) }
+}
+
+/// An engine with a transposition table of `hash_mb` megabytes. Returns null
+/// when `hash_mb` is 0.
+#[unsafe(no_mangle)]
+pub extern "C" fn chess_engine_new(hash_mb: usize) -> *mut ChessEngine {
+    if hash_mb == 0 {
+        return ptr::null_mut();
+    }
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(ChessEngine {
+            engine: Engine::with_hash(hash_mb),
+        }))
+    })
+}
+
+/// # Safety
+///
+/// `engine` must be null or an engine from this library that was not freed
+/// yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_engine_free(engine: *mut ChessEngine) {
+    if !engine.is_null() {
+        drop(unsafe { Box::from_raw(engine) });
+    }
+}
+
+/// Searches the position within `limits`. Returns `CHESS_ERROR` when
+/// `limits` is null or sets no limit, as the search would not end, and when
+/// the position has no legal moves.
+///
+/// # Safety
+///
+/// `engine` and `board` must be live, `limits` null or valid, and `result`
+/// valid for writing.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chess_engine_search(
+    engine: *mut ChessEngine,
+    board: *const ChessBoard,
+    limits: *const ChessSearchLimits,
+    result: *mut ChessSearchResult,
+) -> i32 {
+    let (Some(engine), Some(board), Some(limits), Some(result)) = (unsafe {
+        (
+            engine.as_mut(),
+            board.as_ref(),
+            limits.as_ref(),
+            result.as_mut(),
+        )
+    }) else {
+        return CHESS_ERROR;
+    };
+    if limits.depth == 0 && limits.time_ms == 0 && limits.nodes == 0 {
+        return CHESS_ERROR;
+    }
+    let limits = SearchLimits {
+        depth: (limits.depth > 0).then(|| limits.depth.min(MAX_DEPTH as u32) as u8),
+        time_ms: (limits.time_ms > 0).then_some(limits.time_ms),
+        nodes: (limits.nodes > 0).then_some(limits.nodes),
+    };
+    guard(CHESS_ERROR, || {
+        if board.board.legal_moves().is_empty() {
+            return CHESS_ERROR;
+        }
+
+        let previous = &board.history[..board.history.len() - 1];
+        let search = engine.engine.search(&board.board, previous, &limits);
+        *result = ChessSearchResult {
+            best_move: search.best_move.into(),
+            score: search.score,
+            nodes: search.nodes,
+            time_ms: search.time_ms,
+        };
+        CHESS_OK
+    })
+}
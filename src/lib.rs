@@ -0,0 +1,69 @@
+//! A bitboard chess engine that can be embedded as a library.
+//!
+//! The items re-exported at the crate root form the public API:
+//! positions and legal moves ([`Board`], [`Moves`], `Board::make_move`),
+//! FEN and SAN conversion (`Board::new_from_fen`, `Board::get_fen`,
+//! `Board::move_to_san`, `Board::parse_san`) and the search ([`Engine`],
+//! [`SearchLimits`], [`SearchResult`]). The attack tables are generated at compile time and
+//! the remaining lookup tables are built on first use, so no initialization
+//! call is needed.
+//!
+//! ```
+//! use chessengine::{Board, Engine, SearchLimits};
+//!
+//! let board = Board::new_from_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1").unwrap();
+//! assert_eq!(board.legal_moves().len(), 20);
+//!
+//! let mut engine = Engine::with_hash(16);
+//! let limits = SearchLimits {
+//!     depth: Some(4),
+//!     ..SearchLimits::default()
+//! };
+//! let result = engine.search(&board, &[], &limits);
+//! assert_eq!(board.move_to_san(&result.best_move), "Rd8#");
+//! ```
+//!
+//! The command-line tools behind the `chessengine` binary are compiled with
+//! the default `cli` feature; turn off default features to leave them out.
+//!
+//! With the `ffi` feature, the [`ffi`] module exports the same functionality
+//! to C, declared in `include/chessengine.h`. Build the C library with
+//! `cargo rustc --lib --no-default-features --features ffi --crate-type
+//! staticlib` (or `cdylib`).
+
+#![warn(missing_docs)]
+
+mod bitboard;
+#[cfg(feature = "cli")]
+mod book;
+#[cfg(feature = "cli")]
+mod cecp;
+mod chess;
+#[cfg(feature = "cli")]
+mod cli;
+#[cfg(feature = "cli")]
+mod datagen;
+mod engine;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "cli")]
+mod pgn;
+#[cfg(feature = "cli")]
+mod play;
+#[cfg(feature = "cli")]
+mod tournament;
+#[cfg(feature = "cli")]
+mod uci;
+
+pub use bitboard::bitboard::Bitboard;
+pub use chess::moves_gen::moves_struct::{
+    FLAG_CAPTURE, FLAG_CASTLE, FLAG_EN_PASSANT, FLAG_NORMAL, Moves, PROMOTION_TYPES,
+};
+pub use chess::square::{File, Rank, Square};
+pub use chess::table::{Board, Color, GameStatus, Piece, START_FEN, Type};
+pub use engine::find_best_move::{Engine, MAX_DEPTH, SearchLimits, SearchResult};
+
+/// Entry point of the `chessengine` binary, not part of the library API.
+#[cfg(feature = "cli")]
+#[doc(hidden)]
+pub use cli::commands::run as run_cli;
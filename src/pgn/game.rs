@@ -0,0 +1,155 @@
+use std::str::FromStr;
+
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::chess::table::Board;
+
+/// Tags written first and in this order, as required by the PGN standard.
+pub const SEVEN_TAG_ROSTER: [&str; 7] =
+    ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl FromStr for GameResult {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1-0" => Ok(GameResult::WhiteWins),
+            "0-1" => Ok(GameResult::BlackWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            "*" => Ok(GameResult::Unknown),
+            _ => Err(format!("Invalid result: {}", s)),
+        }
+    }
+}
+
+impl GameResult {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        }
+    }
+
+    /// Score from White's point of view, `None` for unfinished games.
+    pub fn white_score(&self) -> Option<f32> {
+        match self {
+            GameResult::WhiteWins => Some(1.0),
+            GameResult::BlackWins => Some(0.0),
+            GameResult::Draw => Some(0.5),
+            GameResult::Unknown => None,
+        }
+    }
+}
+
+/// One move of a line together with its annotations. `variations` holds the
+/// alternatives to this move, each starting from the position before it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct MoveNode {
+    pub mv: Moves,
+    pub san: String,
+    pub nags: Vec<u8>,
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    pub variations: Vec<Vec<MoveNode>>,
+}
+
+impl MoveNode {
+    pub fn new(mv: Moves, san: String) -> Self {
+        MoveNode {
+            mv,
+            san,
+            nags: Vec::new(),
+            comment_before: None,
+            comment: None,
+            variations: Vec::new(),
+        }
+    }
+}
+
+/// A game record: the tag pairs in file order, the starting position and the
+/// main line with its nested variations.
+#[derive(Clone)]
+pub struct Game {
+    pub tags: Vec<(String, String)>,
+    pub start: Board,
+    pub comment: Option<String>,
+    pub moves: Vec<MoveNode>,
+    pub result: GameResult,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Game {
+    pub fn new() -> Self {
+        let mut game = Game {
+            tags: Vec::new(),
+            start: Board::new(),
+            comment: None,
+            moves: Vec::new(),
+            result: GameResult::Unknown,
+        };
+        for name in SEVEN_TAG_ROSTER {
+            game.set_tag(name, "?");
+        }
+        game.set_tag("Result", "*");
+        game
+    }
+
+    /// A game starting from a custom position, recorded with `SetUp`/`FEN` tags.
+    pub fn from_fen(fen: &str) -> Result<Self, String> {
+        let mut game = Game::new();
+        game.start = Board::new_from_fen(fen)?;
+        game.set_tag("SetUp", "1");
+        game.set_tag("FEN", fen);
+        Ok(game)
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(n, _)| n == name) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn set_result(&mut self, result: GameResult) {
+        self.result = result;
+        self.set_tag("Result", result.as_str());
+    }
+
+    /// Appends a move to the main line. The move must be legal in the final
+    /// position of the main line.
+    pub fn push(&mut self, mv: Moves) {
+        let board = self.end_position();
+        let san = board.move_to_san(&mv);
+        self.moves.push(MoveNode::new(mv, san));
+    }
+
+    /// The position after the last move of the main line.
+    pub fn end_position(&self) -> Board {
+        let mut board = self.start;
+        for node in &self.moves {
+            board.make_move_with_undo(&node.mv);
+        }
+        board
+    }
+}
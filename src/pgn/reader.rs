@@ -0,0 +1,307 @@
+use crate::chess::table::Board;
+use crate::pgn::game::{Game, GameResult, MoveNode};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    VariationStart,
+    VariationEnd,
+    Result(GameResult),
+    Move(String),
+}
+
+/// Splits PGN text into tokens. Move numbers and escape lines are dropped and
+/// move suffixes such as `!?` are turned into their NAG.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line_start = true;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if line_start => {
+                chars.by_ref().find(|&c| c == '\n');
+                continue;
+            }
+            '[' => {
+                let tag: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if let Some((name, value)) = tag.trim().split_once(char::is_whitespace) {
+                    let value = value.trim();
+                    let value = value.strip_prefix('"').unwrap_or(value);
+                    let value = value.strip_suffix('"').unwrap_or(value);
+                    tokens.push(Token::Tag(name.to_string(), value.replace("\\\"", "\"")));
+                }
+            }
+            '{' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                tokens.push(Token::Comment(
+                    comment.split_whitespace().collect::<Vec<_>>().join(" "),
+                ));
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+                line_start = true;
+                continue;
+            }
+            '(' => tokens.push(Token::VariationStart),
+            ')' => tokens.push(Token::VariationEnd),
+            c if c.is_whitespace() => {}
+            _ => {
+                let mut word = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "{}();[".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                push_word(&mut tokens, &word);
+            }
+        }
+        line_start = c == '\n';
+    }
+    tokens
+}
+
+fn push_word(tokens: &mut Vec<Token>, word: &str) {
+    if let Ok(result) = word.parse::<GameResult>() {
+        tokens.push(Token::Result(result));
+        return;
+    }
+    if let Some(nag) = word.strip_prefix('$') {
+        if let Ok(nag) = nag.parse() {
+            tokens.push(Token::Nag(nag));
+        }
+        return;
+    }
+
+    // "12." and "12..." as well as "12.e4", but not the zeros of "0-0"
+    let after_number = word.trim_start_matches(|c: char| c.is_ascii_digit());
+    let word = match after_number.strip_prefix('.') {
+        Some(rest) => rest.trim_start_matches('.'),
+        None => word,
+    };
+    if word.is_empty() {
+        return;
+    }
+
+    let san = word.trim_end_matches(['!', '?']);
+    tokens.push(Token::Move(san.to_string()));
+    let nag = match &word[san.len()..] {
+        "!" => 1,
+        "?" => 2,
+        "!!" => 3,
+        "??" => 4,
+        "!?" => 5,
+        "?!" => 6,
+        _ => return,
+    };
+    tokens.push(Token::Nag(nag));
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Reads moves from `board` until the end of the line: a closing
+    /// parenthesis for a variation, or a result or new tag for the main line.
+    /// Comments follow the move they annotate; one in front of the first
+    /// move goes to `lead`.
+    fn parse_line(
+        &mut self,
+        mut board: Board,
+        lead: &mut Option<String>,
+    ) -> Result<Vec<MoveNode>, String> {
+        let mut line: Vec<MoveNode> = Vec::new();
+        let mut before = board;
+
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Tag(..) | Token::Result(_) | Token::VariationEnd => break,
+                _ => {}
+            }
+
+            match self.next().unwrap() {
+                Token::Comment(text) => match line.last_mut() {
+                    Some(node) => append_comment(&mut node.comment, text),
+                    None => append_comment(lead, text),
+                },
+                Token::Nag(nag) => {
+                    if let Some(node) = line.last_mut() {
+                        node.nags.push(nag);
+                    }
+                }
+                Token::VariationStart => {
+                    if line.is_empty() {
+                        return Err("Variation before any move".to_string());
+                    }
+                    let mut variation_lead = None;
+                    let mut variation = self.parse_line(before, &mut variation_lead)?;
+                    if let Some(first) = variation.first_mut()
+                        && let Some(text) = variation_lead
+                    {
+                        append_comment(&mut first.comment_before, text);
+                    }
+                    if self.next() != Some(Token::VariationEnd) {
+                        return Err("Unterminated variation".to_string());
+                    }
+                    if !variation.is_empty() {
+                        line.last_mut().unwrap().variations.push(variation);
+                    }
+                }
+                Token::Move(san) => {
+                    let mv = board.parse_san(&san)?;
+                    line.push(MoveNode::new(mv, board.move_to_san(&mv)));
+                    before = board;
+                    board.make_move_with_undo(&mv);
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(line)
+    }
+}
+
+fn append_comment(target: &mut Option<String>, text: String) {
+    match target {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(&text);
+        }
+        None => *target = Some(text),
+    }
+}
+
+/// Parses every game of a PGN file. A game with a syntax error or an illegal
+/// move yields an error but does not stop the following games from loading.
+pub fn parse_games(text: &str) -> Vec<Result<Game, String>> {
+    let mut parser = Parser {
+        tokens: tokenize(text),
+        pos: 0,
+    };
+    let mut games = Vec::new();
+
+    while parser.peek().is_some() {
+        let start = parser.pos;
+        let game = parse_game_tokens(&mut parser);
+        if game.is_err() {
+            // Skip to the end of the broken game
+            parser.pos = parser.pos.max(start + 1);
+            while let Some(token) = parser.peek() {
+                match token {
+                    Token::Tag(..) => break,
+                    Token::Result(_) => {
+                        parser.next();
+                        break;
+                    }
+                    _ => parser.pos += 1,
+                }
+            }
+        }
+        games.push(game);
+    }
+
+    games
+}
+
+fn parse_game_tokens(parser: &mut Parser) -> Result<Game, String> {
+    let mut game = Game::new();
+    game.tags.clear();
+
+    while let Some(Token::Tag(name, value)) = parser.peek().cloned() {
+        parser.next();
+        game.set_tag(&name, &value);
+    }
+
+    if let Some(fen) = game.tag("FEN") {
+        game.start = Board::new_from_fen(fen)?;
+    }
+    if let Some(result) = game.tag("Result").and_then(|tag| tag.parse().ok()) {
+        game.result = result;
+    }
+
+    let mut lead = None;
+    game.moves = parser.parse_line(game.start, &mut lead)?;
+    game.comment = lead;
+
+    match parser.peek() {
+        Some(Token::Result(result)) => {
+            game.result = *result;
+            parser.next();
+        }
+        Some(Token::VariationEnd) => return Err("Unexpected ')'".to_string()),
+        _ => {}
+    }
+
+    Ok(game)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_zero_castling_comments_nags_and_variations() {
+        let text = r#"[Event "Test"]
+[White "A"]
+[Black "B"]
+
+{Opening} 1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. 0-0 $1 {Castles early} (4. c3 Nf6
+5. d4 exd4 6. cxd4 Bb4+ 7. Bd2 Bxd2+ 8. Nbxd2 d5 ; Open centre
+9. exd5 Nxd5 10. 0-0) 4... Nf6 5. d3 d6 6. Bg5 h6 7. Bh4 Qe7 8. Nc3 Bd7 9. Qd2
+0-0-0!? 1/2-1/2
+"#;
+        let games = parse_games(text);
+        assert_eq!(games.len(), 1);
+        let game = games[0].as_ref().unwrap();
+
+        assert_eq!(game.tag("White"), Some("A"));
+        assert_eq!(game.comment.as_deref(), Some("Opening"));
+        assert_eq!(game.result, GameResult::Draw);
+        assert_eq!(game.moves.len(), 18);
+
+        let castle = &game.moves[6];
+        assert_eq!(castle.san, "O-O");
+        assert_eq!(castle.nags, vec![1]);
+        assert_eq!(castle.comment.as_deref(), Some("Castles early"));
+        assert_eq!(castle.variations.len(), 1);
+
+        let variation = &castle.variations[0];
+        assert_eq!(variation.len(), 13);
+        assert_eq!(variation[9].comment.as_deref(), Some("Open centre"));
+        assert_eq!(variation[12].san, "O-O");
+
+        let long_castle = game.moves.last().unwrap();
+        assert_eq!(long_castle.san, "O-O-O");
+        assert_eq!(long_castle.nags, vec![5]);
+    }
+
+    #[test]
+    fn move_numbers_are_dropped() {
+        let tokens = tokenize("12. e4 12... e5 13.Nf3 0-0 0-0-0");
+        let moves: Vec<_> = tokens
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Move(san) => Some(san),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(moves, ["e4", "e5", "Nf3", "0-0", "0-0-0"]);
+    }
+}
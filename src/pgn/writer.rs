@@ -0,0 +1,166 @@
+use crate::chess::table::Color;
+use crate::pgn::game::{Game, MoveNode, SEVEN_TAG_ROSTER};
+
+const MAX_LINE_LENGTH: usize = 79;
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Appends the tokens of `line`, whose first move is played at `move_number`
+/// by `side`. A black move gets its own `N...` number at the start of a line
+/// and after comments or variations.
+fn write_line(tokens: &mut Vec<String>, line: &[MoveNode], mut move_number: u16, mut side: Color) {
+    let mut needs_number = true;
+
+    for node in line {
+        if let Some(comment) = &node.comment_before {
+            tokens.push(format!("{{{}}}", comment));
+            needs_number = true;
+        }
+
+        match side {
+            Color::White => tokens.push(format!("{}.", move_number)),
+            Color::Black if needs_number => tokens.push(format!("{}...", move_number)),
+            Color::Black => {}
+        }
+        needs_number = false;
+
+        tokens.push(node.san.clone());
+        for nag in &node.nags {
+            tokens.push(format!("${}", nag));
+        }
+        if let Some(comment) = &node.comment {
+            tokens.push(format!("{{{}}}", comment));
+            needs_number = true;
+        }
+
+        for variation in &node.variations {
+            tokens.push("(".to_string());
+            write_line(tokens, variation, move_number, side);
+            tokens.push(")".to_string());
+            needs_number = true;
+        }
+
+        if side == Color::Black {
+            move_number += 1;
+        }
+        side = side.opposite();
+    }
+}
+
+/// Formats a game as PGN: the seven tag roster, the remaining tags in order,
+/// then the movetext wrapped to 80 columns, followed by a blank line.
+pub fn write_game(game: &Game) -> String {
+    let mut out = String::new();
+
+    for name in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => game.result.as_str(),
+            _ => game.tag(name).unwrap_or("?"),
+        };
+        out.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+    }
+    for (name, value) in &game.tags {
+        if !SEVEN_TAG_ROSTER.contains(&name.as_str()) {
+            out.push_str(&format!("[{} \"{}\"]\n", name, escape(value)));
+        }
+    }
+    out.push('\n');
+
+    let mut tokens = Vec::new();
+    if let Some(comment) = &game.comment {
+        tokens.push(format!("{{{}}}", comment));
+    }
+    write_line(
+        &mut tokens,
+        &game.moves,
+        game.start.get_fullmove_number(),
+        game.start.get_side(),
+    );
+    tokens.push(game.result.as_str().to_string());
+
+    let mut line_length = 0;
+    for token in tokens {
+        let glue = !(line_length == 0 || token == ")" || out.ends_with('('));
+        if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            out.push('\n');
+            line_length = 0;
+        } else if glue {
+            out.push(' ');
+            line_length += 1;
+        }
+        line_length += token.len();
+        out.push_str(&token);
+    }
+    out.push_str("\n\n");
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pgn::game::GameResult;
+    use crate::pgn::reader::parse_games;
+
+    fn read_one(text: &str) -> Game {
+        let mut games = parse_games(text);
+        assert_eq!(games.len(), 1);
+        games.remove(0).unwrap()
+    }
+
+    fn assert_same_game(a: &Game, b: &Game) {
+        assert_eq!(a.tags, b.tags);
+        assert_eq!(a.start.get_fen(), b.start.get_fen());
+        assert_eq!(a.comment, b.comment);
+        assert_eq!(a.moves, b.moves);
+        assert_eq!(a.result, b.result);
+    }
+
+    #[test]
+    fn round_trips_variations_comments_and_nags() {
+        let text = r#"[Event "Club \"Open\""]
+[Site "?"]
+[Date "2024.01.01"]
+[Round "1"]
+[White "A"]
+[Black "B"]
+[Result "0-1"]
+[Annotator "C"]
+
+{Opening} 1. e4 e5 2. Nf3 $1 Nc6 (2... d6 {Philidor} 3. d4 (3. Bc4 Be7) 3...
+Nf6 $2) 3. Bb5 {Spanish} a6 $5 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3
+O-O 9. h3 Nb8 10. d4 Nbd7 {Breyer} 0-1
+"#;
+        let game = read_one(text);
+        assert_eq!(game.result, GameResult::BlackWins);
+        assert_eq!(game.tag("Event"), Some("Club \"Open\""));
+        assert_eq!(game.moves[2].nags, vec![1]);
+        assert_eq!(game.moves[3].variations[0][1].variations.len(), 1);
+
+        let written = write_game(&game);
+        assert!(written.contains("[Result \"0-1\"]"));
+        assert!(written.trim_end().ends_with("0-1"));
+        assert!(written.lines().all(|line| line.len() <= MAX_LINE_LENGTH));
+
+        let reread = read_one(&written);
+        assert_same_game(&game, &reread);
+        assert_eq!(write_game(&reread), written);
+    }
+
+    #[test]
+    fn round_trips_a_game_from_a_black_to_move_position() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap();
+        for text in ["e8d7", "e2e4", "d7e6"] {
+            let board = game.end_position();
+            game.push(crate::chess::notation::parse_move(&board, text).unwrap());
+        }
+        game.moves[0].comment = Some("Only move".to_string());
+        game.set_result(GameResult::Draw);
+
+        let written = write_game(&game);
+        assert!(written.contains("40... Kd7 {Only move} 41. e4 Ke6 1/2-1/2"));
+        assert_same_game(&game, &read_one(&written));
+    }
+}
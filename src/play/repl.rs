@@ -0,0 +1,457 @@
+use std::io::{self, BufRead, Write};
+
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::chess::notation::parse_move;
+use crate::chess::table::{Board, Color, GameStatus, START_FEN};
+use crate::engine::find_best_move::{Engine, MAX_DEPTH, SearchLimits, SearchResult};
+use crate::engine::search::MATE_SCORE;
+
+pub struct PlayConfig {
+    pub fen: String,
+    pub human: Color,
+    pub depth: u8,
+    pub time_ms: u64,
+    pub hash_mb: usize,
+}
+
+pub const PLAY_USAGE: &str =
+    "usage: play [--color white|black] [--depth N] [--time MS] [--hash MB] [--fen FEN]";
+
+const HELP: &str = "Commands:
+  <move>        play a move in SAN (Nf3, exd5, O-O) or coordinates (g1f3)
+  undo          take back your last move and the engine's reply
+  hint          suggest a move
+  go            let the engine play the side to move, then swap sides
+  flip          turn the board around
+  moves         list the legal moves
+  depth N       limit the engine to N plies (0 for no limit)
+  time MS       give the engine MS milliseconds per move (0 for no limit)
+  new [white|black]  start a new game, playing the given color
+  fen           print the position as FEN
+  help          show this list
+  quit          leave";
+
+fn parse_color(value: &str) -> Result<Color, String> {
+    match value {
+        "white" | "w" => Ok(Color::White),
+        "black" | "b" => Ok(Color::Black),
+        _ => Err(format!("Invalid color: {}", value)),
+    }
+}
+
+pub fn parse_play_args(args: &[String]) -> Result<PlayConfig, String> {
+    let mut config = PlayConfig {
+        fen: START_FEN.to_string(),
+        human: Color::White,
+        depth: MAX_DEPTH,
+        time_ms: 1000,
+        hash_mb: 64,
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let flag = arg.as_str();
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("{} expects a value\n{}", flag, PLAY_USAGE))?;
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        match flag {
+            "--color" => config.human = parse_color(value)?,
+            "--depth" => config.depth = value.parse().map_err(|_| invalid())?,
+            "--time" => config.time_ms = value.parse().map_err(|_| invalid())?,
+            "--hash" => config.hash_mb = value.parse().map_err(|_| invalid())?,
+            "--fen" => config.fen = value.clone(),
+            _ => return Err(format!("Unknown option {}\n{}", flag, PLAY_USAGE)),
+        }
+    }
+
+    Board::new_from_fen(&config.fen)?;
+    Ok(config)
+}
+
+/// Pawns, or `#N` / `#-N` for a forced mate in N moves.
+fn format_score(score: i32) -> String {
+    let plies = MATE_SCORE - score.abs();
+    if plies < MAX_DEPTH as i32 {
+        let moves = (plies + 1) / 2;
+        return if score > 0 {
+            format!("#{}", moves)
+        } else {
+            format!("#-{}", moves)
+        };
+    }
+    format!("{:+.2}", score as f64 / 100.0)
+}
+
+fn color_name(color: Color) -> &'static str {
+    color.match_color("White", "Black")
+}
+
+/// One human against the engine. Every position reached is kept so moves can
+/// be taken back.
+struct Session {
+    engine: Engine,
+    start: Board,
+    positions: Vec<Board>,
+    history: Vec<u64>,
+    human: Color,
+    flipped: bool,
+    limits: SearchLimits,
+}
+
+impl Session {
+    fn new(config: &PlayConfig) -> Result<Self, String> {
+        let board = Board::new_from_fen(&config.fen)?;
+        Ok(Session {
+            engine: Engine::with_hash(config.hash_mb),
+            start: board,
+            positions: vec![board],
+            history: vec![board.get_hash()],
+            human: config.human,
+            flipped: config.human == Color::Black,
+            limits: SearchLimits {
+                depth: Some(config.depth.clamp(1, MAX_DEPTH)),
+                time_ms: Some(config.time_ms).filter(|&ms| ms > 0),
+                nodes: None,
+            },
+        })
+    }
+
+    fn board(&self) -> &Board {
+        self.positions.last().unwrap_or(&self.start)
+    }
+
+    fn reset(&mut self, human: Color) {
+        self.positions = vec![self.start];
+        self.history = vec![self.start.get_hash()];
+        self.human = human;
+        self.flipped = human == Color::Black;
+        self.engine.clear();
+    }
+
+    fn status(&self) -> GameStatus {
+        self.board().game_status(&self.history)
+    }
+
+    fn show(&self) {
+        let board = self.board();
+        println!("\n{}\n", board.diagram(self.flipped));
+        match self.status() {
+            GameStatus::Ongoing => {
+                let check = if board.is_king_in_check(board.get_side()) {
+                    ", check"
+                } else {
+                    ""
+                };
+                println!("{} to move{}", color_name(board.get_side()), check);
+            }
+            GameStatus::Checkmate => println!(
+                "Checkmate, {} wins",
+                color_name(board.get_side().opposite())
+            ),
+            GameStatus::Stalemate => println!("Stalemate, draw"),
+            GameStatus::ThreefoldRepetition => println!("Draw by threefold repetition"),
+            GameStatus::FiftyMove => println!("Draw by the fifty-move rule"),
+            GameStatus::InsufficientMaterial => println!("Draw by insufficient material"),
+        }
+    }
+
+    /// Coordinate notation first, then SAN.
+    fn parse(&self, text: &str) -> Result<Moves, String> {
+        let board = self.board();
+        if let Some(mv) = parse_move(board, text)
+            && board.legal_moves().contains(&mv)
+        {
+            return Ok(mv);
+        }
+        board.parse_san(text)
+    }
+
+    fn play(&mut self, mv: Moves) {
+        let mut board = *self.board();
+        board.make_move_with_undo(&mv);
+        self.positions.push(board);
+        self.history.push(board.get_hash());
+    }
+
+    fn search(&mut self) -> SearchResult {
+        let board = *self.board();
+        let previous = &self.history[..self.history.len() - 1];
+        self.engine.search(&board, previous, &self.limits)
+    }
+
+    fn engine_move(&mut self) {
+        let board = *self.board();
+        let result = self.search();
+        println!(
+            "{} plays {} ({}, {} nodes, {:.2}s)",
+            color_name(board.get_side()),
+            board.move_to_san(&result.best_move),
+            format_score(result.score),
+            result.nodes,
+            result.time_ms as f64 / 1000.0
+        );
+        self.play(result.best_move);
+    }
+
+    /// Lets the engine answer as long as it is its turn and the game goes on.
+    fn reply(&mut self) {
+        if self.board().get_side() != self.human && self.status() == GameStatus::Ongoing {
+            self.engine_move();
+        }
+        self.show();
+    }
+
+    /// Takes back plies until it is the human's turn again, at least one.
+    fn undo(&mut self) -> Result<(), String> {
+        if self.positions.len() < 2 {
+            return Err("Nothing to take back".to_string());
+        }
+        loop {
+            self.positions.pop();
+            self.history.pop();
+            if self.board().get_side() == self.human || self.positions.len() < 2 {
+                return Ok(());
+            }
+        }
+    }
+
+    fn execute(&mut self, line: &str) -> Result<bool, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some(&command) = words.first() else {
+            return Ok(true);
+        };
+
+        match command {
+            "quit" | "exit" => return Ok(false),
+            "help" | "?" => println!("{}", HELP),
+            "flip" => {
+                self.flipped = !self.flipped;
+                self.show();
+            }
+            "fen" => println!("{}", self.board().get_fen()),
+            "moves" => {
+                let board = self.board();
+                let mut moves: Vec<String> = board
+                    .legal_moves()
+                    .iter()
+                    .map(|mv| board.move_to_san(mv))
+                    .collect();
+                moves.sort();
+                println!("{}", moves.join(" "));
+            }
+            "undo" | "takeback" => {
+                self.undo()?;
+                self.show();
+            }
+            "hint" => {
+                if self.status() != GameStatus::Ongoing {
+                    return Err("The game is over".to_string());
+                }
+                let board = *self.board();
+                let result = self.search();
+                println!(
+                    "Hint: {} ({})",
+                    board.move_to_san(&result.best_move),
+                    format_score(result.score)
+                );
+            }
+            "go" => {
+                if self.status() != GameStatus::Ongoing {
+                    return Err("The game is over".to_string());
+                }
+                self.human = self.board().get_side().opposite();
+                self.engine_move();
+                self.show();
+            }
+            "depth" | "time" => {
+                let value: u64 = words
+                    .get(1)
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| format!("usage: {} N", command))?;
+                let mut limits = self.limits;
+                if command == "depth" {
+                    limits.depth =
+                        Some(value.clamp(1, MAX_DEPTH as u64) as u8).filter(|_| value > 0);
+                } else {
+                    limits.time_ms = Some(value).filter(|&ms| ms > 0);
+                }
+                // Without either limit the engine would never answer
+                if limits.depth.is_none() && limits.time_ms.is_none() {
+                    return Err("The engine needs a depth or a time limit".to_string());
+                }
+                self.limits = limits;
+                println!(
+                    "Engine limits: depth {}, time {}",
+                    self.limits.depth.map_or("-".to_string(), |d| d.to_string()),
+                    self.limits
+                        .time_ms
+                        .map_or("-".to_string(), |t| format!("{} ms", t))
+                );
+            }
+            "new" => {
+                let human = match words.get(1) {
+                    Some(color) => parse_color(color)?,
+                    None => self.human,
+                };
+                self.reset(human);
+                self.reply();
+            }
+            _ => {
+                if self.status() != GameStatus::Ongoing {
+                    return Err("The game is over, use undo or new".to_string());
+                }
+                if self.board().get_side() != self.human {
+                    return Err("It is the engine's turn, use go".to_string());
+                }
+                let mv = self
+                    .parse(command)
+                    .map_err(|_| format!("Illegal move: {} (try moves or help)", command))?;
+                self.play(mv);
+                self.reply();
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Runs the read-eval-print loop on standard input until `quit` or end of
+/// input.
+pub fn run_play(config: &PlayConfig) -> Result<(), String> {
+    let mut session = Session::new(config)?;
+    println!(
+        "Playing {} against the engine. Type help for commands.",
+        color_name(session.human)
+    );
+    session.reply();
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!(
+            "{}> ",
+            color_name(session.board().get_side()).to_lowercase()
+        );
+        io::stdout().flush().map_err(|e| e.to_string())?;
+
+        let Some(line) = lines.next() else {
+            println!();
+            return Ok(());
+        };
+        let line = line.map_err(|e| e.to_string())?;
+        match session.execute(line.trim()) {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    fn session(fen: &str, human: Color) -> Session {
+        Session::new(&PlayConfig {
+            fen: fen.to_string(),
+            human,
+            depth: 1,
+            time_ms: 0,
+            hash_mb: 1,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_play_args() {
+        let config = parse_play_args(&[]).unwrap();
+        assert_eq!(config.fen, START_FEN);
+        assert_eq!(config.human, Color::White);
+        assert_eq!(config.depth, MAX_DEPTH);
+
+        let config = parse_play_args(&args("--color b --depth 6 --time 250 --hash 8")).unwrap();
+        assert_eq!(config.human, Color::Black);
+        assert_eq!(config.depth, 6);
+        assert_eq!(config.time_ms, 250);
+        assert_eq!(config.hash_mb, 8);
+
+        let mut fen = args("--fen");
+        fen.push("4k3/8/8/8/8/8/8/4K3 b - - 0 1".to_string());
+        assert_eq!(
+            parse_play_args(&fen).unwrap().fen,
+            "4k3/8/8/8/8/8/8/4K3 b - - 0 1"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_play_args() {
+        for text in [
+            "--depth",
+            "--depth x",
+            "--time -1",
+            "--color red",
+            "--level 3",
+            "--fen nonsense",
+        ] {
+            assert!(parse_play_args(&args(text)).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn formats_pawns_and_mates() {
+        assert_eq!(format_score(0), "+0.00");
+        assert_eq!(format_score(150), "+1.50");
+        assert_eq!(format_score(-35), "-0.35");
+        assert_eq!(format_score(MATE_SCORE - 1), "#1");
+        assert_eq!(format_score(MATE_SCORE - 3), "#2");
+        assert_eq!(format_score(-(MATE_SCORE - 2)), "#-1");
+        assert_eq!(format_score(MATE_SCORE - 200), "+198.00");
+    }
+
+    #[test]
+    fn undo_goes_back_to_the_humans_turn() {
+        let mut session = session(START_FEN, Color::White);
+        assert!(session.undo().is_err());
+
+        for text in ["e2e4", "e7e5", "g1f3"] {
+            let mv = session.parse(text).unwrap();
+            session.play(mv);
+        }
+        // The human's move has no reply yet, so one ply is taken back
+        session.undo().unwrap();
+        assert_eq!(session.positions.len(), 3);
+        assert_eq!(session.history.len(), 3);
+        assert_eq!(session.board().get_side(), Color::White);
+        // Then the engine's reply and the human's move before it
+        session.undo().unwrap();
+        assert_eq!(session.board().get_hash(), Board::new().get_hash());
+        assert!(session.undo().is_err());
+    }
+
+    #[test]
+    fn undo_stops_at_the_start_when_the_engine_moved_first() {
+        let mut session = session(START_FEN, Color::Black);
+        let mv = session.parse("e2e4").unwrap();
+        session.play(mv);
+        session.undo().unwrap();
+        assert_eq!(session.positions.len(), 1);
+        assert_eq!(session.history, vec![Board::new().get_hash()]);
+    }
+
+    #[test]
+    fn keeps_a_depth_or_time_limit() {
+        let mut session = session(START_FEN, Color::White);
+        assert!(session.execute("depth 0").is_err());
+        assert_eq!(session.limits.depth, Some(1));
+
+        session.execute("time 100").unwrap();
+        session.execute("depth 0").unwrap();
+        assert_eq!(session.limits.depth, None);
+        assert!(session.execute("time 0").is_err());
+        assert_eq!(session.limits.time_ms, Some(100));
+    }
+}
@@ -0,0 +1,3 @@
+pub mod player;
+pub mod runner;
+pub mod sprt;
@@ -0,0 +1,310 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use crate::chess::moves_gen::moves_struct::Moves;
+use crate::chess::notation::parse_move;
+use crate::chess::table::Board;
+use crate::engine::find_best_move::{DEFAULT_HASH_MB, Engine, MAX_DEPTH, allocate_time};
+use crate::engine::search::MATE_SCORE;
+
+/// The game so far, in the form both kinds of players need it.
+pub struct GamePosition<'a> {
+    pub start_fen: &'a str,
+    pub moves: &'a [Moves],
+    pub board: &'a Board,
+    /// Hashes of every position reached, the current one included.
+    pub history: &'a [u64],
+}
+
+/// Remaining time and increments in milliseconds, or a fixed depth.
+#[derive(Clone, Copy)]
+pub enum SearchLimits {
+    Clock {
+        wtime: u64,
+        btime: u64,
+        winc: u64,
+        binc: u64,
+    },
+    Depth(u8),
+}
+
+/// The move chosen and the score reported for it in centipawns from the
+/// mover's point of view, when the player gives one.
+pub struct PlayerMove {
+    pub mv: Moves,
+    pub score: Option<i32>,
+}
+
+pub trait Player {
+    fn name(&self) -> &str;
+    fn new_game(&mut self) -> Result<(), String>;
+    fn best_move(
+        &mut self,
+        position: &GamePosition,
+        limits: SearchLimits,
+    ) -> Result<PlayerMove, String>;
+}
+
+/// The engine of this build, searched in process.
+pub struct EnginePlayer {
+    name: String,
+    engine: Engine,
+    max_depth: u8,
+}
+
+impl EnginePlayer {
+    /// Accepts `internal`, optionally followed by `,hash=MB` and `,depth=N`.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let mut hash_mb = DEFAULT_HASH_MB;
+        let mut max_depth = MAX_DEPTH;
+
+        for option in spec.split(',').skip(1) {
+            let (key, value) = option
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid engine option: {}", option))?;
+            let invalid = || format!("Invalid value for {}: {}", key, value);
+            match key {
+                "hash" => hash_mb = value.parse().map_err(|_| invalid())?,
+                "depth" => max_depth = value.parse().map_err(|_| invalid())?,
+                _ => return Err(format!("Unknown engine option: {}", key)),
+            }
+        }
+
+        Ok(EnginePlayer {
+            name: spec.to_string(),
+            engine: Engine::with_hash(hash_mb),
+            max_depth,
+        })
+    }
+}
+
+impl Player for EnginePlayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) -> Result<(), String> {
+        self.engine.clear();
+        Ok(())
+    }
+
+    fn best_move(
+        &mut self,
+        position: &GamePosition,
+        limits: SearchLimits,
+    ) -> Result<PlayerMove, String> {
+        let (depth, time_limit) = match limits {
+            SearchLimits::Clock {
+                wtime,
+                btime,
+                winc,
+                binc,
+            } => {
+                let (time, inc) = position
+                    .board
+                    .get_side()
+                    .match_color((wtime, winc), (btime, binc));
+                (self.max_depth, Some(allocate_time(time, inc)))
+            }
+            SearchLimits::Depth(depth) => (depth.min(self.max_depth), None),
+        };
+
+        // The search adds the current position to the history itself
+        let previous = &position.history[..position.history.len() - 1];
+        let mv = self
+            .engine
+            .find_best_move(position.board, depth, previous, time_limit);
+        Ok(PlayerMove {
+            mv,
+            score: Some(self.engine.score),
+        })
+    }
+}
+
+/// An external engine speaking UCI on its standard input and output.
+pub struct UciPlayer {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl UciPlayer {
+    pub fn start(path: &str) -> Result<Self, String> {
+        let mut child = Command::new(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Cannot start {}: {}", path, e))?;
+
+        let stdin = child.stdin.take().ok_or("No stdin for engine")?;
+        let stdout = BufReader::new(child.stdout.take().ok_or("No stdout for engine")?);
+        let mut player = UciPlayer {
+            name: path.to_string(),
+            child,
+            stdin,
+            stdout,
+        };
+
+        player.send("uci")?;
+        loop {
+            let line = player.read_line()?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                player.name = name.trim().to_string();
+            }
+            if line.trim() == "uciok" {
+                break;
+            }
+        }
+        player.sync()?;
+
+        Ok(player)
+    }
+
+    fn send(&mut self, command: &str) -> Result<(), String> {
+        writeln!(self.stdin, "{}", command)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("{}: {}", self.name, e))
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        let mut line = String::new();
+        match self.stdout.read_line(&mut line) {
+            Ok(0) => Err(format!("{} exited", self.name)),
+            Ok(_) => Ok(line),
+            Err(e) => Err(format!("{}: {}", self.name, e)),
+        }
+    }
+
+    fn sync(&mut self) -> Result<(), String> {
+        self.send("isready")?;
+        while self.read_line()?.trim() != "readyok" {}
+        Ok(())
+    }
+}
+
+/// Reads the score of an `info` line: `score cp 35` or `score mate -3`.
+fn parse_info_score(line: &str) -> Option<i32> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let index = words.iter().position(|&w| w == "score")?;
+    let value: i32 = words.get(index + 2)?.parse().ok()?;
+    match *words.get(index + 1)? {
+        "cp" => Some(value),
+        "mate" if value > 0 => Some(MATE_SCORE - value),
+        "mate" => Some(-MATE_SCORE - value),
+        _ => None,
+    }
+}
+
+impl Player for UciPlayer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn new_game(&mut self) -> Result<(), String> {
+        self.send("ucinewgame")?;
+        self.sync()
+    }
+
+    fn best_move(
+        &mut self,
+        position: &GamePosition,
+        limits: SearchLimits,
+    ) -> Result<PlayerMove, String> {
+        let mut command = format!("position fen {}", position.start_fen);
+        if !position.moves.is_empty() {
+            command.push_str(" moves");
+            for mv in position.moves {
+                command.push(' ');
+                command.push_str(&mv.to_string());
+            }
+        }
+        self.send(&command)?;
+
+        let go = match limits {
+            SearchLimits::Clock {
+                wtime,
+                btime,
+                winc,
+                binc,
+            } => format!(
+                "go wtime {} btime {} winc {} binc {}",
+                wtime, btime, winc, binc
+            ),
+            SearchLimits::Depth(depth) => format!("go depth {}", depth),
+        };
+        self.send(&go)?;
+
+        let mut score = None;
+        loop {
+            let line = self.read_line()?;
+            if line.starts_with("info") {
+                score = parse_info_score(&line).or(score);
+            } else if let Some(rest) = line.strip_prefix("bestmove") {
+                let text = rest.split_whitespace().next().unwrap_or("");
+                let mv = parse_move(position.board, text)
+                    .ok_or_else(|| format!("{} sent an invalid move: {}", self.name, text))?;
+                return Ok(PlayerMove { mv, score });
+            }
+        }
+    }
+}
+
+impl Drop for UciPlayer {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.wait();
+    }
+}
+
+/// `internal[,hash=MB][,depth=N]` for this engine, anything else is the path
+/// of a UCI engine.
+pub fn create_player(spec: &str) -> Result<Box<dyn Player>, String> {
+    if spec == "internal" || spec.starts_with("internal,") {
+        Ok(Box::new(EnginePlayer::from_spec(spec)?))
+    } else {
+        Ok(Box::new(UciPlayer::start(spec)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn once_repeated_root_is_not_a_draw() {
+        let fen = "4k3/8/8/8/8/8/8/Q3K3 w - - 0 1";
+        let mut board = Board::new_from_fen(fen).unwrap();
+        let mut moves = Vec::new();
+        let mut history = vec![board.get_hash()];
+        for text in ["e1d1", "e8d8", "d1e1", "d8e8"] {
+            let mv = parse_move(&board, text).unwrap();
+            board.make_move_with_undo(&mv);
+            moves.push(mv);
+            history.push(board.get_hash());
+        }
+        assert_eq!(history.first(), history.last());
+
+        let position = GamePosition {
+            start_fen: fen,
+            moves: &moves,
+            board: &board,
+            history: &history,
+        };
+        let mut player = EnginePlayer::from_spec("internal,hash=1").unwrap();
+        let reply = player.best_move(&position, SearchLimits::Depth(4)).unwrap();
+        assert!(reply.score.unwrap() > 500, "{:?}", reply.score);
+    }
+
+    #[test]
+    fn mate_scores_match_the_search() {
+        assert_eq!(parse_info_score("info depth 5 score cp -35"), Some(-35));
+        assert_eq!(parse_info_score("info score mate 3"), Some(MATE_SCORE - 3));
+        assert_eq!(
+            parse_info_score("info score mate -2"),
+            Some(-MATE_SCORE + 2)
+        );
+        assert_eq!(parse_info_score("info depth 1 nodes 20"), None);
+    }
+}
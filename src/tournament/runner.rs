@@ -0,0 +1,566 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::time::Instant;
+
+use crate::chess::epd::parse_epd_line;
+use crate::chess::table::{Board, Color, GameStatus, START_FEN};
+use crate::pgn::game::{Game, GameResult};
+use crate::pgn::writer::write_game;
+use crate::tournament::player::{GamePosition, Player, SearchLimits, create_player};
+use crate::tournament::sprt::{MatchScore, Sprt, SprtResult};
+
+// Allowance for process and pipe overhead before a player loses on time
+const TIME_MARGIN_MS: u64 = 50;
+// Games still running after this many plies are adjudicated as draws
+const MAX_PLIES: usize = 600;
+// Draw adjudication only starts after this many plies
+const DRAW_MIN_PLY: usize = 80;
+
+#[derive(Clone, Copy)]
+pub enum TimeControl {
+    /// Base time and increment per move, in milliseconds.
+    Clock {
+        base: u64,
+        inc: u64,
+    },
+    Depth(u8),
+}
+
+/// Score based adjudication. A game is resigned once both players have
+/// agreed for `resign_moves` moves each that one side is at least
+/// `resign_score` ahead, and drawn once both report scores within
+/// `draw_score` of zero for `draw_moves` moves each. A score of 0 disables
+/// the rule.
+#[derive(Clone, Copy)]
+pub struct Adjudication {
+    pub resign_score: i32,
+    pub resign_moves: usize,
+    pub draw_score: i32,
+    pub draw_moves: usize,
+}
+
+pub struct MatchConfig {
+    pub engine1: String,
+    pub engine2: String,
+    pub openings: Vec<String>,
+    pub games: usize,
+    pub time_control: TimeControl,
+    pub adjudication: Adjudication,
+    pub pgn_path: Option<String>,
+    pub sprt: Option<Sprt>,
+}
+
+pub const MATCH_USAGE: &str = "usage: match [--engine1 SPEC] [--engine2 SPEC] [--openings FILE.epd] [--games N] [--tc SECONDS+INC | --depth N] [--resign CP] [--draw CP] [--pgn FILE] [--sprt ELO0 ELO1]
+SPEC is internal[,hash=MB][,depth=N] or the path of a UCI engine";
+
+fn next_value<'a>(
+    iter: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> Result<&'a str, String> {
+    iter.next()
+        .map(String::as_str)
+        .ok_or_else(|| format!("{} expects a value", flag))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+/// Parses `10+0.1` (seconds) into a clock in milliseconds.
+fn parse_time_control(value: &str) -> Result<TimeControl, String> {
+    let (base, inc) = value.split_once('+').unwrap_or((value, "0"));
+    let base: f64 = parse_number(base, "--tc")?;
+    let inc: f64 = parse_number(inc, "--tc")?;
+    Ok(TimeControl::Clock {
+        base: (base * 1000.0) as u64,
+        inc: (inc * 1000.0) as u64,
+    })
+}
+
+fn read_openings(path: &str) -> Result<Vec<String>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let openings: Vec<String> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|line| parse_epd_line(line).map(|record| record.fen))
+        .collect::<Result<_, _>>()?;
+
+    if openings.is_empty() {
+        return Err(format!("No positions in {}", path));
+    }
+    Ok(openings)
+}
+
+pub fn parse_match_args(args: &[String]) -> Result<MatchConfig, String> {
+    let mut config = MatchConfig {
+        engine1: "internal".to_string(),
+        engine2: "internal".to_string(),
+        openings: vec![START_FEN.to_string()],
+        games: 100,
+        time_control: TimeControl::Clock {
+            base: 10_000,
+            inc: 100,
+        },
+        adjudication: Adjudication {
+            resign_score: 1000,
+            resign_moves: 4,
+            draw_score: 10,
+            draw_moves: 8,
+        },
+        pgn_path: None,
+        sprt: None,
+    };
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let flag = arg.as_str();
+        match flag {
+            "--engine1" => config.engine1 = next_value(&mut iter, flag)?.to_string(),
+            "--engine2" => config.engine2 = next_value(&mut iter, flag)?.to_string(),
+            "--openings" => config.openings = read_openings(next_value(&mut iter, flag)?)?,
+            "--games" => config.games = parse_number(next_value(&mut iter, flag)?, flag)?,
+            "--tc" => config.time_control = parse_time_control(next_value(&mut iter, flag)?)?,
+            "--depth" => {
+                config.time_control =
+                    TimeControl::Depth(parse_number(next_value(&mut iter, flag)?, flag)?)
+            }
+            "--resign" => {
+                config.adjudication.resign_score = parse_number(next_value(&mut iter, flag)?, flag)?
+            }
+            "--draw" => {
+                config.adjudication.draw_score = parse_number(next_value(&mut iter, flag)?, flag)?
+            }
+            "--pgn" => config.pgn_path = Some(next_value(&mut iter, flag)?.to_string()),
+            "--sprt" => {
+                let elo0 = parse_number(next_value(&mut iter, flag)?, flag)?;
+                let elo1 = parse_number(next_value(&mut iter, flag)?, flag)?;
+                config.sprt = Some(Sprt {
+                    elo0,
+                    elo1,
+                    alpha: 0.05,
+                    beta: 0.05,
+                });
+            }
+            _ => return Err(format!("Unknown option {}\n{}", flag, MATCH_USAGE)),
+        }
+    }
+
+    Ok(config)
+}
+
+/// Tracks the scores reported by both players, from White's point of view.
+struct Adjudicator {
+    rules: Adjudication,
+    resign_count: usize,
+    draw_count: usize,
+    last_score: Option<i32>,
+}
+
+impl Adjudicator {
+    fn new(rules: Adjudication) -> Self {
+        Adjudicator {
+            rules,
+            resign_count: 0,
+            draw_count: 0,
+            last_score: None,
+        }
+    }
+
+    /// Records the score after a move and returns the adjudicated result, if any.
+    fn update(&mut self, white_score: Option<i32>, ply: usize) -> Option<GameResult> {
+        let Some(score) = white_score else {
+            self.resign_count = 0;
+            self.draw_count = 0;
+            self.last_score = None;
+            return None;
+        };
+
+        let resign = self.rules.resign_score;
+        let same_side = self.last_score.is_none_or(|last| (last > 0) == (score > 0));
+        if resign > 0 && score.abs() >= resign && same_side {
+            self.resign_count += 1;
+        } else {
+            self.resign_count = 0;
+        }
+
+        if self.rules.draw_score > 0 && ply >= DRAW_MIN_PLY && score.abs() <= self.rules.draw_score
+        {
+            self.draw_count += 1;
+        } else {
+            self.draw_count = 0;
+        }
+        self.last_score = Some(score);
+
+        if resign > 0 && self.resign_count >= 2 * self.rules.resign_moves {
+            return Some(if score > 0 {
+                GameResult::WhiteWins
+            } else {
+                GameResult::BlackWins
+            });
+        }
+        if self.rules.draw_score > 0 && self.draw_count >= 2 * self.rules.draw_moves {
+            return Some(GameResult::Draw);
+        }
+        None
+    }
+}
+
+fn win_for(color: Color) -> GameResult {
+    color.match_color(GameResult::WhiteWins, GameResult::BlackWins)
+}
+
+/// Plays one game and returns the record with its `Termination` tag set.
+fn play_game(
+    white: &mut dyn Player,
+    black: &mut dyn Player,
+    opening: &str,
+    config: &MatchConfig,
+) -> Result<Game, String> {
+    white.new_game()?;
+    black.new_game()?;
+
+    let mut board = Board::new_from_fen(opening)?;
+    let mut game = if opening == START_FEN {
+        Game::new()
+    } else {
+        Game::from_fen(opening)?
+    };
+    game.set_tag("White", white.name());
+    game.set_tag("Black", black.name());
+    if let TimeControl::Clock { base, inc } = config.time_control {
+        game.set_tag(
+            "TimeControl",
+            &format!("{}+{}", base as f64 / 1000.0, inc as f64 / 1000.0),
+        );
+    }
+
+    let mut moves = Vec::new();
+    let mut history = vec![board.get_hash()];
+    let mut clocks = match config.time_control {
+        TimeControl::Clock { base, .. } => [base, base],
+        TimeControl::Depth(_) => [0, 0],
+    };
+    let mut adjudicator = Adjudicator::new(config.adjudication);
+    let mut buffer = Vec::with_capacity(256);
+
+    let (result, termination) = loop {
+        let side = board.get_side();
+        match board.game_status(&history) {
+            GameStatus::Ongoing => {}
+            GameStatus::Checkmate => break (win_for(side.opposite()), "normal"),
+            _ => break (GameResult::Draw, "normal"),
+        }
+        if moves.len() >= MAX_PLIES {
+            break (GameResult::Draw, "adjudication");
+        }
+
+        let limits = match config.time_control {
+            TimeControl::Clock { inc, .. } => SearchLimits::Clock {
+                wtime: clocks[0],
+                btime: clocks[1],
+                winc: inc,
+                binc: inc,
+            },
+            TimeControl::Depth(depth) => SearchLimits::Depth(depth),
+        };
+        let position = GamePosition {
+            start_fen: opening,
+            moves: &moves,
+            board: &board,
+            history: &history,
+        };
+
+        let player: &mut dyn Player = side.match_color(&mut *white, &mut *black);
+        let start = Instant::now();
+        let reply = player.best_move(&position, limits);
+        let elapsed = start.elapsed().as_millis() as u64;
+
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(e) => {
+                eprintln!("{}", e);
+                break (win_for(side.opposite()), "rules infraction");
+            }
+        };
+        if !board.get_legal_moves(side, &mut buffer).contains(&reply.mv) {
+            eprintln!(
+                "{} played an illegal move: {}",
+                player.name(),
+                reply.mv.to_string()
+            );
+            break (win_for(side.opposite()), "rules infraction");
+        }
+
+        if let TimeControl::Clock { inc, .. } = config.time_control {
+            let clock = &mut clocks[side.match_color(0, 1)];
+            if elapsed > *clock + TIME_MARGIN_MS {
+                break (win_for(side.opposite()), "time forfeit");
+            }
+            *clock = clock.saturating_sub(elapsed) + inc;
+        }
+
+        game.push(reply.mv);
+        if let Some(node) = game.moves.last_mut() {
+            node.comment = Some(match reply.score {
+                Some(score) => format!(
+                    "{:+.2} {:.2}s",
+                    score as f64 / 100.0,
+                    elapsed as f64 / 1000.0
+                ),
+                None => format!("{:.2}s", elapsed as f64 / 1000.0),
+            });
+        }
+        board.make_move_with_undo(&reply.mv);
+        moves.push(reply.mv);
+        history.push(board.get_hash());
+
+        let white_score = reply.score.map(|s| side.match_color(s, -s));
+        if let Some(result) = adjudicator.update(white_score, moves.len()) {
+            break (result, "adjudication");
+        }
+    };
+
+    game.set_result(result);
+    game.set_tag("Termination", termination);
+    Ok(game)
+}
+
+fn append_pgn(path: &str, game: &Game) -> Result<(), String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(write_game(game).as_bytes()))
+        .map_err(|e| format!("Cannot write {}: {}", path, e))
+}
+
+/// Plays the match. Each opening is played twice with colors reversed, and
+/// the match stops early once the SPRT reaches a decision.
+pub fn run_match(config: &MatchConfig) -> Result<MatchScore, String> {
+    let mut engine1 = create_player(&config.engine1)?;
+    let mut engine2 = create_player(&config.engine2)?;
+    let name1 = engine1.name().to_string();
+    let name2 = engine2.name().to_string();
+    let mut score = MatchScore::default();
+
+    for round in 0..config.games {
+        let opening = &config.openings[(round / 2) % config.openings.len()];
+        let engine1_white = round % 2 == 0;
+
+        let mut game = if engine1_white {
+            play_game(engine1.as_mut(), engine2.as_mut(), opening, config)?
+        } else {
+            play_game(engine2.as_mut(), engine1.as_mut(), opening, config)?
+        };
+        game.set_tag("Event", "Engine match");
+        game.set_tag("Round", &(round + 1).to_string());
+
+        let white_points = game.result.white_score().unwrap_or(0.5);
+        let engine1_points = if engine1_white {
+            white_points
+        } else {
+            1.0 - white_points
+        };
+        if engine1_points == 1.0 {
+            score.wins += 1;
+        } else if engine1_points == 0.0 {
+            score.losses += 1;
+        } else {
+            score.draws += 1;
+        }
+
+        println!(
+            "Finished game {} ({} vs {}): {} {{{}}}",
+            round + 1,
+            game.tag("White").unwrap_or("?"),
+            game.tag("Black").unwrap_or("?"),
+            game.result.as_str(),
+            game.tag("Termination").unwrap_or("normal"),
+        );
+        println!(
+            "Score of {} vs {}: {} - {} - {} [{:.3}] {}",
+            name1,
+            name2,
+            score.wins,
+            score.losses,
+            score.draws,
+            score.score(),
+            score.games()
+        );
+        if let Some((elo, margin)) = score.elo() {
+            println!("Elo difference: {:.1} +/- {:.1}", elo, margin);
+        }
+
+        if let Some(path) = &config.pgn_path {
+            append_pgn(path, &game)?;
+        }
+
+        if let Some(sprt) = &config.sprt {
+            let (lower, upper) = sprt.bounds();
+            println!(
+                "SPRT: llr {:.2} ({:.2}, {:.2}) [{:.1}, {:.1}]",
+                sprt.llr(&score),
+                lower,
+                upper,
+                sprt.elo0,
+                sprt.elo1
+            );
+            match sprt.status(&score) {
+                SprtResult::AcceptH1 => {
+                    println!("SPRT: H1 accepted");
+                    break;
+                }
+                SprtResult::AcceptH0 => {
+                    println!("SPRT: H0 accepted");
+                    break;
+                }
+                SprtResult::Continue => {}
+            }
+        }
+    }
+
+    Ok(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(str::to_string).collect()
+    }
+
+    const RULES: Adjudication = Adjudication {
+        resign_score: 500,
+        resign_moves: 2,
+        draw_score: 10,
+        draw_moves: 2,
+    };
+
+    #[test]
+    fn resigns_after_both_players_agree() {
+        let mut adjudicator = Adjudicator::new(RULES);
+        for ply in 1..4 {
+            assert_eq!(adjudicator.update(Some(-600), ply), None);
+        }
+        assert_eq!(
+            adjudicator.update(Some(-700), 4),
+            Some(GameResult::BlackWins)
+        );
+    }
+
+    #[test]
+    fn resign_count_restarts_on_a_missing_or_small_score() {
+        let mut adjudicator = Adjudicator::new(RULES);
+        adjudicator.update(Some(600), 1);
+        adjudicator.update(Some(600), 2);
+        adjudicator.update(None, 3);
+        adjudicator.update(Some(600), 4);
+        adjudicator.update(Some(100), 5);
+        assert_eq!(adjudicator.resign_count, 0);
+
+        // A sign change starts a new count for the other side
+        adjudicator.update(Some(600), 6);
+        adjudicator.update(Some(-600), 7);
+        assert_eq!(adjudicator.resign_count, 0);
+        for ply in 8..11 {
+            assert_eq!(adjudicator.update(Some(-600), ply), None);
+        }
+        assert_eq!(
+            adjudicator.update(Some(-600), 11),
+            Some(GameResult::BlackWins)
+        );
+    }
+
+    #[test]
+    fn draws_only_after_the_minimum_ply() {
+        let mut adjudicator = Adjudicator::new(RULES);
+        for ply in DRAW_MIN_PLY - 4..DRAW_MIN_PLY {
+            assert_eq!(adjudicator.update(Some(0), ply), None);
+        }
+        assert_eq!(adjudicator.draw_count, 0);
+
+        for ply in DRAW_MIN_PLY..DRAW_MIN_PLY + 3 {
+            assert_eq!(adjudicator.update(Some(5), ply), None);
+        }
+        assert_eq!(
+            adjudicator.update(Some(-10), DRAW_MIN_PLY + 3),
+            Some(GameResult::Draw)
+        );
+    }
+
+    #[test]
+    fn zero_scores_disable_adjudication() {
+        let mut adjudicator = Adjudicator::new(Adjudication {
+            resign_score: 0,
+            resign_moves: 1,
+            draw_score: 0,
+            draw_moves: 1,
+        });
+        for ply in DRAW_MIN_PLY..DRAW_MIN_PLY + 10 {
+            assert_eq!(adjudicator.update(Some(0), ply), None);
+            assert_eq!(adjudicator.update(Some(5000), ply), None);
+        }
+    }
+
+    #[test]
+    fn parses_time_controls() {
+        assert!(matches!(
+            parse_time_control("10+0.1"),
+            Ok(TimeControl::Clock {
+                base: 10_000,
+                inc: 100
+            })
+        ));
+        assert!(matches!(
+            parse_time_control("0.5"),
+            Ok(TimeControl::Clock { base: 500, inc: 0 })
+        ));
+        assert!(parse_time_control("10+x").is_err());
+        assert!(parse_time_control("").is_err());
+    }
+
+    #[test]
+    fn parses_match_args() {
+        let config = parse_match_args(&args(
+            "--engine2 ./other --games 20 --depth 6 --resign 800 --draw 0 --pgn out.pgn --sprt 0 10",
+        ))
+        .unwrap();
+        assert_eq!(config.engine1, "internal");
+        assert_eq!(config.engine2, "./other");
+        assert_eq!(config.openings, [START_FEN]);
+        assert_eq!(config.games, 20);
+        assert!(matches!(config.time_control, TimeControl::Depth(6)));
+        assert_eq!(config.adjudication.resign_score, 800);
+        assert_eq!(config.adjudication.draw_score, 0);
+        assert_eq!(config.pgn_path.as_deref(), Some("out.pgn"));
+        let sprt = config.sprt.unwrap();
+        assert_eq!((sprt.elo0, sprt.elo1), (0.0, 10.0));
+
+        let defaults = parse_match_args(&[]).unwrap();
+        assert!(matches!(
+            defaults.time_control,
+            TimeControl::Clock {
+                base: 10_000,
+                inc: 100
+            }
+        ));
+        assert!(defaults.sprt.is_none());
+    }
+
+    #[test]
+    fn rejects_bad_match_args() {
+        for text in [
+            "--games",
+            "--games ten",
+            "--sprt 0",
+            "--tc fast",
+            "--depth -1",
+            "--speed 2",
+        ] {
+            assert!(parse_match_args(&args(text)).is_err(), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+/// Game counts from the first engine's point of view.
+#[derive(Default, Clone, Copy)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, between 0 and 1.
+    pub fn score(&self) -> f64 {
+        (self.wins as f64 + self.draws as f64 / 2.0) / self.games().max(1) as f64
+    }
+
+    /// Variance of the result of one game.
+    fn variance(&self) -> f64 {
+        let n = self.games().max(1) as f64;
+        let s = self.score();
+        (self.wins as f64 * (1.0 - s).powi(2)
+            + self.draws as f64 * (0.5 - s).powi(2)
+            + self.losses as f64 * s.powi(2))
+            / n
+    }
+
+    /// Elo difference and the half width of its 95% confidence interval.
+    /// `None` until both a point has been won and one has been lost.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let s = self.score();
+        if s <= 0.0 || s >= 1.0 {
+            return None;
+        }
+        let margin = 1.96 * (self.variance() / self.games() as f64).sqrt();
+        let low = score_to_elo((s - margin).max(f64::EPSILON));
+        let high = score_to_elo((s + margin).min(1.0 - f64::EPSILON));
+        Some((score_to_elo(s), (high - low) / 2.0))
+    }
+}
+
+fn score_to_elo(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+fn elo_to_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SprtResult {
+    Continue,
+    AcceptH0,
+    AcceptH1,
+}
+
+/// Sequential probability ratio test of H0: elo = `elo0` against
+/// H1: elo = `elo1`, with false positive rate `alpha` and false negative rate
+/// `beta`.
+#[derive(Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log likelihood ratio, using the normal approximation of the
+    /// generalized SPRT on the trinomial game outcomes.
+    pub fn llr(&self, score: &MatchScore) -> f64 {
+        let variance = score.variance();
+        if score.games() == 0 || variance <= 0.0 {
+            return 0.0;
+        }
+        let s0 = elo_to_score(self.elo0);
+        let s1 = elo_to_score(self.elo1);
+        (s1 - s0) * (2.0 * score.score() - s0 - s1) * score.games() as f64 / (2.0 * variance)
+    }
+
+    pub fn status(&self, score: &MatchScore) -> SprtResult {
+        let llr = self.llr(score);
+        let (lower, upper) = self.bounds();
+        if llr >= upper {
+            SprtResult::AcceptH1
+        } else if llr <= lower {
+            SprtResult::AcceptH0
+        } else {
+            SprtResult::Continue
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reference values come from an independent implementation of the same
+    // formulas (the normal approximation used by cutechess, with logistic Elo).
+
+    fn score(wins: u32, draws: u32, losses: u32) -> MatchScore {
+        MatchScore {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    const SPRT: Sprt = Sprt {
+        elo0: 0.0,
+        elo1: 5.0,
+        alpha: 0.05,
+        beta: 0.05,
+    };
+
+    #[test]
+    fn bounds_follow_alpha_and_beta() {
+        let (lower, upper) = SPRT.bounds();
+        assert_close(lower, (0.05f64 / 0.95).ln());
+        assert_close(upper, 19f64.ln());
+        assert_close(upper, 2.9444389791664403);
+    }
+
+    #[test]
+    fn llr_matches_reference_values() {
+        assert_close(SPRT.llr(&score(100, 200, 80)), 0.5276093055719648);
+        assert_close(SPRT.llr(&score(80, 200, 100)), -0.6947081047657022);
+        assert_close(SPRT.llr(&score(1200, 2000, 1000)), 4.684515591449643);
+        assert_close(SPRT.llr(&score(1000, 2000, 1200)), -6.352114090956113);
+
+        assert_eq!(SPRT.llr(&MatchScore::default()), 0.0);
+        assert_eq!(SPRT.llr(&score(0, 10, 0)), 0.0);
+    }
+
+    #[test]
+    fn status_stops_at_the_bounds() {
+        assert!(SPRT.status(&score(100, 200, 80)) == SprtResult::Continue);
+        assert!(SPRT.status(&score(1200, 2000, 1000)) == SprtResult::AcceptH1);
+        assert!(SPRT.status(&score(1000, 2000, 1200)) == SprtResult::AcceptH0);
+    }
+
+    #[test]
+    fn elo_and_margin_match_reference_values() {
+        let (elo, margin) = score(60, 30, 10).elo().unwrap();
+        // A 75% score is 400 * log10(3) Elo
+        assert_close(elo, 400.0 * 3f64.log10());
+        assert_close(margin, 62.04621919346276);
+
+        let (elo, margin) = score(30, 40, 30).elo().unwrap();
+        assert_close(elo, 0.0);
+        assert_close(margin, 53.15897190457869);
+
+        // No Elo until both sides have scored
+        assert!(score(5, 0, 0).elo().is_none());
+        assert!(score(0, 0, 5).elo().is_none());
+        assert!(score(0, 3, 2).elo().is_some());
+    }
+}
@@ -1,20 +1,33 @@
 use crate::{
+    book::polyglot::PolyglotBook,
     chess::{
-        moves_gen::moves_struct::{
-            FLAG_CAPTURE, FLAG_CASTLE, FLAG_EN_PASSANT, FLAG_NORMAL, Moves, PROMOTE_BISHOP,
-            PROMOTE_KNIGHT, PROMOTE_QUEEN, PROMOTE_ROOK,
-        },
-        table::Board,
+        notation::parse_move,
+        table::{Board, Color},
+    },
+    engine::{
+        bench::{parse_bench_args, run_bench},
+        find_best_move::{Engine, MAX_DEPTH, allocate_time},
+        syzygy::tablebase,
     },
-    engine::find_best_move::Engine,
 };
-use std::io::{self, BufRead, Write};
+use std::io::{self, Write};
+
+pub const ENGINE_NAME: &str = "swag chess V1.4.2";
 
 pub struct UciEngine {
     board: Board,
     engine: Engine,
     debug: bool,
     history: Vec<u64>,
+    own_book: bool,
+    best_book_move: bool,
+    book: Option<PolyglotBook>,
+}
+
+impl Default for UciEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl UciEngine {
@@ -25,29 +38,30 @@ impl UciEngine {
             engine: Engine::new(),
             debug: false,
             history: vec![board.get_hash()],
+            own_book: false,
+            best_book_move: false,
+            book: None,
         }
     }
 
-    pub fn run(&mut self) {
-        let stdin = io::stdin();
+    /// Answers commands until `quit` or the end of `lines`.
+    pub fn run(&mut self, lines: impl Iterator<Item = String>) {
         let mut stdout = io::stdout();
 
-        for line in stdin.lock().lines() {
-            if let Ok(command) = line {
-                let trimmed = command.trim();
-                if trimmed.is_empty() {
-                    continue;
-                }
+        for command in lines {
+            let trimmed = command.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
 
-                let response = self.process_command(trimmed);
-                if let Some(resp) = response {
-                    writeln!(stdout, "{}", resp).unwrap();
-                    stdout.flush().unwrap();
-                }
+            let response = self.process_command(trimmed);
+            if let Some(resp) = response {
+                writeln!(stdout, "{}", resp).unwrap();
+                stdout.flush().unwrap();
+            }
 
-                if trimmed == "quit" {
-                    break;
-                }
+            if trimmed == "quit" {
+                break;
             }
         }
     }
@@ -71,7 +85,15 @@ impl UciEngine {
                 self.handle_position(&parts[1..]);
                 None
             }
+            "setoption" => self.handle_setoption(command),
             "go" => Some(self.handle_go(&parts[1..])),
+            "bench" => match parse_bench_args(&parts[1..]) {
+                Ok((depth, hash_mb)) => {
+                    run_bench(depth, hash_mb);
+                    None
+                }
+                Err(e) => Some(format!("info string {}", e)),
+            },
             "quit" => None,
             "debug" => {
                 if parts.len() > 1 {
@@ -85,12 +107,62 @@ impl UciEngine {
 
     fn handle_uci(&self) -> String {
         let mut response = String::new();
-        response.push_str("id name swag chess V1.4.2\n");
+        response.push_str(&format!("id name {}\n", ENGINE_NAME));
         response.push_str("id author Frigge\n");
+        response.push_str("option name SyzygyPath type string default <empty>\n");
+        response.push_str("option name SyzygyProbeLimit type spin default 7 min 0 max 7\n");
+        response.push_str("option name OwnBook type check default false\n");
+        response.push_str("option name BookFile type string default <empty>\n");
+        response.push_str("option name BestBookMove type check default false\n");
         response.push_str("uciok");
         response
     }
 
+    fn handle_setoption(&mut self, command: &str) -> Option<String> {
+        let (name, value) = parse_setoption(command)?;
+        let value = value.to_string();
+
+        match name.to_lowercase().as_str() {
+            "syzygypath" => {
+                let count = tablebase::init(&value);
+                Some(format!("info string found {} tablebases", count))
+            }
+            "syzygyprobelimit" => {
+                if let Ok(limit) = value.parse::<usize>() {
+                    tablebase::set_probe_limit(limit);
+                }
+                None
+            }
+            "ownbook" => {
+                self.own_book = value == "true";
+                None
+            }
+            "bestbookmove" => {
+                self.best_book_move = value == "true";
+                None
+            }
+            "bookfile" => {
+                if value.is_empty() || value == "<empty>" {
+                    self.book = None;
+                    return None;
+                }
+                match PolyglotBook::open(&value) {
+                    Ok(book) => {
+                        let response =
+                            format!("info string book loaded with {} entries", book.len());
+                        self.book = Some(book);
+                        Some(response)
+                    }
+                    Err(e) => {
+                        self.book = None;
+                        Some(format!("info string {}", e))
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
     fn handle_position(&mut self, args: &[&str]) {
         if args.is_empty() {
             return;
@@ -125,7 +197,7 @@ impl UciEngine {
 
         if move_index < args.len() && args[move_index] == "moves" {
             for &move_str in &args[move_index + 1..] {
-                if let Some(mv) = self.parse_move(move_str) {
+                if let Some(mv) = parse_move(&self.board, move_str) {
                     self.board.make_move_with_undo(&mv);
                     self.history.push(self.board.get_hash());
                 }
@@ -133,67 +205,22 @@ impl UciEngine {
         }
     }
 
-    fn parse_move(&self, move_str: &str) -> Option<Moves> {
-        if move_str.len() < 4 {
-            return None;
-        }
-
-        let from_str = &move_str[0..2];
-        let to_str = &move_str[2..4];
-
-        let from = algebraic_to_index(from_str)?;
-        let to = algebraic_to_index(to_str)?;
-
-        let mut promotion = 0;
-        let mut is_promotion = false;
-        let mut flags = FLAG_NORMAL;
-
-        if move_str.len() == 5 {
-            is_promotion = true;
-            promotion = match move_str.chars().nth(4)? {
-                'q' => PROMOTE_QUEEN,
-                'r' => PROMOTE_ROOK,
-                'b' => PROMOTE_BISHOP,
-                'n' => PROMOTE_KNIGHT,
-                _ => return None,
-            };
-        }
-
-        let is_king_move = self.board.king.get_value() & (1u64 << from) != 0;
-        if is_king_move {
-            let from_to_diff = (to as i8 - from as i8).abs();
-            if from_to_diff == 2 {
-                flags = FLAG_CASTLE;
-            }
-        }
-
-        if flags != FLAG_CASTLE {
-            let target_white = self.board.white.get_value() & (1u64 << to) != 0;
-            let target_black = self.board.black.get_value() & (1u64 << to) != 0;
-
-            if (self.board.is_white_turn && target_black)
-                || (!self.board.is_white_turn && target_white)
-            {
-                flags = FLAG_CAPTURE;
-            }
-
-            if self.board.enpassant.get_value() == (1u64 << to) {
-                let is_pawn = self.board.pawn.get_value() & (1u64 << from) != 0;
-                if is_pawn {
-                    flags = FLAG_EN_PASSANT;
-                }
-            }
+    fn handle_go(&mut self, args: &[&str]) -> String {
+        if self.own_book
+            && let Some(mv) = self
+                .book
+                .as_ref()
+                .and_then(|book| book.probe(&self.board, self.best_book_move))
+        {
+            return format!("bestmove {}", mv.to_string());
         }
 
-        Some(Moves::new(from, to, promotion, flags, is_promotion))
-    }
-
-    fn handle_go(&mut self, args: &[&str]) -> String {
-        let mut depth = 64;
+        let mut depth = MAX_DEPTH;
         let mut wtime: Option<u64> = None;
         let mut btime: Option<u64> = None;
         let mut winc: Option<u64> = None;
         let mut binc: Option<u64> = None;
+        let mut nodes: Option<u64> = None;
         let mut infinite = false;
 
         let mut i = 0;
@@ -249,6 +276,16 @@ impl UciEngine {
                         i += 1;
                     }
                 }
+                "nodes" => {
+                    if i + 1 < args.len() {
+                        if let Ok(n) = args[i + 1].parse::<u64>() {
+                            nodes = Some(n);
+                        }
+                        i += 2;
+                    } else {
+                        i += 1;
+                    }
+                }
                 "infinite" => {
                     infinite = true;
                     i += 1;
@@ -262,45 +299,65 @@ impl UciEngine {
         let time_limit = if infinite {
             None
         } else {
-            let (my_time, my_inc) = if self.board.is_white_turn {
+            let (my_time, my_inc) = if self.board.get_side() == Color::White {
                 (wtime, winc.unwrap_or(0))
             } else {
                 (btime, binc.unwrap_or(0))
             };
 
-            match my_time {
-                Some(t) => {
-                    let time_slot = (t as f64 / 20.0) + (my_inc as f64 / 2.0);
-
-                    let max_alloc = t.saturating_sub(100).max(50);
-
-                    Some((time_slot as u64).clamp(50, max_alloc))
-                }
-                None => None,
-            }
+            my_time.map(|t| allocate_time(t, my_inc))
         };
 
+        self.engine.node_limit = nodes;
         let best_move = self
             .engine
             .find_best_move(&self.board, depth, &self.history, time_limit);
+        self.engine.node_limit = None;
         format!("bestmove {}", best_move.to_string())
     }
 }
 
-fn algebraic_to_index(alg: &str) -> Option<u8> {
-    if alg.len() != 2 {
-        return None;
+/// Splits `setoption name <name> [value <value>]` into the name, with words
+/// joined by single spaces, and the value exactly as written, so paths keep
+/// their runs of spaces.
+fn parse_setoption(command: &str) -> Option<(String, &str)> {
+    let mut rest = command;
+    let mut name: Option<Vec<&str>> = None;
+    loop {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (word, tail) = rest.split_at(end);
+        match (word, &mut name) {
+            ("", _) => return Some((name?.join(" "), "")),
+            ("name", None) => name = Some(Vec::new()),
+            ("value", Some(words)) if !words.is_empty() => {
+                return Some((words.join(" "), tail.trim()));
+            }
+            (word, Some(words)) => words.push(word),
+            _ => {}
+        }
+        rest = tail;
     }
-    let chars: Vec<char> = alg.chars().collect();
-    let file_char = chars[0];
-    let rank_char = chars[1];
-
-    let file = file_char.to_ascii_lowercase() as u8 - b'a';
-    let rank = rank_char.to_digit(10)? as u8 - 1;
+}
 
-    if file > 7 || rank > 7 {
-        return None;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setoption_keeps_the_value_verbatim() {
+        assert_eq!(
+            parse_setoption("setoption name SyzygyPath value /tb/3  men:/tb/4 men \n"),
+            Some(("SyzygyPath".to_string(), "/tb/3  men:/tb/4 men"))
+        );
+        assert_eq!(
+            parse_setoption("setoption  name Syzygy  Probe Limit   value 5"),
+            Some(("Syzygy Probe Limit".to_string(), "5"))
+        );
+        assert_eq!(
+            parse_setoption("setoption name Clear Hash"),
+            Some(("Clear Hash".to_string(), ""))
+        );
+        assert_eq!(parse_setoption("setoption value 5"), None);
     }
-
-    Some(rank * 8 + file)
 }
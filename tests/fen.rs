@@ -0,0 +1,24 @@
+//! `Board::new_from_fen` must refuse positions the move generator cannot handle.
+
+use chessengine::Board;
+
+#[test]
+fn rejects_missing_or_extra_kings() {
+    for fen in [
+        "4k3/8/8/8/8/8/8/8 w - - 0 1",
+        "8/8/8/8/8/8/8/4K3 b - - 0 1",
+        "8/8/8/8/8/8/8/8 w - - 0 1",
+        "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+        "3kk3/8/8/8/8/8/8/4K3 w - - 0 1",
+    ] {
+        assert!(Board::new_from_fen(fen).is_err(), "{fen}");
+    }
+}
+
+#[test]
+fn accepts_one_king_per_side() {
+    let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+    let board = Board::new_from_fen(fen).unwrap();
+    assert_eq!(board.get_fen(), fen);
+    assert_eq!(board.legal_moves().len(), 5);
+}
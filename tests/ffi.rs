@@ -0,0 +1,219 @@
+//! Compiles `tests/ffi/ffi_test.c` against the static library and runs it.
+//! Needs a C compiler, taken from `CC` or `cc` on the path.
+#![cfg(feature = "ffi")]
+
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process::Command;
+
+#[test]
+fn c_program_uses_the_library() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    // The package only builds an rlib, so the static library is built here,
+    // in its own target directory to stay clear of the running build
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ffi");
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    let status = Command::new(cargo)
+        .args(["rustc", "--quiet", "--lib", "--no-default-features"])
+        .args(["--features", "ffi", "--crate-type", "staticlib"])
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(&target_dir)
+        .status()
+        .expect("cannot run cargo");
+    assert!(status.success(), "building the static library failed");
+    let library = target_dir.join("debug/libchessengine.a");
+
+    let program = target_dir.join("ffi_test");
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(compiler)
+        .arg(root.join("tests/ffi/ffi_test.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg(&library)
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .expect("cannot run the C compiler");
+    assert!(status.success(), "compiling ffi_test.c failed");
+
+    let output = Command::new(&program).output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// `include/chessengine.h` must be what cbindgen generates from `src/ffi.rs`.
+/// Skipped when cbindgen is not installed.
+#[test]
+fn header_matches_cbindgen() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let output = match Command::new("cbindgen")
+        .current_dir(&root)
+        .args(["--config", "cbindgen.toml", "--quiet"])
+        .output()
+    {
+        Ok(output) => output,
+        Err(error) if error.kind() == ErrorKind::NotFound => {
+            eprintln!("cbindgen not found, header check skipped");
+            return;
+        }
+        Err(error) => panic!("cannot run cbindgen: {error}"),
+    };
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let committed = fs::read_to_string(root.join("include/chessengine.h")).unwrap();
+    assert!(
+        String::from_utf8_lossy(&output.stdout) == committed,
+        "include/chessengine.h is out of date, regenerate it with \
+         cbindgen --config cbindgen.toml --output include/chessengine.h"
+    );
+}
+
+mod boundary {
+    use std::ffi::{CString, c_char};
+    use std::ptr;
+
+    use chessengine::ffi::*;
+
+    fn from_fen(fen: &str) -> *mut ChessBoard {
+        let fen = CString::new(fen).unwrap();
+        unsafe { chess_board_from_fen(fen.as_ptr()) }
+    }
+
+    #[test]
+    fn invalid_fens_give_null() {
+        for fen in [
+            "8/8/8/8/8/8/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/3KK3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w X - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 w - e3 0 1",
+            "P3k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K2r b - - 0 1",
+            "4k2R/8/8/8/8/8/8/4K3 w - - 0 1",
+        ] {
+            assert!(from_fen(fen).is_null(), "{fen}");
+        }
+        assert!(unsafe { chess_board_from_fen(ptr::null()) }.is_null());
+    }
+
+    #[test]
+    fn castling_rights_without_their_pieces_are_dropped() {
+        let board = from_fen("4k3/8/8/8/8/8/8/4K3 w KQkq - 0 1");
+        assert!(!board.is_null());
+        let mut buffer = [0 as c_char; 128];
+        unsafe { chess_board_fen(board, buffer.as_mut_ptr(), buffer.len()) };
+        let fen = unsafe { std::ffi::CStr::from_ptr(buffer.as_ptr()) };
+        assert_eq!(fen.to_str().unwrap(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        unsafe { chess_board_free(board) };
+    }
+
+    #[test]
+    fn move_to_san_rejects_illegal_moves() {
+        let board = chess_board_new();
+        let mut buffer = [b'x' as c_char; 16];
+        for mv in [0, 1, u32::MAX] {
+            let len =
+                unsafe { chess_board_move_to_san(board, mv, buffer.as_mut_ptr(), buffer.len()) };
+            assert_eq!(len, 0);
+            assert_eq!(buffer[0], 0);
+        }
+
+        let text = CString::new("e2e4").unwrap();
+        let mv = unsafe { chess_board_parse_move(board, text.as_ptr()) };
+        let len = unsafe { chess_board_move_to_san(board, mv, buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(len, 2);
+        unsafe { chess_board_free(board) };
+    }
+
+    #[test]
+    fn zero_hash_gives_no_engine() {
+        assert!(chess_engine_new(0).is_null());
+        let engine = chess_engine_new(1);
+        assert!(!engine.is_null());
+        unsafe { chess_engine_free(engine) };
+    }
+
+    #[test]
+    fn null_board_is_an_error() {
+        let engine = chess_engine_new(1);
+        let mut result = ChessSearchResult {
+            best_move: 0,
+            score: 0,
+            nodes: 0,
+            time_ms: 0,
+        };
+        unsafe {
+            assert_eq!(chess_board_status(ptr::null()), CHESS_ERROR);
+            assert_eq!(chess_board_legal_moves(ptr::null(), ptr::null_mut(), 0), 0);
+            assert_eq!(chess_board_make_move(ptr::null_mut(), 1), CHESS_ERROR);
+            assert_eq!(
+                chess_engine_search(engine, ptr::null(), ptr::null(), &mut result),
+                CHESS_ERROR
+            );
+            chess_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn unbounded_search_is_an_error() {
+        let engine = chess_engine_new(1);
+        let board = chess_board_new();
+        let mut result = ChessSearchResult {
+            best_move: 0,
+            score: 0,
+            nodes: 0,
+            time_ms: 0,
+        };
+        let unbounded = ChessSearchLimits {
+            depth: 0,
+            time_ms: 0,
+            nodes: 0,
+        };
+        let bounded = ChessSearchLimits {
+            depth: 1,
+            ..unbounded
+        };
+        unsafe {
+            assert_eq!(
+                chess_engine_search(engine, board, ptr::null(), &mut result),
+                CHESS_ERROR
+            );
+            assert_eq!(
+                chess_engine_search(engine, board, &unbounded, &mut result),
+                CHESS_ERROR
+            );
+            assert_eq!(result.best_move, 0);
+            assert_eq!(
+                chess_engine_search(engine, board, &bounded, &mut result),
+                CHESS_OK
+            );
+            assert_ne!(result.best_move, 0);
+            chess_board_free(board);
+            chess_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn illegal_moves_leave_the_board_unchanged() {
+        let board = chess_board_new();
+        unsafe {
+            assert_eq!(chess_board_make_move(board, 0), CHESS_ERROR);
+            assert_eq!(chess_board_make_move(board, u32::MAX), CHESS_ERROR);
+            assert_eq!(chess_board_side_to_move(board), 0);
+            assert_eq!(chess_board_legal_moves(board, ptr::null_mut(), 0), 20);
+            chess_board_free(board);
+        }
+    }
+}
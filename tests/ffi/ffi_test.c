@@ -0,0 +1,77 @@
+/* Exercises the C interface: FEN, legal moves, moves, game status and a
+ * bounded search. Exits with a non-zero status on the first failure. */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "chessengine.h"
+
+#define CHECK(condition)                                              \
+  do {                                                                \
+    if (!(condition)) {                                               \
+      fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+              #condition);                                            \
+      return 1;                                                       \
+    }                                                                 \
+  } while (0)
+
+static const char *START =
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+int main(void) {
+  char text[128];
+  uint32_t moves[256];
+
+  ChessBoard *board = chess_board_new();
+  CHECK(board != NULL);
+  CHECK(chess_board_fen(board, text, sizeof text) == strlen(START));
+  CHECK(strcmp(text, START) == 0);
+  CHECK(chess_board_legal_moves(board, moves, 256) == 20);
+  CHECK(chess_board_side_to_move(board) == 0);
+
+  /* Fool's mate, in both notations */
+  const char *game[] = {"f2f3", "e5", "g2g4", "Qh4#"};
+  for (int i = 0; i < 4; i++) {
+    uint32_t mv = chess_board_parse_move(board, game[i]);
+    CHECK(mv != 0);
+    CHECK(chess_board_make_move(board, mv) == CHESS_OK);
+  }
+  CHECK(chess_board_status(board) == 1);
+  CHECK(chess_board_legal_moves(board, NULL, 0) == 0);
+  CHECK(chess_board_parse_move(board, "e2e4") == 0);
+  CHECK(chess_board_make_move(board, 0) == CHESS_ERROR);
+  chess_board_free(board);
+
+  CHECK(chess_board_from_fen("not a position") == NULL);
+
+  board = chess_board_from_fen("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1");
+  CHECK(board != NULL);
+
+  /* Truncated output still reports the full length */
+  uint32_t mate = chess_board_parse_move(board, "Rd8");
+  CHECK(chess_board_move_to_san(board, mate, text, 3) == 4);
+  CHECK(strcmp(text, "Rd") == 0);
+
+  ChessEngine *engine = chess_engine_new(16);
+  CHECK(engine != NULL);
+  ChessSearchLimits limits = {.depth = 4, .time_ms = 0, .nodes = 0};
+  ChessSearchResult result;
+  CHECK(chess_engine_search(engine, board, &limits, &result) == CHESS_OK);
+  CHECK(result.best_move == mate);
+  CHECK(result.score > 19000);
+  CHECK(result.nodes > 0);
+
+  chess_move_to_uci(result.best_move, text, sizeof text);
+  CHECK(strcmp(text, "d1d8") == 0);
+  chess_board_move_to_san(board, result.best_move, text, sizeof text);
+  CHECK(strcmp(text, "Rd8#") == 0);
+
+  CHECK(chess_board_make_move(board, result.best_move) == CHESS_OK);
+  CHECK(chess_engine_search(engine, board, &limits, &result) == CHESS_ERROR);
+
+  chess_engine_free(engine);
+  chess_board_free(board);
+
+  printf("ffi test passed\n");
+  return 0;
+}
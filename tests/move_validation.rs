@@ -0,0 +1,98 @@
+//! Checks `Board::is_pseudo_legal`, `Board::is_legal` and `Board::gives_check`
+//! against the move generator on the positions used for perft.
+
+use chessengine::{
+    Board, FLAG_CAPTURE, FLAG_CASTLE, FLAG_EN_PASSANT, FLAG_NORMAL, Moves, PROMOTION_TYPES, Square,
+    Type,
+};
+
+const POSITIONS: [&str; 10] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+    "8/8/1k6/2b5/2pP4/8/5K2/8 b - d3 0 1",
+    "5k2/8/8/8/8/8/8/4K2R w K - 0 1",
+    "r3k3/8/8/8/8/8/8/3K4 b q - 0 1",
+];
+
+/// Every move encoding with a piece of the side to move on the from-square.
+fn candidates(b: &Board) -> Vec<Moves> {
+    let mut moves = Vec::new();
+    for from in b.get_pieces(b.get_side(), Type::Any) {
+        for to in Square::iter() {
+            for flag in [FLAG_NORMAL, FLAG_CAPTURE, FLAG_CASTLE, FLAG_EN_PASSANT] {
+                moves.push(Moves::new(from, to, flag));
+            }
+            for promotion in PROMOTION_TYPES {
+                for flag in [FLAG_NORMAL, FLAG_CAPTURE] {
+                    moves.push(Moves::new_promotion(from, to, promotion, flag));
+                }
+            }
+        }
+    }
+    moves
+}
+
+fn check_position(b: &Board, depth: u8, exhaustive: bool) {
+    let legal = b.legal_moves();
+    let side = b.get_side();
+
+    for mv in &legal {
+        assert!(b.is_pseudo_legal(mv), "{} {}", b.get_fen(), mv.to_string());
+        assert!(b.is_legal(mv), "{} {}", b.get_fen(), mv.to_string());
+
+        let mut after = *b;
+        after.make_move(mv).unwrap();
+        let checks = after.is_king_in_check(side.opposite());
+        assert_eq!(
+            b.gives_check(mv),
+            checks,
+            "{} {}",
+            b.get_fen(),
+            mv.to_string()
+        );
+    }
+
+    if exhaustive {
+        for mv in candidates(b) {
+            let accepted = b.is_pseudo_legal(&mv) && b.is_legal(&mv);
+            assert_eq!(
+                accepted,
+                legal.contains(&mv),
+                "{} {} flags {}",
+                b.get_fen(),
+                mv.to_string(),
+                mv.flags()
+            );
+        }
+    }
+
+    if depth == 0 {
+        return;
+    }
+    for mv in legal {
+        let mut after = *b;
+        after.make_move(&mv).unwrap();
+        check_position(&after, depth - 1, exhaustive);
+    }
+}
+
+#[test]
+fn generated_moves_pass_validation() {
+    for fen in POSITIONS {
+        let board = Board::new_from_fen(fen).unwrap();
+        check_position(&board, 2, false);
+    }
+}
+
+#[test]
+fn validation_accepts_exactly_the_generated_moves() {
+    for fen in POSITIONS {
+        let board = Board::new_from_fen(fen).unwrap();
+        check_position(&board, 1, true);
+    }
+}